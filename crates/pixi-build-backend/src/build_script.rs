@@ -0,0 +1,188 @@
+//! Customization of the build scripts that are generated by the backends.
+//!
+//! Backends render their build script from a Jinja template with a
+//! backend-specific context. The `build-script-template` option replaces that
+//! template with a user template that receives the same context variables.
+//! The `pre-build-script` and `post-build-script` options add snippets that
+//! run before and after the generated script.
+
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+};
+
+use miette::{Context, IntoDiagnostic};
+use minijinja::Environment;
+use serde::Serialize;
+
+use crate::generated_recipe::BackendConfig;
+
+/// The user customizations of a generated build script.
+#[derive(Debug, Default, Clone)]
+pub struct BuildScriptOptions {
+    /// The Jinja template that replaces the default template of the backend.
+    pub template: Option<PathBuf>,
+
+    /// A snippet that is executed before the generated script.
+    pub pre_build_script: Option<String>,
+
+    /// A snippet that is executed after the generated script.
+    pub post_build_script: Option<String>,
+}
+
+/// A build script that was rendered for a generated recipe.
+#[derive(Debug)]
+pub struct RenderedBuildScript {
+    /// The contents of the build script.
+    pub script: String,
+
+    /// The input globs of the files the script was rendered from, relative to
+    /// the source directory. Changes to these files require a rebuild.
+    pub input_globs: BTreeSet<String>,
+}
+
+impl BuildScriptOptions {
+    /// Reads the options from the backend configuration and renders the build
+    /// script with `render`. A relative template path is resolved against
+    /// `source_dir`, like the other paths of the backend configuration.
+    pub fn render_for_config(
+        config: &impl BackendConfig,
+        source_dir: &Path,
+        render: impl FnOnce(&Self) -> miette::Result<String>,
+    ) -> miette::Result<RenderedBuildScript> {
+        let options = Self::from_config(config, source_dir);
+        Ok(RenderedBuildScript {
+            script: render(&options)?,
+            input_globs: options.input_globs(source_dir),
+        })
+    }
+
+    /// Reads the options from the backend configuration. A relative template
+    /// path is resolved against `source_dir`.
+    pub fn from_config(config: &impl BackendConfig, source_dir: &Path) -> Self {
+        Self {
+            template: config
+                .build_script_template()
+                .map(|path| source_dir.join(path)),
+            pre_build_script: config.pre_build_script().map(str::to_string),
+            post_build_script: config.post_build_script().map(str::to_string),
+        }
+    }
+
+    /// Renders the build script from `context`, using the user template if
+    /// one is configured and `default_template` otherwise. The pre- and
+    /// post-build snippets are added around the rendered script.
+    pub fn render(
+        &self,
+        default_template: &str,
+        context: &impl Serialize,
+    ) -> miette::Result<String> {
+        let env = Environment::new();
+        let script = match &self.template {
+            Some(path) => {
+                let template = fs_err::read_to_string(path)
+                    .into_diagnostic()
+                    .context("failed to read the build script template")?;
+                env.render_str(&template, context)
+                    .into_diagnostic()
+                    .with_context(|| {
+                        format!(
+                            "failed to render the build script template '{}'",
+                            path.display()
+                        )
+                    })?
+            }
+            None => env
+                .render_str(default_template, context)
+                .into_diagnostic()
+                .context("failed to render the build script")?,
+        };
+
+        let sections = [
+            self.pre_build_script.as_deref(),
+            Some(script.as_str()),
+            self.post_build_script.as_deref(),
+        ];
+        Ok(sections
+            .into_iter()
+            .flatten()
+            .map(str::trim)
+            .filter(|section| !section.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n"))
+    }
+
+    /// Returns the input globs of the files that influence the build script,
+    /// relative to `source_dir`.
+    pub fn input_globs(&self, source_dir: &Path) -> BTreeSet<String> {
+        self.template
+            .iter()
+            .filter_map(|path| pathdiff::diff_paths(path, source_dir))
+            .filter_map(|path| path.to_str().map(|path| path.replace('\\', "/")))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct Context {
+        source_dir: String,
+    }
+
+    #[test]
+    fn test_default_template() {
+        let script = BuildScriptOptions::default()
+            .render(
+                "\nmake -C {{ source_dir }}\n",
+                &Context {
+                    source_dir: String::from("src"),
+                },
+            )
+            .unwrap();
+        assert_eq!(script, "make -C src");
+    }
+
+    #[test]
+    fn test_user_template_with_hooks() {
+        let source_dir = tempfile::tempdir().unwrap();
+        fs_err::write(
+            source_dir.path().join("build.sh.j2"),
+            "ninja -C {{ source_dir }}\n",
+        )
+        .unwrap();
+
+        let options = BuildScriptOptions {
+            template: Some(source_dir.path().join("build.sh.j2")),
+            pre_build_script: Some(String::from("echo before\n")),
+            post_build_script: Some(String::from("echo after")),
+        };
+        let script = options
+            .render(
+                "make -C {{ source_dir }}",
+                &Context {
+                    source_dir: String::from("src"),
+                },
+            )
+            .unwrap();
+        assert_eq!(script, "echo before\nninja -C src\necho after");
+        assert_eq!(
+            options.input_globs(source_dir.path()),
+            BTreeSet::from([String::from("build.sh.j2")])
+        );
+    }
+
+    #[test]
+    fn test_missing_template() {
+        let options = BuildScriptOptions {
+            template: Some(PathBuf::from("does-not-exist.j2")),
+            ..Default::default()
+        };
+        let context = Context {
+            source_dir: String::from("src"),
+        };
+        assert!(options.render("", &context).is_err());
+    }
+}
@@ -0,0 +1,416 @@
+//! Cancellation of in-flight JSON-RPC requests.
+//!
+//! Similar to the Language Server Protocol, a client can cancel a request that
+//! is still being processed by sending a `$/cancelRequest` notification with
+//! the id of that request:
+//!
+//! ```json
+//! { "jsonrpc": "2.0", "method": "$/cancelRequest", "params": { "id": 3 } }
+//! ```
+//!
+//! The future that processes the request is dropped, the processes that were
+//! spawned for the request (e.g. the build script) are killed and the request
+//! is answered with an error with code [`REQUEST_CANCELLED_CODE`].
+//!
+//! The build script is spawned by rattler-build, so the backend has no handle
+//! to its process. Instead, a request registers the directories in which it
+//! runs processes with [`register_process_directory`]. When the request is
+//! cancelled, the processes spawned by the backend whose command line refers
+//! to one of these directories are killed, together with their children.
+//! Processes of other requests are left running.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use jsonrpc_core::{
+    BoxFuture, Call, Failure, Id, MetaIoHandler, Output, Params, Response, futures::future::Either,
+    middleware::Middleware,
+};
+use serde::Deserialize;
+use tokio::sync::Notify;
+
+/// The name of the notification that cancels an in-flight request.
+pub const CANCEL_REQUEST_METHOD: &str = "$/cancelRequest";
+
+/// The error code that is returned for a request that was cancelled.
+///
+/// This is the same code that the Language Server Protocol uses.
+pub const REQUEST_CANCELLED_CODE: i64 = -32800;
+
+tokio::task_local! {
+    /// The directories in which the request that is processed by the current
+    /// task runs processes.
+    static REQUEST_DIRECTORIES: Arc<Mutex<Vec<PathBuf>>>;
+}
+
+/// Registers `directory` as a directory in which the current request runs
+/// processes, e.g. the work directory of a build. If the request is
+/// cancelled, the processes whose command line refers to this directory are
+/// killed.
+///
+/// Has no effect when called outside of a request.
+pub fn register_process_directory(directory: &Path) {
+    let _ = REQUEST_DIRECTORIES.try_with(|directories| {
+        directories
+            .lock()
+            .expect("lock is poisoned")
+            .push(directory.to_path_buf())
+    });
+}
+
+/// The parameters of the `$/cancelRequest` notification.
+#[derive(Debug, Deserialize)]
+pub struct CancelParams {
+    /// The id of the request to cancel.
+    pub id: Id,
+}
+
+/// A middleware that keeps track of all in-flight requests so they can be
+/// cancelled by their id.
+#[derive(Debug, Default, Clone)]
+pub struct CancellationMiddleware {
+    in_flight: Arc<Mutex<HashMap<Id, Arc<Notify>>>>,
+}
+
+impl CancellationMiddleware {
+    /// Cancels the in-flight request with the given id. Returns `false` if no
+    /// such request is being processed.
+    pub fn cancel(&self, id: &Id) -> bool {
+        let in_flight = self.in_flight.lock().expect("lock is poisoned");
+        match in_flight.get(id) {
+            Some(cancelled) => {
+                cancelled.notify_one();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Constructs an IO handler that uses this middleware and handles the
+    /// `$/cancelRequest` notification.
+    pub fn io_handler(&self) -> MetaIoHandler<(), Self> {
+        let middleware = self.clone();
+        let mut io = MetaIoHandler::with_middleware(self.clone());
+        io.add_notification(CANCEL_REQUEST_METHOD, move |params: Params| match params
+            .parse::<CancelParams>()
+        {
+            Ok(CancelParams { id }) => {
+                if !middleware.cancel(&id) {
+                    tracing::debug!("cannot cancel request {id:?}, it is not in flight");
+                }
+            }
+            Err(err) => tracing::warn!("invalid {CANCEL_REQUEST_METHOD} notification: {err}"),
+        });
+        io
+    }
+}
+
+impl Middleware<()> for CancellationMiddleware {
+    type Future = BoxFuture<Option<Response>>;
+    type CallFuture = BoxFuture<Option<Output>>;
+
+    fn on_call<F, X>(&self, call: Call, meta: (), next: F) -> Either<Self::CallFuture, X>
+    where
+        F: Fn(Call, ()) -> X + Send + Sync,
+        X: Future<Output = Option<Output>> + Send + 'static,
+    {
+        let Call::MethodCall(method_call) = &call else {
+            return Either::Right(next(call, meta));
+        };
+        let id = method_call.id.clone();
+        let jsonrpc = method_call.jsonrpc;
+
+        let cancelled = Arc::new(Notify::new());
+        self.in_flight
+            .lock()
+            .expect("lock is poisoned")
+            .insert(id.clone(), cancelled.clone());
+
+        let in_flight = self.in_flight.clone();
+        let directories = Arc::new(Mutex::new(Vec::new()));
+        let request = REQUEST_DIRECTORIES.scope(directories.clone(), next(call, meta));
+        Either::Left(Box::pin(async move {
+            let output = tokio::select! {
+                output = request => output,
+                _ = cancelled.notified() => {
+                    tracing::info!("request {id:?} was cancelled");
+                    // Listing and killing the processes runs external commands, so it
+                    // must not block the runtime.
+                    let directories = directories.lock().expect("lock is poisoned").clone();
+                    if let Err(err) =
+                        tokio::task::spawn_blocking(move || kill_request_processes(&directories))
+                            .await
+                    {
+                        tracing::warn!("failed to kill the processes of request {id:?}: {err}");
+                    }
+                    Some(Output::Failure(Failure {
+                        jsonrpc,
+                        error: cancelled_error(),
+                        id: id.clone(),
+                    }))
+                }
+            };
+            in_flight.lock().expect("lock is poisoned").remove(&id);
+            output
+        }))
+    }
+}
+
+/// Returns the error that is sent in response to a cancelled request.
+pub fn cancelled_error() -> jsonrpc_core::Error {
+    jsonrpc_core::Error {
+        code: jsonrpc_core::ErrorCode::ServerError(REQUEST_CANCELLED_CODE),
+        message: String::from("request was cancelled"),
+        data: None,
+    }
+}
+
+/// Kills the processes that were spawned by this process and whose command
+/// line refers to one of `directories`, including their children.
+///
+/// The build script of a package is executed by rattler-build in a child
+/// process. Dropping the future of a build does not terminate this process,
+/// so it is killed explicitly when a build is cancelled.
+///
+/// This blocks until the processes are listed and killed, so it should be
+/// called from a blocking task.
+fn kill_request_processes(directories: &[PathBuf]) {
+    if directories.is_empty() {
+        return;
+    }
+    let pids = request_processes(std::process::id(), &list_processes(), directories);
+    if pids.is_empty() {
+        return;
+    }
+    tracing::debug!("killing child processes {pids:?}");
+    if let Err(err) = kill_processes(&pids) {
+        tracing::warn!("failed to kill child processes: {err}");
+    }
+}
+
+/// A process that is running on the system.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ProcessInfo {
+    pid: u32,
+    ppid: u32,
+    command_line: String,
+}
+
+/// Returns the ids of the processes that descend from `root` and whose
+/// command line refers to one of `directories`, and of all their
+/// descendants. Children are returned before their parents.
+fn request_processes(root: u32, processes: &[ProcessInfo], directories: &[PathBuf]) -> Vec<u32> {
+    let prefixes = directories
+        .iter()
+        .map(|directory| format!("{}{}", directory.display(), std::path::MAIN_SEPARATOR))
+        .collect::<Vec<_>>();
+
+    fn collect(
+        parent: u32,
+        processes: &[ProcessInfo],
+        prefixes: &[String],
+        matched: bool,
+        result: &mut Vec<u32>,
+    ) {
+        for process in processes {
+            if process.ppid != parent || process.pid == parent {
+                continue;
+            }
+            let matched = matched
+                || prefixes
+                    .iter()
+                    .any(|prefix| process.command_line.contains(prefix.as_str()));
+            collect(process.pid, processes, prefixes, matched, result);
+            if matched {
+                result.push(process.pid);
+            }
+        }
+    }
+
+    let mut result = Vec::new();
+    collect(root, processes, &prefixes, false, &mut result);
+    result
+}
+
+/// Returns all processes that are running on the system.
+#[cfg(unix)]
+fn list_processes() -> Vec<ProcessInfo> {
+    let Ok(output) = std::process::Command::new("ps")
+        .args(["-A", "-o", "pid=,ppid=,args="])
+        .output()
+    else {
+        return Vec::new();
+    };
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(parse_process_line)
+        .collect()
+}
+
+/// Returns all processes that are running on the system.
+#[cfg(windows)]
+fn list_processes() -> Vec<ProcessInfo> {
+    let Ok(output) = std::process::Command::new("powershell")
+        .args([
+            "-NoProfile",
+            "-Command",
+            "Get-CimInstance Win32_Process | ForEach-Object { \"$($_.ProcessId) $($_.ParentProcessId) $($_.CommandLine)\" }",
+        ])
+        .output()
+    else {
+        return Vec::new();
+    };
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(parse_process_line)
+        .collect()
+}
+
+/// Parses a line with the id, the parent id and the command line of a
+/// process, separated by whitespace.
+fn parse_process_line(line: &str) -> Option<ProcessInfo> {
+    let (pid, rest) = line.trim_start().split_once(char::is_whitespace)?;
+    let (ppid, command_line) = rest
+        .trim_start()
+        .split_once(char::is_whitespace)
+        .unwrap_or((rest.trim_start(), ""));
+    Some(ProcessInfo {
+        pid: pid.parse().ok()?,
+        ppid: ppid.trim().parse().ok()?,
+        command_line: command_line.trim().to_string(),
+    })
+}
+
+#[cfg(unix)]
+fn kill_processes(pids: &[u32]) -> std::io::Result<()> {
+    std::process::Command::new("kill")
+        .arg("-KILL")
+        .args(pids.iter().map(u32::to_string))
+        .status()
+        .map(|_| ())
+}
+
+#[cfg(windows)]
+fn kill_processes(pids: &[u32]) -> std::io::Result<()> {
+    for pid in pids {
+        std::process::Command::new("taskkill")
+            .args(["/F", "/T", "/PID", &pid.to_string()])
+            .status()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{Value, json};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_cancel_in_flight_request() {
+        let middleware = CancellationMiddleware::default();
+        let mut io = middleware.io_handler();
+        io.add_method("pending", |_params: Params| {
+            std::future::pending::<jsonrpc_core::Result<Value>>()
+        });
+        let io = Arc::new(io);
+
+        let request = tokio::spawn({
+            let io = io.clone();
+            async move {
+                io.handle_request(
+                    r#"{"jsonrpc":"2.0","method":"pending","params":{},"id":7}"#,
+                    (),
+                )
+                .await
+            }
+        });
+
+        // Wait for the request to be registered before cancelling it.
+        while !middleware
+            .in_flight
+            .lock()
+            .unwrap()
+            .contains_key(&Id::Num(7))
+        {
+            tokio::task::yield_now().await;
+        }
+
+        let notification = io
+            .handle_request(
+                r#"{"jsonrpc":"2.0","method":"$/cancelRequest","params":{"id":7}}"#,
+                (),
+            )
+            .await;
+        assert_eq!(notification, None);
+
+        let response: Value = serde_json::from_str(&request.await.unwrap().unwrap()).unwrap();
+        assert_eq!(response["id"], json!(7));
+        assert_eq!(response["error"]["code"], json!(REQUEST_CANCELLED_CODE));
+        assert!(middleware.in_flight.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_completed_request_is_not_tracked() {
+        let middleware = CancellationMiddleware::default();
+        let mut io = middleware.io_handler();
+        io.add_method("ping", |_params: Params| async { Ok(json!("pong")) });
+
+        let response = io
+            .handle_request(r#"{"jsonrpc":"2.0","method":"ping","id":1}"#, ())
+            .await
+            .unwrap();
+        assert!(response.contains("pong"));
+        assert!(!middleware.cancel(&Id::Num(1)));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_request_processes_only_selects_registered_directories() {
+        let process = |pid, ppid, command_line: &str| ProcessInfo {
+            pid,
+            ppid,
+            command_line: command_line.to_string(),
+        };
+        let processes = [
+            process(1, 0, "init"),
+            process(10, 1, "pixi-build-cmake"),
+            process(11, 10, "bash -e /builds/a/work/conda_build.sh"),
+            process(12, 11, "cmake --build ."),
+            process(13, 12, "c++ -c main.cpp"),
+            process(21, 10, "bash -e /builds/ab/work/conda_build.sh"),
+            process(22, 21, "cmake --build ."),
+            process(31, 1, "bash -e /builds/a/work/conda_build.sh"),
+        ];
+
+        let pids = request_processes(10, &processes, &[PathBuf::from("/builds/a")]);
+        assert_eq!(pids, vec![13, 12, 11]);
+
+        let pids = request_processes(10, &processes, &[PathBuf::from("/builds/ab")]);
+        assert_eq!(pids, vec![22, 21]);
+    }
+
+    #[test]
+    fn test_parse_process_line() {
+        assert_eq!(
+            parse_process_line("  42     7 bash -e /builds/a/work/conda_build.sh"),
+            Some(ProcessInfo {
+                pid: 42,
+                ppid: 7,
+                command_line: "bash -e /builds/a/work/conda_build.sh".to_string(),
+            })
+        );
+        assert_eq!(
+            parse_process_line("42 7"),
+            Some(ProcessInfo {
+                pid: 42,
+                ppid: 7,
+                command_line: String::new(),
+            })
+        );
+        assert_eq!(parse_process_line("PID PPID COMMAND"), None);
+    }
+}
@@ -1,14 +1,44 @@
-use clap::{Parser, Subcommand};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use clap::{Args, Parser, Subcommand};
 use clap_verbosity_flag::{InfoLevel, Verbosity};
-use miette::IntoDiagnostic;
+use itertools::Itertools;
+use miette::{Context, IntoDiagnostic};
 use pixi_build_types::{
-    BackendCapabilities, FrontendCapabilities,
-    procedures::negotiate_capabilities::NegotiateCapabilitiesParams,
+    BackendCapabilities, BinaryPackageSpecV1, FrontendCapabilities, NamedSpecV1, PackageSpecV1,
+    ProjectModelV1, VariantValue, VersionedProjectModel,
+    procedures::{
+        conda_build_v1::{
+            CondaBuildV1Dependency, CondaBuildV1DependencyRunExportSource,
+            CondaBuildV1DependencySource, CondaBuildV1Output, CondaBuildV1Params,
+            CondaBuildV1Prefix, CondaBuildV1PrefixPackage, CondaBuildV1RunExports,
+        },
+        conda_outputs::{CondaOutput, CondaOutputRunExports, CondaOutputsParams},
+        initialize::InitializeParams,
+        negotiate_capabilities::NegotiateCapabilitiesParams,
+    },
 };
 use rattler_build::console_utils::{LoggingOutputHandler, get_default_env_filter};
+use rattler_conda_types::{
+    ChannelUrl, MatchSpec, PackageName, PackageNameMatcher, ParseStrictness, Platform,
+    PrefixRecord,
+    package::{PackageFile, RunExportsJson},
+};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use url::Url;
 
-use crate::{protocol::ProtocolInstantiator, server::Server};
+use crate::{
+    generated_recipe::GeneratedRecipe,
+    notifications::BuildNotificationLayer,
+    protocol::{GenerateRecipeParams, Protocol, ProtocolInstantiator},
+    server::Server,
+    specs_conversion::{binary_package_spec_to_match_spec, package_spec_to_match_spec},
+    transcript::RECORD_ENV_VAR,
+};
 
 #[allow(missing_docs)]
 #[derive(Parser)]
@@ -22,6 +52,21 @@ pub struct App {
     #[clap(long)]
     http_port: Option<u16>,
 
+    /// The port to accept WebSocket connections on.
+    #[clap(long, conflicts_with = "http_port")]
+    websocket_port: Option<u16>,
+
+    /// The path of a Unix domain socket to accept connections on.
+    #[cfg(unix)]
+    #[clap(long, conflicts_with_all = ["http_port", "websocket_port"])]
+    unix_socket: Option<PathBuf>,
+
+    /// Record every request and response to a transcript in the `debug`
+    /// directory of the work directory, which can be replayed with the
+    /// `replay` subcommand.
+    #[clap(long, env = RECORD_ENV_VAR)]
+    record: bool,
+
     /// Enable verbose logging.
     #[command(flatten)]
     verbose: Verbosity<InfoLevel>,
@@ -31,12 +76,99 @@ pub struct App {
 pub enum Commands {
     /// Get the capabilities of the backend.
     Capabilities,
+
+    /// Print the recipe that the backend generates for a package.
+    GenerateRecipe(GenerateRecipeArgs),
+
+    /// Build a package without a pixi frontend, using prefixes that already
+    /// contain the build and host dependencies.
+    Build(BuildArgs),
+
+    /// Re-execute the requests of a recorded transcript against this backend.
+    Replay {
+        /// The transcript to replay, e.g. `debug/transcript.json` in the work
+        /// directory of a build.
+        transcript: PathBuf,
+    },
+}
+
+/// The arguments that describe the package to work on.
+#[derive(Args, Debug)]
+pub struct PackageArgs {
+    /// The path to the manifest of the package.
+    #[clap(long)]
+    manifest_path: PathBuf,
+
+    /// A JSON file that contains the project model of the package, e.g. the
+    /// `project_model.json` in the debug directory of a build.
+    #[clap(long)]
+    project_model: PathBuf,
+
+    /// A TOML or JSON file that contains the backend configuration.
+    #[clap(long)]
+    config: Option<PathBuf>,
+
+    /// The platform to build the package for.
+    #[clap(long, default_value_t = Platform::current())]
+    host_platform: Platform,
+
+    /// Override the value of a variant, e.g. `--variant python=3.12`. Can be
+    /// passed multiple times, also for the same variant.
+    #[clap(long = "variant", value_name = "KEY=VALUE", value_parser = parse_variant)]
+    variants: Vec<(String, String)>,
+}
+
+#[derive(Args, Debug)]
+pub struct GenerateRecipeArgs {
+    #[command(flatten)]
+    package: PackageArgs,
+}
+
+#[derive(Args, Debug)]
+pub struct BuildArgs {
+    #[command(flatten)]
+    package: PackageArgs,
+
+    /// The prefix that contains the build dependencies.
+    #[clap(long)]
+    build_prefix: PathBuf,
+
+    /// The prefix that contains the host dependencies.
+    #[clap(long)]
+    host_prefix: PathBuf,
+
+    /// The directory to write the built packages to.
+    #[clap(long)]
+    output_dir: PathBuf,
+
+    /// The directory in which the packages are built. Defaults to a `work`
+    /// directory inside the output directory.
+    #[clap(long)]
+    work_dir: Option<PathBuf>,
+
+    /// The name of the output to build. Required if the package has more than
+    /// one output.
+    #[clap(long, value_name = "NAME")]
+    output: Option<String>,
+
+    /// The channels that the dependencies of the package come from.
+    #[clap(long = "channel", short = 'c')]
+    channels: Vec<Url>,
 }
 
-/// Run the sever on the specified port or over stdin/stdout.
-async fn run_server<T: ProtocolInstantiator>(port: Option<u16>, protocol: T) -> miette::Result<()> {
-    let server = Server::new(protocol);
-    if let Some(port) = port {
+/// Run the sever over the transport selected by the command line arguments,
+/// or over stdin/stdout if none was selected.
+async fn run_server<T: ProtocolInstantiator>(args: &App, protocol: T) -> miette::Result<()> {
+    let server = Server::new(protocol).with_recording(args.record);
+
+    #[cfg(unix)]
+    if let Some(path) = &args.unix_socket {
+        return server.run_over_unix_socket(path).await;
+    }
+
+    if let Some(port) = args.websocket_port {
+        server.run_over_websocket(port).await
+    } else if let Some(port) = args.http_port {
         server.run_over_http(port)
     } else {
         // running over stdin/stdout
@@ -55,12 +187,15 @@ pub(crate) async fn main_impl<T: ProtocolInstantiator, F: FnOnce(LoggingOutputHa
     let registry = tracing_subscriber::registry()
         .with(get_default_env_filter(args.verbose.log_level_filter()).into_diagnostic()?);
 
-    registry.with(log_handler.clone()).init();
+    registry
+        .with(log_handler.clone())
+        .with(BuildNotificationLayer)
+        .init();
 
     let factory = factory(log_handler);
 
     match args.command {
-        None => run_server(args.http_port, factory).await,
+        None => run_server(&args, factory).await,
         Some(Commands::Capabilities) => {
             let backend_capabilities = capabilities::<T>().await?;
             eprintln!(
@@ -79,7 +214,416 @@ pub(crate) async fn main_impl<T: ProtocolInstantiator, F: FnOnce(LoggingOutputHa
             );
             Ok(())
         }
+        Some(Commands::GenerateRecipe(args)) => {
+            let generated_recipe = factory
+                .generate_recipe(GenerateRecipeParams {
+                    initialize: args.package.initialize_params()?,
+                    host_platform: args.package.host_platform,
+                    variant_configuration: args.package.variant_configuration(),
+                })
+                .await?;
+            print_generated_recipe(&generated_recipe)
+        }
+        Some(Commands::Build(args)) => build(&factory, args).await,
+        Some(Commands::Replay { transcript }) => {
+            let replayed = Server::new(factory).replay(&transcript).await?;
+            for entry in &replayed {
+                eprintln!("{}: {}", entry.method, entry.outcome);
+            }
+            let failed = replayed
+                .iter()
+                .filter(|entry| !entry.outcome.matches_recording())
+                .count();
+            if failed > 0 {
+                miette::bail!("{failed} request(s) did not behave like when they were recorded");
+            }
+            Ok(())
+        }
+    }
+}
+
+impl PackageArgs {
+    fn initialize_params(&self) -> miette::Result<InitializeParams> {
+        let project_model = fs_err::read_to_string(&self.project_model).into_diagnostic()?;
+        // Accept both a versioned project model and a bare v1 model, which is
+        // what is written to the debug directory.
+        let project_model = match serde_json::from_str::<VersionedProjectModel>(&project_model) {
+            Ok(project_model) => project_model,
+            Err(_) => serde_json::from_str::<ProjectModelV1>(&project_model)
+                .into_diagnostic()
+                .with_context(|| {
+                    format!(
+                        "failed to parse the project model in '{}'",
+                        self.project_model.display()
+                    )
+                })?
+                .into(),
+        };
+
+        let configuration = self.config.as_deref().map(read_config).transpose()?;
+
+        Ok(InitializeParams {
+            workspace_root: None,
+            source_dir: None,
+            manifest_path: self.manifest_path.clone(),
+            project_model: Some(project_model),
+            configuration,
+            target_configuration: None,
+            cache_directory: None,
+        })
+    }
+
+    fn variant_configuration(&self) -> Option<BTreeMap<String, Vec<VariantValue>>> {
+        let mut variant_configuration = BTreeMap::<String, Vec<VariantValue>>::new();
+        for (key, value) in &self.variants {
+            variant_configuration
+                .entry(key.clone())
+                .or_default()
+                .push(VariantValue::String(value.clone()));
+        }
+        (!variant_configuration.is_empty()).then_some(variant_configuration)
+    }
+}
+
+/// Builds an output of a package the same way the frontend would: the backend
+/// is initialized, the outputs are determined with `conda/outputs` and the
+/// selected output is built with `conda/build_v1`.
+///
+/// The build and host prefixes are resolved for a single variant of a single
+/// output, so exactly one output has to match the arguments.
+async fn build<T: ProtocolInstantiator>(factory: &T, args: BuildArgs) -> miette::Result<()> {
+    let host_platform = args.package.host_platform;
+    let build_platform = Platform::current();
+    let work_dir = args
+        .work_dir
+        .clone()
+        .unwrap_or_else(|| args.output_dir.join("work"));
+    let channels = args
+        .channels
+        .into_iter()
+        .map(ChannelUrl::from)
+        .collect::<Vec<_>>();
+
+    let (protocol, _) = factory
+        .initialize(args.package.initialize_params()?)
+        .await?;
+
+    let outputs = protocol
+        .conda_outputs(CondaOutputsParams {
+            channels: channels.clone(),
+            host_platform,
+            build_platform,
+            variant_configuration: args.package.variant_configuration(),
+            variant_files: None,
+            work_directory: work_dir.clone(),
+        })
+        .await?
+        .outputs
+        .into_iter()
+        .filter(|output| {
+            args.output
+                .as_ref()
+                .is_none_or(|name| name == output.metadata.name.as_normalized())
+        })
+        .collect::<Vec<_>>();
+    let [output]: [CondaOutput; 1] = outputs.try_into().map_err(|outputs: Vec<CondaOutput>| {
+        if outputs.is_empty() {
+            return miette::miette!("the package does not have any of the requested outputs");
+        }
+        let candidates = outputs
+            .iter()
+            .map(|output| {
+                format!(
+                    "  {}-{}-{}",
+                    output.metadata.name.as_normalized(),
+                    output.metadata.version,
+                    output.metadata.build
+                )
+            })
+            .join("\n");
+        miette::miette!(
+            help = "select a single output with `--output` and a single variant with `--variant`",
+            "the prefixes can only be used to build one output, but {} match:\n{candidates}",
+            outputs.len()
+        )
+    })?;
+
+    let build_records = prefix_records(&args.build_prefix)?;
+    let host_records = prefix_records(&args.host_prefix)?;
+    let (run_dependencies, run_constraints) =
+        run_requirements(&output, &build_records, &host_records)?;
+
+    let metadata = output.metadata;
+    let result = protocol
+        .conda_build_v1(CondaBuildV1Params {
+            channels,
+            build_prefix: Some(prefix(&args.build_prefix, build_platform, build_records)),
+            host_prefix: Some(prefix(&args.host_prefix, host_platform, host_records)),
+            run_constraints: Some(run_constraints),
+            run_dependencies: Some(run_dependencies),
+            run_exports: Some(run_exports(output.run_exports)),
+            work_directory: work_dir.join(format!(
+                "{}-{}",
+                metadata.name.as_normalized(),
+                metadata.build
+            )),
+            output: CondaBuildV1Output {
+                name: metadata.name,
+                version: Some(metadata.version),
+                build: Some(metadata.build),
+                subdir: metadata.subdir,
+                variant: metadata.variant,
+            },
+            output_directory: Some(args.output_dir.clone()),
+            editable: None,
+        })
+        .await?;
+    println!("{}", result.output_file.display());
+
+    Ok(())
+}
+
+/// Reads the records of the packages that are installed in a prefix.
+fn prefix_records(path: &Path) -> miette::Result<Vec<PrefixRecord>> {
+    let mut records = Vec::new();
+    let conda_meta = path.join("conda-meta");
+    if conda_meta.is_dir() {
+        for entry in fs_err::read_dir(&conda_meta).into_diagnostic()? {
+            let path = entry.into_diagnostic()?.path();
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            let record = PrefixRecord::from_path(&path)
+                .into_diagnostic()
+                .with_context(|| format!("failed to read '{}'", path.display()))?;
+            records.push(record);
+        }
+    }
+    Ok(records)
+}
+
+/// Describes an existing prefix, including the packages that are installed in
+/// it.
+fn prefix(path: &Path, platform: Platform, records: Vec<PrefixRecord>) -> CondaBuildV1Prefix {
+    CondaBuildV1Prefix {
+        prefix: path.to_path_buf(),
+        platform,
+        virtual_packages: None,
+        dependencies: Vec::new(),
+        packages: records
+            .into_iter()
+            .map(|record| CondaBuildV1PrefixPackage {
+                repodata_record: record.repodata_record,
+            })
+            .collect(),
+    }
+}
+
+/// Determines the run dependencies and run constraints of an output: the run
+/// requirements of its recipe, and the run exports of the packages in the
+/// build and host prefixes, the same way the frontend adds them.
+fn run_requirements(
+    output: &CondaOutput,
+    build_records: &[PrefixRecord],
+    host_records: &[PrefixRecord],
+) -> miette::Result<(Vec<CondaBuildV1Dependency>, Vec<CondaBuildV1Dependency>)> {
+    let mut depends = output
+        .run_dependencies
+        .depends
+        .iter()
+        .map(|dependency| CondaBuildV1Dependency {
+            spec: package_spec_to_match_spec(
+                PackageName::new_unchecked(&dependency.name),
+                dependency.spec.clone(),
+            ),
+            source: None,
+        })
+        .collect::<Vec<_>>();
+    let mut constraints = output
+        .run_dependencies
+        .constraints
+        .iter()
+        .map(|constraint| CondaBuildV1Dependency {
+            spec: binary_package_spec_to_match_spec(
+                PackageName::new_unchecked(&constraint.name),
+                constraint.spec.clone(),
+            ),
+            source: None,
+        })
+        .collect::<Vec<_>>();
+
+    let noarch = !output.metadata.noarch.is_none();
+    for (from, records) in [("build", build_records), ("host", host_records)] {
+        for record in records {
+            let package_name = &record.repodata_record.package_record.name;
+            if output
+                .ignore_run_exports
+                .from_package
+                .iter()
+                .any(|name| name == package_name)
+            {
+                continue;
+            }
+            let Some(package_run_exports) = package_run_exports(record)? else {
+                continue;
+            };
+
+            // Packages in the build prefix only contribute their strong run
+            // exports, and noarch outputs only the noarch run exports.
+            let (run_depends, run_constrains) = match (from, noarch) {
+                ("host", true) => (package_run_exports.noarch, Vec::new()),
+                (_, true) => (Vec::new(), Vec::new()),
+                ("build", false) => (
+                    package_run_exports.strong,
+                    package_run_exports.strong_constrains,
+                ),
+                _ => (
+                    [package_run_exports.weak, package_run_exports.strong].concat(),
+                    [
+                        package_run_exports.weak_constrains,
+                        package_run_exports.strong_constrains,
+                    ]
+                    .concat(),
+                ),
+            };
+
+            for (specs, target) in [
+                (run_depends, &mut depends),
+                (run_constrains, &mut constraints),
+            ] {
+                for spec in specs {
+                    let spec = MatchSpec::from_str(&spec, ParseStrictness::Lenient)
+                        .into_diagnostic()
+                        .with_context(|| {
+                            format!(
+                                "failed to parse the run export '{spec}' of '{}'",
+                                package_name.as_source()
+                            )
+                        })?;
+                    let ignored = match &spec.name {
+                        Some(PackageNameMatcher::Exact(name)) => output
+                            .ignore_run_exports
+                            .by_name
+                            .iter()
+                            .any(|ignored| ignored == name),
+                        _ => false,
+                    };
+                    if ignored {
+                        continue;
+                    }
+                    target.push(CondaBuildV1Dependency {
+                        spec,
+                        source: Some(CondaBuildV1DependencySource::RunExport(
+                            CondaBuildV1DependencyRunExportSource {
+                                from: from.to_string(),
+                                package_name: package_name.clone(),
+                            },
+                        )),
+                    });
+                }
+            }
+        }
+    }
+
+    Ok((depends, constraints))
+}
+
+/// Reads the run exports of an installed package from the directory it was
+/// extracted to. Returns `None` if the package does not have any.
+fn package_run_exports(record: &PrefixRecord) -> miette::Result<Option<RunExportsJson>> {
+    let Some(package_dir) = &record.extracted_package_dir else {
+        return Ok(None);
+    };
+    if !package_dir.join(RunExportsJson::package_path()).is_file() {
+        return Ok(None);
+    }
+    RunExportsJson::from_package_directory(package_dir)
+        .into_diagnostic()
+        .with_context(|| {
+            format!(
+                "failed to read the run exports of '{}'",
+                package_dir.display()
+            )
+        })
+        .map(Some)
+}
+
+/// Converts the run exports of an output, which are written to the package
+/// that is built.
+fn run_exports(run_exports: CondaOutputRunExports) -> CondaBuildV1RunExports {
+    let depends = |specs: Vec<NamedSpecV1<PackageSpecV1>>| {
+        specs
+            .into_iter()
+            .map(|dependency| CondaBuildV1Dependency {
+                spec: package_spec_to_match_spec(
+                    PackageName::new_unchecked(dependency.name),
+                    dependency.spec,
+                ),
+                source: None,
+            })
+            .collect()
+    };
+    let constrains = |specs: Vec<NamedSpecV1<BinaryPackageSpecV1>>| {
+        specs
+            .into_iter()
+            .map(|constraint| CondaBuildV1Dependency {
+                spec: binary_package_spec_to_match_spec(
+                    PackageName::new_unchecked(constraint.name),
+                    constraint.spec,
+                ),
+                source: None,
+            })
+            .collect()
+    };
+    CondaBuildV1RunExports {
+        weak: depends(run_exports.weak),
+        strong: depends(run_exports.strong),
+        noarch: depends(run_exports.noarch),
+        weak_constrains: constrains(run_exports.weak_constrains),
+        strong_constrains: constrains(run_exports.strong_constrains),
+    }
+}
+
+/// Reads a backend configuration from a TOML or JSON file, depending on its
+/// extension.
+fn read_config(path: &Path) -> miette::Result<serde_json::Value> {
+    let contents = fs_err::read_to_string(path).into_diagnostic()?;
+    let config = if path.extension().is_some_and(|ext| ext == "toml") {
+        toml::from_str(&contents).into_diagnostic()
+    } else {
+        serde_json::from_str(&contents).into_diagnostic()
+    };
+    config.with_context(|| format!("failed to parse the configuration in '{}'", path.display()))
+}
+
+/// Parses a `KEY=VALUE` variant override.
+fn parse_variant(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+        _ => Err(format!("expected KEY=VALUE, got '{s}'")),
+    }
+}
+
+/// Prints the recipe to stdout, and the input globs to stderr.
+fn print_generated_recipe(generated_recipe: &GeneratedRecipe) -> miette::Result<()> {
+    print!(
+        "{}",
+        generated_recipe.recipe.to_yaml_pretty().into_diagnostic()?
+    );
+
+    for (title, globs) in [
+        (
+            "Metadata input globs",
+            &generated_recipe.metadata_input_globs,
+        ),
+        ("Build input globs", &generated_recipe.build_input_globs),
+    ] {
+        eprintln!("{title}:");
+        for glob in globs {
+            eprintln!("  {glob}");
+        }
     }
+    Ok(())
 }
 
 /// The entry point for the CLI which should be called from the backends implementation.
@@ -108,3 +652,53 @@ async fn capabilities<Factory: ProtocolInstantiator>() -> miette::Result<Backend
 
     Ok(result.capabilities)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_variant() {
+        assert_eq!(
+            parse_variant("python=3.12").unwrap(),
+            (String::from("python"), String::from("3.12"))
+        );
+        assert!(parse_variant("python").is_err());
+        assert!(parse_variant("=3.12").is_err());
+    }
+
+    #[test]
+    fn test_parse_build_command() {
+        let app = App::try_parse_from([
+            "backend",
+            "build",
+            "--manifest-path",
+            "pixi.toml",
+            "--project-model",
+            "project_model.json",
+            "--build-prefix",
+            "build",
+            "--host-prefix",
+            "host",
+            "--output-dir",
+            "output",
+            "--variant",
+            "python=3.12",
+            "--variant",
+            "python=3.13",
+        ])
+        .unwrap();
+
+        let Some(Commands::Build(args)) = app.command else {
+            panic!("expected the build command");
+        };
+        assert_eq!(args.package.host_platform, Platform::current());
+        assert_eq!(
+            args.package.variant_configuration().unwrap()["python"],
+            vec![
+                VariantValue::String(String::from("3.12")),
+                VariantValue::String(String::from("3.13")),
+            ]
+        );
+    }
+}
@@ -18,6 +18,32 @@ pub fn build_configuration(
     variant: BTreeMap<NormalizedKey, Variable>,
     directories: Directories,
 ) -> miette::Result<BuildConfigurationParams> {
+    let (build_platform, host_platform) =
+        platforms_with_virtual_packages(build_platform, host_platform)?;
+
+    let channels = channels.into_iter().map(Into::into).collect();
+
+    let params = BuildConfigurationParams {
+        channels,
+        build_platform,
+        host_platform,
+        variant,
+        directories,
+    };
+
+    Ok(params)
+}
+
+/// Returns the build and host [`PlatformWithVirtualPackages`] for the given
+/// platforms.
+///
+/// If either platform is missing, the current platform and its virtual
+/// packages are detected, honoring the `CONDA_OVERRIDE_*` environment
+/// variables, and used instead.
+pub fn platforms_with_virtual_packages(
+    build_platform: Option<PlatformAndVirtualPackages>,
+    host_platform: Option<PlatformAndVirtualPackages>,
+) -> miette::Result<(PlatformWithVirtualPackages, PlatformWithVirtualPackages)> {
     let build_platform = build_platform.map(|p| PlatformWithVirtualPackages {
         platform: p.platform,
         virtual_packages: p.virtual_packages.unwrap_or_default(),
@@ -28,31 +54,19 @@ pub fn build_configuration(
         virtual_packages: p.virtual_packages.unwrap_or_default(),
     });
 
-    let (build_platform, host_platform) = match (build_platform, host_platform) {
-        (Some(build_platform), Some(host_platform)) => (build_platform, host_platform),
+    match (build_platform, host_platform) {
+        (Some(build_platform), Some(host_platform)) => Ok((build_platform, host_platform)),
         (build_platform, host_platform) => {
             let current_platform = rattler_build::metadata::PlatformWithVirtualPackages::detect(
                 &VirtualPackageOverrides::from_env(),
             )
             .into_diagnostic()?;
-            (
+            Ok((
                 build_platform.unwrap_or_else(|| current_platform.clone()),
                 host_platform.unwrap_or(current_platform),
-            )
+            ))
         }
-    };
-
-    let channels = channels.into_iter().map(Into::into).collect();
-
-    let params = BuildConfigurationParams {
-        channels,
-        build_platform,
-        host_platform,
-        variant,
-        directories,
-    };
-
-    Ok(params)
+    }
 }
 
 /// The parameters used to construct a BuildConfiguration
@@ -64,3 +78,49 @@ pub struct BuildConfigurationParams {
     pub variant: BTreeMap<NormalizedKey, Variable>,
     pub directories: Directories,
 }
+
+#[cfg(test)]
+mod tests {
+    use rattler_conda_types::{GenericVirtualPackage, Platform};
+
+    use super::*;
+
+    #[test]
+    fn test_explicit_virtual_packages_are_used() {
+        let cuda = GenericVirtualPackage {
+            name: "__cuda".parse().unwrap(),
+            version: "12.4".parse().unwrap(),
+            build_string: "0".to_string(),
+        };
+
+        let (build_platform, host_platform) = platforms_with_virtual_packages(
+            Some(PlatformAndVirtualPackages {
+                platform: Platform::Linux64,
+                virtual_packages: None,
+            }),
+            Some(PlatformAndVirtualPackages {
+                platform: Platform::Linux64,
+                virtual_packages: Some(vec![cuda.clone()]),
+            }),
+        )
+        .unwrap();
+        assert_eq!(build_platform.platform, Platform::Linux64);
+        assert!(build_platform.virtual_packages.is_empty());
+        assert_eq!(host_platform.platform, Platform::Linux64);
+        assert_eq!(host_platform.virtual_packages, vec![cuda]);
+    }
+
+    #[test]
+    fn test_missing_platforms_are_detected() {
+        let (build_platform, host_platform) = platforms_with_virtual_packages(
+            None,
+            Some(PlatformAndVirtualPackages {
+                platform: Platform::Linux64,
+                virtual_packages: None,
+            }),
+        )
+        .unwrap();
+        assert_eq!(build_platform.platform, Platform::current());
+        assert_eq!(host_platform.platform, Platform::Linux64);
+    }
+}
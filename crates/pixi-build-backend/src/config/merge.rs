@@ -0,0 +1,190 @@
+//! Strategies to merge a configuration value with its target-specific value.
+//!
+//! Every strategy has the same signature: it receives the name of the field,
+//! the base value and the target-specific value, and returns the merged
+//! value. Use [`crate::merge_target_config`] to apply a strategy to every
+//! field of a configuration.
+
+use std::collections::BTreeMap;
+
+use indexmap::IndexMap;
+
+/// A configuration value that may be left unset.
+pub trait MergeValue: Clone {
+    /// Returns true if the value was set in the configuration.
+    fn is_set(&self) -> bool;
+}
+
+impl<T: Clone> MergeValue for Option<T> {
+    fn is_set(&self) -> bool {
+        self.is_some()
+    }
+}
+
+impl<T: Clone> MergeValue for Vec<T> {
+    fn is_set(&self) -> bool {
+        !self.is_empty()
+    }
+}
+
+impl<K: Clone, V: Clone> MergeValue for IndexMap<K, V> {
+    fn is_set(&self) -> bool {
+        !self.is_empty()
+    }
+}
+
+impl<K: Clone, V: Clone> MergeValue for BTreeMap<K, V> {
+    fn is_set(&self) -> bool {
+        !self.is_empty()
+    }
+}
+
+/// A configuration value that is merged with its target-specific value
+/// instead of being replaced by it.
+pub trait DeepMerge: Sized {
+    /// Merges `target` onto `self`, values from `target` take precedence.
+    fn deep_merge(&self, target: &Self) -> miette::Result<Self>;
+}
+
+impl<K: Clone + std::hash::Hash + Eq, V: Clone> DeepMerge for IndexMap<K, V> {
+    fn deep_merge(&self, target: &Self) -> miette::Result<Self> {
+        let mut merged = self.clone();
+        merged.extend(target.iter().map(|(k, v)| (k.clone(), v.clone())));
+        Ok(merged)
+    }
+}
+
+impl<K: Clone + Ord, V: Clone> DeepMerge for BTreeMap<K, V> {
+    fn deep_merge(&self, target: &Self) -> miette::Result<Self> {
+        let mut merged = self.clone();
+        merged.extend(target.iter().map(|(k, v)| (k.clone(), v.clone())));
+        Ok(merged)
+    }
+}
+
+impl<T: DeepMerge + Clone> DeepMerge for Option<T> {
+    fn deep_merge(&self, target: &Self) -> miette::Result<Self> {
+        match (self, target) {
+            (Some(base), Some(target)) => base.deep_merge(target).map(Some),
+            (base, target) => Ok(target.clone().or_else(|| base.clone())),
+        }
+    }
+}
+
+/// The target-specific value replaces the base value if it is set.
+pub fn replace<T: MergeValue>(_field: &str, base: &T, target: &T) -> miette::Result<T> {
+    Ok(if target.is_set() {
+        target.clone()
+    } else {
+        base.clone()
+    })
+}
+
+/// The target-specific values are appended to the base values.
+pub fn append<T>(_field: &str, base: &T, target: &T) -> miette::Result<T>
+where
+    T: Clone + IntoIterator + Extend<T::Item>,
+{
+    let mut merged = base.clone();
+    merged.extend(target.clone());
+    Ok(merged)
+}
+
+/// The target-specific value is merged onto the base value, see
+/// [`DeepMerge`].
+pub fn deep_merge<T: DeepMerge>(_field: &str, base: &T, target: &T) -> miette::Result<T> {
+    base.deep_merge(target)
+}
+
+/// The value cannot be set for a specific target, the base value is used.
+pub fn base_only<T: MergeValue>(field: &str, base: &T, target: &T) -> miette::Result<T> {
+    if target.is_set() {
+        miette::bail!("`{field}` cannot have a target specific value");
+    }
+    Ok(base.clone())
+}
+
+/// Merges a configuration with its target-specific configuration by applying
+/// a strategy to every field.
+///
+/// The macro expands to a struct expression that lists every field, so a field
+/// that is added to the configuration but not to the merge fails to compile.
+///
+/// ```
+/// use pixi_build_backend::config::merge;
+///
+/// #[derive(Clone, Default)]
+/// struct Config {
+///     env: indexmap::IndexMap<String, String>,
+///     extra_args: Vec<String>,
+///     debug_dir: Option<std::path::PathBuf>,
+/// }
+///
+/// impl Config {
+///     fn merge(&self, target: &Self) -> miette::Result<Self> {
+///         Ok(pixi_build_backend::merge_target_config!(self, target, Self {
+///             env: merge::deep_merge,
+///             extra_args: merge::append,
+///             debug_dir: merge::base_only,
+///         }))
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! merge_target_config {
+    ($base:expr, $target:expr, $ty:path { $($field:ident: $strategy:path),* $(,)? }) => {
+        $ty {
+            $($field: $strategy(stringify!($field), &$base.$field, &$target.$field)?,)*
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replace() {
+        let base = vec![1, 2];
+        assert_eq!(replace("field", &base, &vec![3]).unwrap(), vec![3]);
+        assert_eq!(replace("field", &base, &vec![]).unwrap(), vec![1, 2]);
+        assert_eq!(replace("field", &Some(1), &None).unwrap(), Some(1));
+        assert_eq!(replace("field", &Some(1), &Some(2)).unwrap(), Some(2));
+    }
+
+    #[test]
+    fn test_append() {
+        assert_eq!(
+            append("field", &vec![1, 2], &vec![3]).unwrap(),
+            vec![1, 2, 3]
+        );
+    }
+
+    #[test]
+    fn test_deep_merge() {
+        let base = IndexMap::from([("A", "base"), ("B", "base")]);
+        let target = IndexMap::from([("B", "target"), ("C", "target")]);
+        assert_eq!(
+            deep_merge("field", &Some(base.clone()), &Some(target)).unwrap(),
+            Some(IndexMap::from([
+                ("A", "base"),
+                ("B", "target"),
+                ("C", "target")
+            ]))
+        );
+        assert_eq!(
+            deep_merge("field", &Some(base.clone()), &None).unwrap(),
+            Some(base)
+        );
+    }
+
+    #[test]
+    fn test_base_only() {
+        assert_eq!(base_only("field", &Some(1), &None).unwrap(), Some(1));
+        let error = base_only("field", &Some(1), &Some(2)).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "`field` cannot have a target specific value"
+        );
+    }
+}
@@ -0,0 +1,239 @@
+//! Configuration options that are shared between the backends.
+//!
+//! Backends flatten these structs into their own configuration with
+//! `#[serde(flatten)]`, so the options are spelled and merged the same way in
+//! every backend. Target-specific configurations are merged with
+//! [`crate::merge_target_config`] and the strategies in [`merge`].
+
+pub mod merge;
+
+use std::path::{Path, PathBuf};
+
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    generated_recipe::BackendConfig, merge_target_config, package_format::PackageFormat,
+    sandbox::SandboxConfig,
+};
+use merge::DeepMerge;
+
+/// Configuration options that every backend supports.
+///
+/// Target-specific values replace the base values, except for `debug_dir`
+/// which cannot be set for a specific target.
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct CommonBackendConfig {
+    /// Deprecated. Setting this has no effect; debug data is always written to
+    /// the `debug` subdirectory of the work directory.
+    #[serde(alias = "debug_dir")]
+    pub debug_dir: Option<PathBuf>,
+    /// Extra input globs to include in addition to the default ones
+    #[serde(default)]
+    pub extra_input_globs: Vec<String>,
+    /// Run the tests defined in the recipe after the package has been built.
+    #[serde(default)]
+    pub run_tests: Option<bool>,
+    /// The archive format and compression level of the built package,
+    /// e.g. `conda`, `conda:max` or `tar-bz2`.
+    #[serde(default)]
+    pub package_format: Option<PackageFormat>,
+    /// Build the package reproducibly, with a fixed timestamp and a normalized
+    /// build environment.
+    #[serde(default)]
+    pub reproducible: Option<bool>,
+    /// The timestamp to use for reproducible builds, as seconds since the UNIX
+    /// epoch. The `SOURCE_DATE_EPOCH` environment variable takes precedence.
+    #[serde(default)]
+    pub source_date_epoch: Option<i64>,
+    /// Run the build script in a sandbox with restricted network and
+    /// filesystem access.
+    #[serde(default)]
+    pub sandbox: Option<SandboxConfig>,
+}
+
+impl DeepMerge for CommonBackendConfig {
+    fn deep_merge(&self, target: &Self) -> miette::Result<Self> {
+        Ok(merge_target_config!(
+            self,
+            target,
+            Self {
+                debug_dir: merge::base_only,
+                extra_input_globs: merge::replace,
+                run_tests: merge::replace,
+                package_format: merge::replace,
+                reproducible: merge::replace,
+                source_date_epoch: merge::replace,
+                sandbox: merge::replace,
+            }
+        ))
+    }
+}
+
+impl BackendConfig for CommonBackendConfig {
+    fn debug_dir(&self) -> Option<&Path> {
+        self.debug_dir.as_deref()
+    }
+
+    fn run_tests(&self) -> bool {
+        self.run_tests.unwrap_or(false)
+    }
+
+    fn package_format(&self) -> PackageFormat {
+        self.package_format.unwrap_or_default()
+    }
+
+    fn reproducible(&self) -> bool {
+        self.reproducible.unwrap_or(false)
+    }
+
+    fn source_date_epoch(&self) -> Option<i64> {
+        self.source_date_epoch
+    }
+
+    fn sandbox(&self) -> Option<&SandboxConfig> {
+        self.sandbox.as_ref()
+    }
+
+    fn merge_with_target_config(&self, target_config: &Self) -> miette::Result<Self> {
+        self.deep_merge(target_config)
+    }
+}
+
+/// Configuration options of the backends that generate a recipe from the
+/// project model.
+///
+/// Target-specific environment variables and constraints are added to the base
+/// values, all other target-specific values replace the base values.
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct GeneratorConfig {
+    /// Environment Variables
+    #[serde(default)]
+    pub env: IndexMap<String, String>,
+    /// List of compilers to use (e.g., ["c", "cxx", "cuda"])
+    /// If not specified, the default of the backend will be used
+    pub compilers: Option<Vec<String>>,
+    /// Constraints on the packages of the build environment, as a mapping
+    /// from package name to version spec. A constraint does not add the
+    /// package to the environment.
+    #[serde(default)]
+    pub build_constraints: IndexMap<String, String>,
+    /// Constraints on the packages of the host environment, as a mapping
+    /// from package name to version spec. A constraint does not add the
+    /// package to the environment.
+    #[serde(default)]
+    pub host_constraints: IndexMap<String, String>,
+    /// A YAML file with a partial recipe that is merged onto the generated
+    /// recipe, relative to the source directory.
+    #[serde(default)]
+    pub recipe_overrides: Option<PathBuf>,
+    /// A Jinja template that replaces the default build script template,
+    /// relative to the source directory.
+    #[serde(default)]
+    pub build_script_template: Option<PathBuf>,
+    /// A snippet that is executed before the generated build script.
+    #[serde(default)]
+    pub pre_build_script: Option<String>,
+    /// A snippet that is executed after the generated build script.
+    #[serde(default)]
+    pub post_build_script: Option<String>,
+}
+
+impl DeepMerge for GeneratorConfig {
+    fn deep_merge(&self, target: &Self) -> miette::Result<Self> {
+        Ok(merge_target_config!(
+            self,
+            target,
+            Self {
+                env: merge::deep_merge,
+                compilers: merge::replace,
+                build_constraints: merge::deep_merge,
+                host_constraints: merge::deep_merge,
+                recipe_overrides: merge::replace,
+                build_script_template: merge::replace,
+                pre_build_script: merge::replace,
+                post_build_script: merge::replace,
+            }
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[derive(Debug, Default, Deserialize, Clone)]
+    #[serde(rename_all = "kebab-case", deny_unknown_fields)]
+    struct Config {
+        #[serde(default)]
+        extra_args: Vec<String>,
+        #[serde(flatten)]
+        common: CommonBackendConfig,
+        #[serde(flatten)]
+        generator: GeneratorConfig,
+    }
+
+    #[test]
+    fn test_deserialize_flattened() {
+        let config: Config = serde_json::from_value(json!({
+            "extra-args": ["--verbose"],
+            "debug_dir": "debug",
+            "run-tests": true,
+            "env": { "FOO": "bar" },
+            "pre-build-script": "echo before",
+        }))
+        .unwrap();
+        assert_eq!(config.extra_args, vec!["--verbose"]);
+        assert_eq!(config.common.debug_dir, Some(PathBuf::from("debug")));
+        assert!(config.common.run_tests());
+        assert_eq!(config.generator.env["FOO"], "bar");
+        assert_eq!(
+            config.generator.pre_build_script.as_deref(),
+            Some("echo before")
+        );
+
+        let error = serde_json::from_value::<Config>(json!({ "unknown-key": true })).unwrap_err();
+        assert!(error.to_string().contains("unknown-key"));
+    }
+
+    #[test]
+    fn test_merge() {
+        let base = GeneratorConfig {
+            env: IndexMap::from([
+                (String::from("BASE"), String::from("base")),
+                (String::from("SHARED"), String::from("base")),
+            ]),
+            compilers: Some(vec![String::from("c")]),
+            pre_build_script: Some(String::from("echo base")),
+            ..Default::default()
+        };
+        let target = GeneratorConfig {
+            env: IndexMap::from([(String::from("SHARED"), String::from("target"))]),
+            compilers: Some(vec![String::from("cxx")]),
+            ..Default::default()
+        };
+
+        let merged = base.deep_merge(&target).unwrap();
+        assert_eq!(merged.env["BASE"], "base");
+        assert_eq!(merged.env["SHARED"], "target");
+        assert_eq!(merged.compilers, Some(vec![String::from("cxx")]));
+        assert_eq!(merged.pre_build_script.as_deref(), Some("echo base"));
+
+        let target = CommonBackendConfig {
+            debug_dir: Some(PathBuf::from("debug")),
+            ..Default::default()
+        };
+        let error = CommonBackendConfig::default()
+            .deep_merge(&target)
+            .unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("`debug_dir` cannot have a target specific value")
+        );
+    }
+}
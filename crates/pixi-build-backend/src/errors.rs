@@ -0,0 +1,173 @@
+//! Categorization of the errors that are reported to the frontend.
+//!
+//! Errors are reported to the frontend as JSON-RPC errors. To allow the
+//! frontend to tell e.g. a typo in the configuration from a failing compiler,
+//! errors are wrapped in a [`CategorizedError`] at the place where they occur.
+//! Each [`ErrorCategory`] has a stable error code, and the `data` of the
+//! JSON-RPC error contains the details of the [`CategorizedError`] next to the
+//! rendered diagnostic, which includes the source spans of the error.
+
+use std::fmt::Display;
+
+use serde::Serialize;
+
+/// The error code of errors that do not belong to a specific category.
+pub const UNCATEGORIZED_ERROR_CODE: i64 = -32000;
+
+/// The category of an error that occurred in the backend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ErrorCategory {
+    /// The backend configuration is invalid.
+    Configuration,
+    /// The package manifest or the metadata of the package could not be read.
+    Manifest,
+    /// The variants of a package could not be resolved.
+    VariantResolution,
+    /// The build script failed.
+    BuildScript,
+    /// The built files could not be packaged.
+    Packaging,
+}
+
+impl ErrorCategory {
+    /// Returns the JSON-RPC error code of this category. These codes are
+    /// stable and must not be changed.
+    pub const fn code(self) -> i64 {
+        match self {
+            ErrorCategory::Configuration => -32001,
+            ErrorCategory::Manifest => -32002,
+            ErrorCategory::VariantResolution => -32003,
+            ErrorCategory::BuildScript => -32004,
+            ErrorCategory::Packaging => -32005,
+        }
+    }
+}
+
+/// Additional information about an error that is reported to the frontend.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CategorizedError {
+    /// The category of the error.
+    pub category: ErrorCategory,
+
+    /// The name of the output that failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+
+    /// The last lines of the build log before the error occurred.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub log_excerpt: Vec<String>,
+}
+
+impl CategorizedError {
+    pub fn new(category: ErrorCategory) -> Self {
+        Self {
+            category,
+            output: None,
+            log_excerpt: Vec::new(),
+        }
+    }
+
+    /// Sets the name of the output that failed.
+    pub fn with_output(mut self, output: impl Into<String>) -> Self {
+        self.output = Some(output.into());
+        self
+    }
+
+    /// Sets the last lines of the build log.
+    pub fn with_log_excerpt(mut self, log_excerpt: Vec<String>) -> Self {
+        self.log_excerpt = log_excerpt;
+        self
+    }
+
+    /// Finds the [`CategorizedError`] that an error was wrapped in, if any.
+    pub fn find(err: &miette::Report) -> Option<&Self> {
+        err.downcast_ref::<Self>()
+    }
+}
+
+impl Display for CategorizedError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.category {
+            ErrorCategory::Configuration => write!(f, "invalid backend configuration"),
+            ErrorCategory::Manifest => write!(f, "failed to read the package metadata"),
+            ErrorCategory::VariantResolution => write!(f, "failed to resolve the variants"),
+            ErrorCategory::BuildScript => write!(f, "the build script failed"),
+            ErrorCategory::Packaging => write!(f, "failed to package the build output"),
+        }?;
+        match &self.output {
+            Some(output) => write!(f, " for '{output}'"),
+            None => Ok(()),
+        }
+    }
+}
+
+/// An extension trait to wrap the error of a [`Result`] in a
+/// [`CategorizedError`].
+pub trait CategorizeError<T> {
+    /// Wraps the error in a [`CategorizedError`] of the given category.
+    fn categorize(self, category: ErrorCategory) -> miette::Result<T>;
+
+    /// Wraps the error in the [`CategorizedError`] that is returned by `f`.
+    fn categorize_with(self, f: impl FnOnce() -> CategorizedError) -> miette::Result<T>;
+}
+
+impl<T, E: Into<miette::Report>> CategorizeError<T> for Result<T, E> {
+    fn categorize(self, category: ErrorCategory) -> miette::Result<T> {
+        self.categorize_with(|| CategorizedError::new(category))
+    }
+
+    fn categorize_with(self, f: impl FnOnce() -> CategorizedError) -> miette::Result<T> {
+        self.map_err(|err| {
+            let err = err.into();
+            // Keep the innermost category, it is the most specific one.
+            if CategorizedError::find(&err).is_some() {
+                err
+            } else {
+                err.wrap_err(f())
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_category() {
+        let result: miette::Result<()> = Err(miette::miette!("unknown field `foo`"));
+        let err = result
+            .categorize(ErrorCategory::Configuration)
+            .map_err(|err| err.wrap_err("failed to initialize"))
+            .unwrap_err();
+
+        let categorized = CategorizedError::find(&err).unwrap();
+        assert_eq!(categorized.category, ErrorCategory::Configuration);
+        assert_eq!(categorized.category.code(), -32001);
+    }
+
+    #[test]
+    fn test_innermost_category_is_kept() {
+        let result: miette::Result<()> = Err(miette::miette!("compiler not found"));
+        let err = result
+            .categorize_with(|| {
+                CategorizedError::new(ErrorCategory::BuildScript).with_output("foo")
+            })
+            .categorize(ErrorCategory::Manifest)
+            .unwrap_err();
+
+        assert_eq!(err.to_string(), "the build script failed for 'foo'");
+        assert_eq!(
+            CategorizedError::find(&err).unwrap().category,
+            ErrorCategory::BuildScript
+        );
+    }
+
+    #[test]
+    fn test_uncategorized_error() {
+        let err = miette::miette!("something went wrong");
+        assert!(CategorizedError::find(&err).is_none());
+    }
+}
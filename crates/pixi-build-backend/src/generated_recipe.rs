@@ -1,5 +1,8 @@
+use indexmap::IndexMap;
+use itertools::Itertools;
 use miette::Diagnostic;
 use pixi_build_types::ProjectModelV1;
+use purl::PackageUrl;
 use rattler_build::{NormalizedKey, recipe::variable::Variable};
 use rattler_conda_types::{ChannelUrl, Platform, Version};
 use recipe_stage0::recipe::{About, IntermediateRecipe, Package, Value};
@@ -13,7 +16,10 @@ use std::{
 };
 use thiserror::Error;
 
-use crate::specs_conversion::from_targets_v1_to_conditional_requirements;
+use crate::{
+    TargetSelector, package_format::PackageFormat, sandbox::SandboxConfig,
+    specs_conversion::from_targets_v1_to_conditional_requirements,
+};
 
 #[derive(Debug, Clone, Default)]
 pub struct PythonParams {
@@ -41,7 +47,10 @@ pub trait GenerateRecipe {
     ///
     /// * `model` - The project model to convert into a recipe
     /// * `config` - Backend-specific configuration options
-    /// * `manifest_path` - Path to the project manifest file
+    /// * `manifest_path` - The source directory of the package, or the path of
+    ///   a manifest file in it. Relative paths in the configuration, like the
+    ///   `build-script-template`, are resolved against this directory, the
+    ///   same directory that `recipe-overrides` is resolved against.
     /// * `host_platform` - The host platform will be removed in the future.
     ///   Right now it is used to determine if certain dependencies are present
     ///   for the host platform. Instead, we should rely on recipe selectors and
@@ -96,11 +105,104 @@ pub trait BackendConfig: DeserializeOwned + Clone {
     /// Debug dir provided by the backend config
     fn debug_dir(&self) -> Option<&Path>;
 
+    /// Whether the tests defined in the recipe should be executed after the
+    /// package has been built. Defaults to `false`.
+    fn run_tests(&self) -> bool {
+        false
+    }
+
+    /// The archive format and compression level of the packages that are
+    /// built. Defaults to `.conda` with the default compression level.
+    fn package_format(&self) -> PackageFormat {
+        PackageFormat::default()
+    }
+
+    /// Whether the package should be built reproducibly. See
+    /// [`crate::reproducible`] for details. Defaults to `false`.
+    fn reproducible(&self) -> bool {
+        false
+    }
+
+    /// The timestamp to use for reproducible builds, as seconds since the
+    /// UNIX epoch.
+    fn source_date_epoch(&self) -> Option<i64> {
+        None
+    }
+
+    /// The sandbox in which the build script is executed. If `None` the build
+    /// script is not sandboxed.
+    fn sandbox(&self) -> Option<&SandboxConfig> {
+        None
+    }
+
+    /// Constraints on the packages of the build environment, as a mapping from
+    /// package name to version spec.
+    fn build_constraints(&self) -> Option<&IndexMap<String, String>> {
+        None
+    }
+
+    /// Constraints on the packages of the host environment, as a mapping from
+    /// package name to version spec.
+    fn host_constraints(&self) -> Option<&IndexMap<String, String>> {
+        None
+    }
+
+    /// A YAML file with a partial recipe that is merged onto the generated
+    /// recipe, relative to the source directory. See
+    /// [`crate::recipe_overrides`] for details.
+    fn recipe_overrides(&self) -> Option<&Path> {
+        None
+    }
+
+    /// A Jinja template that replaces the default build script template of
+    /// the backend, relative to the source directory. See
+    /// [`crate::build_script`] for details.
+    fn build_script_template(&self) -> Option<&Path> {
+        None
+    }
+
+    /// A snippet that is executed before the generated build script.
+    fn pre_build_script(&self) -> Option<&str> {
+        None
+    }
+
+    /// A snippet that is executed after the generated build script.
+    fn post_build_script(&self) -> Option<&str> {
+        None
+    }
+
     /// Merge this configuration with a target-specific configuration.
     /// Target-specific values typically override base values.
     fn merge_with_target_config(&self, target_config: &Self) -> miette::Result<Self>;
 }
 
+/// Merges all target configurations whose selector matches `platform` into
+/// `config`.
+///
+/// The target configurations are applied in order of the
+/// [`TargetSelector::precedence`] of their selector: first family selectors
+/// (`unix`, `win`), then operating systems (`linux`, `osx`) and finally exact
+/// platforms. Each layer is merged through
+/// [`BackendConfig::merge_with_target_config`], so more specific selectors
+/// override less specific ones.
+pub fn merge_target_configs<'a, C, S>(
+    config: &C,
+    target_configs: impl IntoIterator<Item = (&'a S, &'a C)>,
+    platform: Platform,
+) -> miette::Result<C>
+where
+    C: BackendConfig + 'a,
+    S: TargetSelector + 'a,
+{
+    target_configs
+        .into_iter()
+        .filter(|(selector, _)| selector.matches(platform))
+        .sorted_by_key(|(selector, _)| selector.precedence())
+        .try_fold(config.clone(), |merged, (_, target_config)| {
+            merged.merge_with_target_config(target_config)
+        })
+}
+
 #[derive(Debug, Error, Diagnostic)]
 pub enum GenerateRecipeError<MetadataProviderError: Diagnostic + 'static> {
     #[error("There was no name defined for the recipe")]
@@ -115,7 +217,14 @@ pub enum GenerateRecipeError<MetadataProviderError: Diagnostic + 'static> {
 pub struct GeneratedRecipe {
     pub recipe: IntermediateRecipe,
     pub metadata_input_globs: BTreeSet<String>,
+    /// The globs of the files that influence the build. These apply to every
+    /// output of the recipe, because all outputs are built from the same
+    /// source directory.
     pub build_input_globs: BTreeSet<String>,
+
+    /// The package URLs that identify the package in its upstream ecosystem
+    /// (e.g. `pkg:pypi/numpy`).
+    pub purls: Option<BTreeSet<PackageUrl>>,
 }
 
 impl GeneratedRecipe {
@@ -200,6 +309,10 @@ impl GeneratedRecipe {
                 .map(Value::Concrete),
         };
 
+        let purls = provider
+            .purls()
+            .map_err(|e| GenerateRecipeError::MetadataProviderError(String::from("purls"), e))?;
+
         let ir = IntermediateRecipe {
             package,
             requirements,
@@ -209,6 +322,7 @@ impl GeneratedRecipe {
 
         Ok(GeneratedRecipe {
             recipe: ir,
+            purls,
             // TODO: extract real input globs from the model
             ..Default::default()
         })
@@ -257,6 +371,12 @@ pub trait MetadataProvider {
     fn repository(&mut self) -> Result<Option<String>, Self::Error> {
         Ok(None)
     }
+
+    /// Returns the package URLs that identify the package in its upstream
+    /// ecosystem or `None` if the provider does not provide any.
+    fn purls(&mut self) -> Result<Option<BTreeSet<PackageUrl>>, Self::Error> {
+        Ok(None)
+    }
 }
 
 pub struct DefaultMetadataProvider;
@@ -264,3 +384,127 @@ pub struct DefaultMetadataProvider;
 impl MetadataProvider for DefaultMetadataProvider {
     type Error = Infallible;
 }
+
+#[cfg(test)]
+mod tests {
+    use pixi_build_types::TargetSelectorV1;
+    use serde::Deserialize;
+
+    use super::*;
+
+    /// A configuration that records the order in which target configurations
+    /// are merged.
+    #[derive(Debug, Clone, Default, Deserialize)]
+    struct LayeredConfig {
+        layers: Vec<String>,
+    }
+
+    impl BackendConfig for LayeredConfig {
+        fn debug_dir(&self) -> Option<&Path> {
+            None
+        }
+
+        fn merge_with_target_config(&self, target_config: &Self) -> miette::Result<Self> {
+            Ok(Self {
+                layers: self
+                    .layers
+                    .iter()
+                    .chain(&target_config.layers)
+                    .cloned()
+                    .collect(),
+            })
+        }
+    }
+
+    fn selectors() -> Vec<TargetSelectorV1> {
+        vec![
+            TargetSelectorV1::Platform(String::from("linux-64")),
+            TargetSelectorV1::Platform(String::from("osx-arm64")),
+            TargetSelectorV1::Platform(String::from("win-64")),
+            TargetSelectorV1::Linux,
+            TargetSelectorV1::MacOs,
+            TargetSelectorV1::Unix,
+            TargetSelectorV1::Win,
+        ]
+    }
+
+    fn selector(selector: &str) -> TargetSelectorV1 {
+        match selector {
+            "unix" => TargetSelectorV1::Unix,
+            "linux" => TargetSelectorV1::Linux,
+            "osx" => TargetSelectorV1::MacOs,
+            "win" => TargetSelectorV1::Win,
+            platform => TargetSelectorV1::Platform(platform.to_string()),
+        }
+    }
+
+    /// The selectors are declared in the least favorable order, most specific
+    /// first, so the result only depends on their precedence.
+    #[rstest::rstest]
+    #[case(Platform::Linux64, &[], &["base"])]
+    #[case(Platform::Linux64, &["linux-64", "linux", "unix"], &["base", "unix", "linux", "linux-64"])]
+    #[case(Platform::Linux64, &["linux", "unix"], &["base", "unix", "linux"])]
+    #[case(Platform::Linux64, &["linux-64", "unix"], &["base", "unix", "linux-64"])]
+    #[case(Platform::Linux64, &["osx-arm64", "win", "osx"], &["base"])]
+    #[case(Platform::LinuxAarch64, &["linux-64", "linux", "unix"], &["base", "unix", "linux"])]
+    #[case(Platform::OsxArm64, &["osx-arm64", "linux", "osx", "unix"], &["base", "unix", "osx", "osx-arm64"])]
+    #[case(Platform::Osx64, &["osx-arm64", "osx"], &["base", "osx"])]
+    #[case(Platform::Win64, &["win-64", "unix", "win"], &["base", "win", "win-64"])]
+    #[case(Platform::Win64, &["linux", "unix"], &["base"])]
+    fn test_merge_target_configs_matrix(
+        #[case] platform: Platform,
+        #[case] selectors: &[&str],
+        #[case] expected: &[&str],
+    ) {
+        let base = LayeredConfig {
+            layers: vec![String::from("base")],
+        };
+        let target_configs = selectors
+            .iter()
+            .map(|name| {
+                let config = LayeredConfig {
+                    layers: vec![name.to_string()],
+                };
+                (selector(name), config)
+            })
+            .collect::<Vec<_>>();
+
+        let merged =
+            merge_target_configs(&base, target_configs.iter().map(|(s, c)| (s, c)), platform)
+                .unwrap();
+        assert_eq!(merged.layers, expected);
+    }
+
+    #[test]
+    fn test_merge_target_configs_layering_order() {
+        let base = LayeredConfig {
+            layers: vec![String::from("base")],
+        };
+        let target_configs = selectors()
+            .into_iter()
+            .map(|selector| {
+                let config = LayeredConfig {
+                    layers: vec![selector.to_string()],
+                };
+                (selector, config)
+            })
+            .collect::<Vec<_>>();
+
+        let merged = |platform| {
+            merge_target_configs(&base, target_configs.iter().map(|(s, c)| (s, c)), platform)
+                .unwrap()
+                .layers
+        };
+
+        assert_eq!(
+            merged(Platform::Linux64),
+            ["base", "unix", "linux", "linux-64"]
+        );
+        assert_eq!(merged(Platform::LinuxAarch64), ["base", "unix", "linux"]);
+        assert_eq!(
+            merged(Platform::OsxArm64),
+            ["base", "unix", "osx", "osx-arm64"]
+        );
+        assert_eq!(merged(Platform::Win64), ["base", "win", "win-64"]);
+    }
+}
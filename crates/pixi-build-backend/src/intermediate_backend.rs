@@ -9,15 +9,15 @@ use itertools::Itertools;
 use miette::{Context, IntoDiagnostic};
 use ordermap::OrderMap;
 use pixi_build_types::{
-    BackendCapabilities, PathSpecV1, ProjectModelV1, SourcePackageSpecV1, TargetSelectorV1,
+    PathSpecV1, PlatformAndVirtualPackages, ProjectModelV1, SourcePackageSpecV1, TargetSelectorV1,
     procedures::{
+        self,
         conda_build_v1::{CondaBuildV1Output, CondaBuildV1Params, CondaBuildV1Result},
         conda_outputs::{
             CondaOutput, CondaOutputDependencies, CondaOutputIgnoreRunExports, CondaOutputMetadata,
             CondaOutputRunExports, CondaOutputsParams, CondaOutputsResult,
         },
         initialize::{InitializeParams, InitializeResult},
-        negotiate_capabilities::{NegotiateCapabilitiesParams, NegotiateCapabilitiesResult},
     },
 };
 use rattler_build::{
@@ -25,30 +25,39 @@ use rattler_build::{
     build::{WorkingDirectoryBehavior, run_build},
     console_utils::LoggingOutputHandler,
     hash::HashInfo,
-    metadata::{BuildConfiguration, Debug, Output, PlatformWithVirtualPackages},
-    recipe::{ParsingError, Recipe, parser::find_outputs_from_src},
+    metadata::{BuildConfiguration, Debug, Output},
+    recipe::{Jinja, ParsingError, Recipe, parser::find_outputs_from_src},
     selectors::SelectorConfig,
     source_code::Source,
     tool_configuration::Configuration,
-    types::{Directories, PackageIdentifier, PackagingSettings},
+    types::{Directories, PackageIdentifier},
     variant_config::{DiscoveredOutput, ParseErrors, VariantConfig},
 };
-use rattler_conda_types::{Platform, compression_level::CompressionLevel, package::ArchiveType};
+use rattler_conda_types::Platform;
 
 use serde::Deserialize;
 use tracing::warn;
 
 use crate::{
-    TargetSelector,
+    common::platforms_with_virtual_packages,
     consts::DEBUG_OUTPUT_DIR,
     dependencies::{
         convert_binary_dependencies, convert_dependencies, convert_input_variant_configuration,
     },
-    generated_recipe::{BackendConfig, GenerateRecipe, PythonParams},
-    protocol::{Protocol, ProtocolInstantiator},
+    errors::{CategorizeError, ErrorCategory},
+    generated_recipe::{
+        BackendConfig, GenerateRecipe, GeneratedRecipe, PythonParams, merge_target_configs,
+    },
+    notifications::{BuildPhase, OutputBuild},
+    outputs_cache::CondaOutputsCache,
+    package_test::run_package_tests,
+    protocol::{GenerateRecipeParams, Protocol, ProtocolInstantiator},
+    recipe_overrides::{RecipeOverrides, RecipeOverridesError},
+    reproducible::{apply_normalized_build_env, source_date_epoch},
     specs_conversion::{
         convert_variant_from_pixi_build_types, convert_variant_to_pixi_build_types,
-        from_build_v1_args_to_finalized_dependencies,
+        from_build_v1_args_to_finalized_dependencies, from_constraint_specs,
+        resolve_conditional_constraints,
     },
     tools::{OneOrMultipleOutputs, output_directory},
 };
@@ -78,6 +87,36 @@ impl<T: GenerateRecipe> IntermediateBackendInstantiator<T> {
             generator: instance,
         }
     }
+
+    /// Constructs the backend for the given initialization parameters.
+    fn instantiate(&self, params: InitializeParams) -> miette::Result<IntermediateBackend<T>> {
+        let project_model = params
+            .project_model
+            .ok_or_else(|| miette::miette!("project model is required"))?;
+
+        let project_model = project_model
+            .into_v1()
+            .ok_or_else(|| miette::miette!("project model v1 is required"))?;
+
+        let config = if let Some(config) = params.configuration {
+            config
+        } else {
+            serde_json::Value::Object(Default::default())
+        };
+
+        let target_config = params.target_configuration.unwrap_or_default();
+
+        IntermediateBackend::<T>::new(
+            params.manifest_path,
+            params.source_dir,
+            project_model,
+            self.generator.clone(),
+            config,
+            target_config,
+            self.logging_output_handler.clone(),
+            params.cache_directory,
+        )
+    }
 }
 
 pub struct IntermediateBackend<T: GenerateRecipe> {
@@ -89,6 +128,9 @@ pub struct IntermediateBackend<T: GenerateRecipe> {
     pub(crate) generate_recipe: Arc<T>,
     pub(crate) config: T::Config,
     pub(crate) target_config: OrderMap<TargetSelectorV1, T::Config>,
+    /// The configuration as it was passed by the frontend, used as part of
+    /// the key of the `conda/outputs` cache.
+    pub(crate) raw_config: serde_json::Value,
     pub(crate) cache_dir: Option<PathBuf>,
 }
 impl<T: GenerateRecipe> IntermediateBackend<T> {
@@ -132,9 +174,15 @@ impl<T: GenerateRecipe> IntermediateBackend<T> {
             }
         };
 
+        let raw_config = serde_json::json!({
+            "config": config,
+            "targets": target_config.iter().collect::<Vec<_>>(),
+        });
+
         let config = serde_json::from_value::<T::Config>(config)
             .into_diagnostic()
-            .context("failed to parse configuration")?;
+            .context("failed to parse configuration")
+            .categorize(ErrorCategory::Configuration)?;
 
         if let Some(path) = config.debug_dir() {
             warn!(
@@ -148,9 +196,8 @@ impl<T: GenerateRecipe> IntermediateBackend<T> {
             .map(|(target, config)| {
                 let config = serde_json::from_value::<T::Config>(config)
                     .into_diagnostic()
-                    .wrap_err_with(|| {
-                        format!("failed to parse target configuration for {target}")
-                    })?;
+                    .wrap_err_with(|| format!("failed to parse target configuration for {target}"))
+                    .categorize(ErrorCategory::Configuration)?;
                 Ok((target, config))
             })
             .collect::<Result<_, miette::Report>>()?;
@@ -162,61 +209,157 @@ impl<T: GenerateRecipe> IntermediateBackend<T> {
             generate_recipe,
             config,
             target_config,
+            raw_config,
             logging_output_handler,
             cache_dir,
         })
     }
-}
 
-#[async_trait::async_trait]
-impl<T> ProtocolInstantiator for IntermediateBackendInstantiator<T>
-where
-    T: GenerateRecipe + Clone + Send + Sync + 'static,
-    T::Config: Send + Sync + 'static,
-{
-    async fn initialize(
+    /// Generates the recipe for `host_platform` the same way it is generated
+    /// for `conda/outputs`. The build input globs of the result also include
+    /// the globs that are extracted from the build.
+    pub fn generate_recipe(
         &self,
-        params: InitializeParams,
-    ) -> miette::Result<(Box<dyn Protocol + Send + Sync + 'static>, InitializeResult)> {
-        let project_model = params
-            .project_model
-            .ok_or_else(|| miette::miette!("project model is required"))?;
+        host_platform: Platform,
+        variant_configuration: Option<BTreeMap<String, Vec<pixi_build_types::VariantValue>>>,
+    ) -> miette::Result<GeneratedRecipe> {
+        let config = merge_target_configs(&self.config, &self.target_config, host_platform)
+            .categorize(ErrorCategory::Configuration)?;
 
-        let project_model = project_model
-            .into_v1()
-            .ok_or_else(|| miette::miette!("project model v1 is required"))?;
+        let mut variants = self
+            .generate_recipe
+            .default_variants(host_platform)
+            .categorize(ErrorCategory::VariantResolution)?;
+        variants.append(
+            &mut convert_input_variant_configuration(variant_configuration).unwrap_or_default(),
+        );
 
-        let config = if let Some(config) = params.configuration {
-            config
-        } else {
-            serde_json::Value::Object(Default::default())
+        let mut generated_recipe = self
+            .generate_recipe
+            .generate_recipe(
+                &self.project_model,
+                &config,
+                self.source_dir.clone(),
+                host_platform,
+                Some(PythonParams { editable: false }),
+                &variants.keys().cloned().collect(),
+                vec![],
+            )
+            .categorize(ErrorCategory::Manifest)?;
+        apply_configured_constraints(&config, &mut generated_recipe)
+            .categorize(ErrorCategory::Configuration)?;
+        self.apply_recipe_overrides(&config, &mut generated_recipe)
+            .categorize(ErrorCategory::Configuration)?;
+
+        let build_input_globs = self.generate_recipe.extract_input_globs_from_build(
+            &config,
+            &self.source_dir,
+            false,
+        )?;
+        generated_recipe.build_input_globs.extend(build_input_globs);
+
+        Ok(generated_recipe)
+    }
+
+    /// Merges the `recipe-overrides` of the configuration onto the generated
+    /// recipe. The overrides file is added to the input globs so that changes
+    /// to it are picked up.
+    fn apply_recipe_overrides(
+        &self,
+        config: &T::Config,
+        generated_recipe: &mut GeneratedRecipe,
+    ) -> Result<(), RecipeOverridesError> {
+        let Some(overrides_path) = config.recipe_overrides() else {
+            return Ok(());
         };
+        let overrides_path = self.source_dir.join(overrides_path);
 
-        let target_config = params.target_configuration.unwrap_or_default();
+        let overrides = RecipeOverrides::from_path(&overrides_path)?;
+        generated_recipe.recipe = overrides.apply(&generated_recipe.recipe)?;
 
-        let instance = IntermediateBackend::<T>::new(
-            params.manifest_path,
-            params.source_dir,
-            project_model,
-            self.generator.clone(),
-            config,
-            target_config,
-            self.logging_output_handler.clone(),
-            params.cache_directory,
-        )?;
+        if let Some(glob) = pathdiff::diff_paths(&overrides_path, &self.source_dir)
+            .and_then(|path| path.to_str().map(|path| path.replace('\\', "/")))
+        {
+            generated_recipe.metadata_input_globs.insert(glob.clone());
+            generated_recipe.build_input_globs.insert(glob);
+        }
+        Ok(())
+    }
 
-        Ok((Box::new(instance), InitializeResult {}))
+    /// Returns everything that influences the result of `conda/outputs`,
+    /// except for the files that are matched by its input globs.
+    fn conda_outputs_cache_inputs(&self, params: &CondaOutputsParams) -> serde_json::Value {
+        // The contents of the variant files are part of the inputs, a missing
+        // file is reported when the variants are loaded.
+        let variant_files = params
+            .variant_files
+            .iter()
+            .flatten()
+            .map(|path| fs_err::read_to_string(path).ok())
+            .collect::<Vec<_>>();
+
+        serde_json::json!({
+            "backend": std::any::type_name::<T>(),
+            "version": env!("CARGO_PKG_VERSION"),
+            "source_dir": self.source_dir,
+            "manifest": self.manifest_rel_path,
+            "project_model": self.project_model,
+            "config": self.raw_config,
+            "params": params,
+            "variant_files": variant_files,
+        })
+    }
+}
+
+/// Adds the `build-constraints` and `host-constraints` of the configuration to
+/// the constraints of the generated recipe.
+fn apply_configured_constraints(
+    config: &impl BackendConfig,
+    generated_recipe: &mut GeneratedRecipe,
+) -> miette::Result<()> {
+    let requirements = &mut generated_recipe.recipe.requirements;
+    if let Some(constraints) = config.build_constraints() {
+        requirements
+            .build_constraints
+            .extend(from_constraint_specs(constraints)?);
     }
+    if let Some(constraints) = config.host_constraints() {
+        requirements
+            .host_constraints
+            .extend(from_constraint_specs(constraints)?);
+    }
+    Ok(())
+}
 
+#[async_trait::async_trait]
+impl<T> ProtocolInstantiator for IntermediateBackendInstantiator<T>
+where
+    T: GenerateRecipe + Clone + Send + Sync + 'static,
+    T::Config: BackendConfig + Send + Sync + 'static,
+{
     async fn negotiate_capabilities(
         _params: NegotiateCapabilitiesParams,
     ) -> miette::Result<NegotiateCapabilitiesResult> {
-        // Returns the capabilities of this backend based on the capabilities of
-        // the frontend.
         Ok(NegotiateCapabilitiesResult {
-            capabilities: default_capabilities(),
+            capabilities: backend_capabilities::<IntermediateBackend<T>>(),
         })
     }
+
+    async fn initialize(
+        &self,
+        params: InitializeParams,
+    ) -> miette::Result<(Box<dyn Protocol + Send + Sync + 'static>, InitializeResult)> {
+        let instance = self.instantiate(params)?;
+        Ok((Box::new(instance), InitializeResult {}))
+    }
+
+    async fn generate_recipe(
+        &self,
+        params: GenerateRecipeParams,
+    ) -> miette::Result<GeneratedRecipe> {
+        self.instantiate(params.initialize)?
+            .generate_recipe(params.host_platform, params.variant_configuration)
+    }
 }
 
 #[async_trait::async_trait]
@@ -225,20 +368,35 @@ where
     T: GenerateRecipe + Clone + Send + Sync + 'static,
     T::Config: BackendConfig + Send + Sync + 'static,
 {
+    fn implemented_methods() -> &'static [&'static str] {
+        &[
+            procedures::conda_outputs::METHOD_NAME,
+            procedures::conda_build_v1::METHOD_NAME,
+        ]
+    }
+
     async fn conda_outputs(
         &self,
         params: CondaOutputsParams,
     ) -> miette::Result<CondaOutputsResult> {
+        // Return the previous result if none of the inputs changed.
+        let cache = match &self.cache_dir {
+            Some(cache_dir) => Some(CondaOutputsCache::new(
+                cache_dir,
+                &self.conda_outputs_cache_inputs(&params),
+            )?),
+            None => None,
+        };
+        if let Some(result) = cache.as_ref().and_then(|cache| cache.get(&self.source_dir)) {
+            return Ok(result);
+        }
+
         let build_platform = params.host_platform;
 
-        let config = self
-            .target_config
-            .iter()
-            .find(|(selector, _)| selector.matches(params.host_platform))
-            .map(|(_, target_config)| self.config.merge_with_target_config(target_config))
-            .unwrap_or_else(|| Ok(self.config.clone()))?;
+        let config = merge_target_configs(&self.config, &self.target_config, params.host_platform)
+            .categorize(ErrorCategory::Configuration)?;
 
-        let selector_config_for_variants = SelectorConfig {
+        let mut selector_config_for_variants = SelectorConfig {
             target_platform: params.host_platform,
             host_platform: params.host_platform,
             build_platform,
@@ -251,7 +409,8 @@ where
 
         let mut variants = self
             .generate_recipe
-            .default_variants(params.host_platform)?;
+            .default_variants(params.host_platform)
+            .categorize(ErrorCategory::VariantResolution)?;
 
         // Construct a `VariantConfig` based on the input parameters. This is a
         // combination of defaults provided by the generator (lowest priority),
@@ -259,7 +418,8 @@ where
         // variants (highest priority).
         let variant_files = params.variant_files.unwrap_or_default();
         let mut variant_config =
-            VariantConfig::from_files(&variant_files, &selector_config_for_variants)?;
+            VariantConfig::from_files(&variant_files, &selector_config_for_variants)
+                .categorize(ErrorCategory::VariantResolution)?;
         variants.append(&mut variant_config.variants);
         variant_config.variants = variants;
 
@@ -269,15 +429,26 @@ where
         variant_config.variants.append(&mut param_variants);
 
         // Construct the intermediate recipe
-        let generated_recipe = self.generate_recipe.generate_recipe(
-            &self.project_model,
-            &config,
-            self.source_dir.clone(),
-            params.host_platform,
-            Some(PythonParams { editable: false }),
-            &variant_config.variants.keys().cloned().collect(),
-            params.channels,
-        )?;
+        let mut generated_recipe = self
+            .generate_recipe
+            .generate_recipe(
+                &self.project_model,
+                &config,
+                self.source_dir.clone(),
+                params.host_platform,
+                Some(PythonParams { editable: false }),
+                &variant_config.variants.keys().cloned().collect(),
+                params.channels,
+            )
+            .categorize(ErrorCategory::Manifest)?;
+        apply_configured_constraints(&config, &mut generated_recipe)
+            .categorize(ErrorCategory::Configuration)?;
+        self.apply_recipe_overrides(&config, &mut generated_recipe)
+            .categorize(ErrorCategory::Configuration)?;
+
+        // The staging cache of multi-output recipes is an experimental feature of
+        // rattler-build.
+        selector_config_for_variants.experimental = generated_recipe.recipe.cache.is_some();
 
         // Convert the recipe to source code.
         // TODO(baszalmstra): In the future it would be great if we could just
@@ -289,7 +460,7 @@ where
             code: Arc::from(
                 generated_recipe
                     .recipe
-                    .to_yaml_pretty()
+                    .to_rattler_build_yaml()
                     .into_diagnostic()?
                     .as_str(),
             ),
@@ -303,12 +474,15 @@ where
         // filled in. This is on purpose because at this point we dont yet know all
         // values like the variant. We should introduce a new type of selector config
         // for this particular case.
-        let outputs = find_outputs_from_src(named_source.clone())?;
-        let discovered_outputs = variant_config.find_variants(
-            &outputs,
-            named_source.clone(),
-            &selector_config_for_variants,
-        )?;
+        let outputs =
+            find_outputs_from_src(named_source.clone()).categorize(ErrorCategory::Manifest)?;
+        let discovered_outputs = variant_config
+            .find_variants(
+                &outputs,
+                named_source.clone(),
+                &selector_config_for_variants,
+            )
+            .categorize(ErrorCategory::VariantResolution)?;
 
         // Construct a mapping that for packages that we want from source.
         //
@@ -324,6 +498,18 @@ where
             })
             .collect();
 
+        // Determine the files that influence the build. Unlike a rattler-build recipe, an
+        // intermediate recipe has a single source: its outputs have no sources of their
+        // own and multi-output recipes build all outputs from one staging cache. A change
+        // to any source file therefore changes every output, so all outputs share the
+        // same input globs.
+        let mut build_input_globs = self.generate_recipe.extract_input_globs_from_build(
+            &config,
+            &self.source_dir,
+            false,
+        )?;
+        build_input_globs.extend(generated_recipe.build_input_globs.iter().cloned());
+
         let mut subpackages = HashMap::new();
         let mut outputs = Vec::new();
 
@@ -344,6 +530,18 @@ where
                 ..selector_config_for_variants.clone()
             };
 
+            // Constraints are not part of the rattler-build recipe, so we evaluate them
+            // ourselves with the same selector config.
+            let jinja = Jinja::new(selector_config.clone());
+            let build_constraints = resolve_conditional_constraints(
+                &generated_recipe.recipe.requirements.build_constraints,
+                &jinja,
+            )?;
+            let host_constraints = resolve_conditional_constraints(
+                &generated_recipe.recipe.requirements.host_constraints,
+                &jinja,
+            )?;
+
             // Convert this discovered output into a recipe.
             let recipe = Recipe::from_node(&discovered_output.node, selector_config.clone())
                 .map_err(|err| {
@@ -438,8 +636,13 @@ where
                     license: recipe.about.license.map(|l| l.to_string()),
                     license_family: recipe.about.license_family,
                     noarch: recipe.build.noarch,
-                    purls: None,
-                    python_site_packages_path: None,
+                    purls: generated_recipe.purls.clone(),
+                    python_site_packages_path: generated_recipe
+                        .recipe
+                        .build
+                        .python
+                        .site_packages_path
+                        .clone(),
                     variant: variant
                         .iter()
                         .map(|(key, value)| {
@@ -466,7 +669,11 @@ where
                         &subpackages,
                         &local_source_packages,
                     )?,
-                    constraints: Vec::new(),
+                    constraints: convert_binary_dependencies(
+                        build_constraints,
+                        &variant,
+                        &subpackages,
+                    )?,
                 }),
                 host_dependencies: Some(CondaOutputDependencies {
                     depends: convert_dependencies(
@@ -475,7 +682,11 @@ where
                         &subpackages,
                         &local_source_packages,
                     )?,
-                    constraints: Vec::new(),
+                    constraints: convert_binary_dependencies(
+                        host_constraints,
+                        &variant,
+                        &subpackages,
+                    )?,
                 }),
                 run_dependencies: CondaOutputDependencies {
                     depends: convert_dependencies(
@@ -535,16 +746,20 @@ where
                     )?,
                 },
 
-                // The input globs are the same for all outputs
-                input_globs: None,
-                // TODO: Implement caching
+                input_globs: Some(build_input_globs.clone()),
             });
         }
 
-        Ok(CondaOutputsResult {
+        let result = CondaOutputsResult {
             outputs,
             input_globs: generated_recipe.metadata_input_globs,
-        })
+        };
+
+        if let Some(Err(err)) = cache.map(|cache| cache.put(&self.source_dir, &result)) {
+            warn!("failed to cache the outputs: {err:?}");
+        }
+
+        Ok(result)
     }
 
     async fn conda_build_v1(
@@ -560,12 +775,33 @@ where
             .as_ref()
             .map_or_else(Platform::current, |prefix| prefix.platform);
 
-        let config = self
-            .target_config
-            .iter()
-            .find(|(selector, _)| selector.matches(host_platform))
-            .map(|(_, target_config)| self.config.merge_with_target_config(target_config))
-            .unwrap_or_else(|| Ok(self.config.clone()))?;
+        // The virtual packages of the prefixes are required to correctly build recipes
+        // that depend on e.g. a specific `__cuda` or `__glibc` version.
+        let (build_platform_with_virtual_packages, host_platform_with_virtual_packages) =
+            platforms_with_virtual_packages(
+                params
+                    .build_prefix
+                    .as_ref()
+                    .map(|prefix| PlatformAndVirtualPackages {
+                        platform: prefix.platform,
+                        virtual_packages: prefix.virtual_packages.clone(),
+                    }),
+                params
+                    .host_prefix
+                    .as_ref()
+                    .map(|prefix| PlatformAndVirtualPackages {
+                        platform: prefix.platform,
+                        virtual_packages: prefix.virtual_packages.clone(),
+                    }),
+            )?;
+
+        let config = merge_target_configs(&self.config, &self.target_config, host_platform)
+            .categorize(ErrorCategory::Configuration)?;
+
+        // Report the progress of the build to the frontend.
+        let output_name = params.output.name.as_normalized().to_string();
+        let build = OutputBuild::start(&output_name);
+        build.progress(BuildPhase::Rendering);
 
         // Construct the variants based on the input parameters. We only
         // have a single variant here so we can just use the variant from the
@@ -583,17 +819,24 @@ where
             .collect();
 
         // Construct the intermediate recipe
-        let mut recipe = self.generate_recipe.generate_recipe(
-            &self.project_model,
-            &config,
-            self.source_dir.clone(),
-            host_platform,
-            Some(PythonParams {
-                editable: params.editable.unwrap_or_default(),
-            }),
-            &variants.keys().cloned().collect(),
-            params.channels,
-        )?;
+        let mut recipe = self
+            .generate_recipe
+            .generate_recipe(
+                &self.project_model,
+                &config,
+                self.source_dir.clone(),
+                host_platform,
+                Some(PythonParams {
+                    editable: params.editable.unwrap_or_default(),
+                }),
+                &variants.keys().cloned().collect(),
+                params.channels.clone(),
+            )
+            .categorize(ErrorCategory::Manifest)?;
+        apply_configured_constraints(&config, &mut recipe)
+            .categorize(ErrorCategory::Configuration)?;
+        self.apply_recipe_overrides(&config, &mut recipe)
+            .categorize(ErrorCategory::Configuration)?;
 
         // Convert the recipe to source code.
         // TODO(baszalmstra): In the future it would be great if we could just
@@ -602,7 +845,13 @@ where
         let recipe_path = self.source_dir.join(&self.manifest_rel_path);
         let named_source = Source {
             name: self.manifest_rel_path.display().to_string(),
-            code: Arc::from(recipe.recipe.to_yaml_pretty().into_diagnostic()?.as_str()),
+            code: Arc::from(
+                recipe
+                    .recipe
+                    .to_rattler_build_yaml()
+                    .into_diagnostic()?
+                    .as_str(),
+            ),
             path: recipe_path.clone(),
         };
 
@@ -613,23 +862,27 @@ where
             build_platform,
             hash: None,
             variant: Default::default(),
-            experimental: false,
+            experimental: recipe.recipe.cache.is_some(),
             allow_undefined: false,
             recipe_path: Some(self.source_dir.join(&self.manifest_rel_path)),
         };
-        let outputs = find_outputs_from_src(named_source.clone())?;
+        let outputs =
+            find_outputs_from_src(named_source.clone()).categorize(ErrorCategory::Manifest)?;
 
         let variant_config = VariantConfig {
             variants,
             pin_run_as_build: None,
             zip_keys: None,
         };
-        let discovered_outputs = variant_config.find_variants(
-            &outputs,
-            named_source.clone(),
-            &selector_config_for_variants,
-        )?;
-        let discovered_output = find_matching_output(&params.output, discovered_outputs)?;
+        let discovered_outputs = variant_config
+            .find_variants(
+                &outputs,
+                named_source.clone(),
+                &selector_config_for_variants,
+            )
+            .categorize(ErrorCategory::VariantResolution)?;
+        let discovered_output = find_matching_output(&params.output, discovered_outputs)
+            .categorize(ErrorCategory::VariantResolution)?;
 
         // Set up the proper directories for the build.
         let directories = conda_build_v1_directories(
@@ -692,6 +945,7 @@ where
         let tool_config = Configuration::builder()
             .with_opt_cache_dir(self.cache_dir.clone())
             .with_logging_output_handler(self.logging_output_handler.clone())
+            // Tests are executed separately after the build, see `run_package_tests`.
             .with_testing(false)
             // Pixi is incremental so keep the build
             .with_keep_build(true)
@@ -700,33 +954,38 @@ where
             .with_environments_externally_managed(true)
             .finish();
 
+        // For reproducible builds the timestamp is fixed and the build script runs in a
+        // normalized environment.
+        let mut output_recipe = discovered_output.recipe;
+        let timestamp = if config.reproducible() {
+            let timestamp = source_date_epoch(config.source_date_epoch(), &self.source_dir)?;
+            apply_normalized_build_env(&mut output_recipe, timestamp);
+            timestamp
+        } else {
+            chrono::Utc::now()
+        };
+
         let output = Output {
-            recipe: discovered_output.recipe,
+            recipe: output_recipe,
             build_configuration: BuildConfiguration {
                 target_platform: discovered_output.target_platform,
-                host_platform: PlatformWithVirtualPackages {
-                    platform: host_platform,
-                    virtual_packages: vec![],
-                },
-                build_platform: PlatformWithVirtualPackages {
-                    platform: build_platform,
-                    virtual_packages: vec![],
-                },
+                host_platform: host_platform_with_virtual_packages,
+                build_platform: build_platform_with_virtual_packages,
                 hash: discovered_output.hash,
                 variant,
                 directories,
-                channels: vec![],
+                // The channels are only used to resolve the test environments.
+                channels: params.channels,
                 channel_priority: Default::default(),
                 solve_strategy: Default::default(),
-                timestamp: chrono::Utc::now(),
+                timestamp,
                 subpackages: BTreeMap::new(),
-                packaging_settings: PackagingSettings::from_args(
-                    ArchiveType::Conda,
-                    CompressionLevel::default(),
-                ),
+                packaging_settings: config.package_format().packaging_settings(),
                 store_recipe: false,
                 force_colors: true,
-                sandbox_config: None,
+                sandbox_config: config
+                    .sandbox()
+                    .and_then(|sandbox| sandbox.to_rattler_build(&self.source_dir)),
                 debug: Debug::new(false),
                 exclude_newer: None,
             },
@@ -745,8 +1004,23 @@ where
             extra_meta: None,
         };
 
-        let (output, output_path) =
-            run_build(output, &tool_config, WorkingDirectoryBehavior::Preserve).await?;
+        build.progress(BuildPhase::Building);
+        let (output, output_path) = build
+            .scope(run_build(
+                output,
+                &tool_config,
+                WorkingDirectoryBehavior::Preserve,
+            ))
+            .await
+            .categorize_with(|| build.failure())?;
+
+        // Run the tests of the recipe against the package that was just built.
+        if config.run_tests() {
+            build.progress(BuildPhase::Testing);
+            build
+                .scope(run_package_tests(&output, &output_path, &tool_config))
+                .await?;
+        }
 
         // Extract the input globs from the build and recipe
         let mut input_globs = self.generate_recipe.extract_input_globs_from_build(
@@ -756,6 +1030,7 @@ where
         )?;
         input_globs.append(&mut recipe.build_input_globs);
 
+        build.progress(BuildPhase::Finished);
         Ok(CondaBuildV1Result {
             output_file: output_path,
             input_globs,
@@ -868,14 +1143,3 @@ pub fn conda_build_v1_directories(
         build_dir: work_directory,
     }
 }
-
-/// Returns the capabilities for this backend
-fn default_capabilities() -> BackendCapabilities {
-    BackendCapabilities {
-        provides_conda_outputs: Some(true),
-        provides_conda_build_v1: Some(true),
-        highest_supported_project_model: Some(
-            pixi_build_types::VersionedProjectModel::highest_version(),
-        ),
-    }
-}
@@ -1,10 +1,23 @@
+pub mod build_script;
+pub mod cancellation;
 pub mod cli;
+pub mod config;
+pub mod errors;
 pub mod generated_recipe;
 pub mod intermediate_backend;
+pub mod notifications;
+pub mod outputs_cache;
+pub mod package_format;
+pub mod package_test;
 pub mod protocol;
 pub mod rattler_build_integration;
+pub mod recipe_overrides;
+pub mod reproducible;
+pub mod sandbox;
 pub mod server;
 pub mod specs_conversion;
+pub mod transcript;
+pub mod work_directory_lock;
 
 pub mod cache;
 pub mod common;
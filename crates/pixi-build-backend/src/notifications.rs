@@ -0,0 +1,558 @@
+//! Notifications that are sent from the backend to the frontend while a
+//! package is being built.
+//!
+//! The frontend opts in to these notifications by setting
+//! `buildNotifications` in the capabilities it passes to
+//! `negotiate_capabilities`:
+//!
+//! ```json
+//! { "capabilities": { "buildNotifications": true } }
+//! ```
+//!
+//! Two notifications are sent:
+//!
+//! - `build/log` for every line that is logged while an output is built, see
+//!   [`BuildLogParams`].
+//! - `build/progress` whenever the build of an output enters a new phase, see
+//!   [`BuildProgressParams`].
+//!
+//! Every session has its own [`BuildNotifier`] that is bound to the
+//! connection of the session, so the notifications of a build are only sent
+//! to the client that requested it. Notifications cannot be sent when
+//! communicating over HTTP.
+
+use std::{
+    collections::VecDeque,
+    future::Future,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use jsonrpc_core::{Notification, Params, Version};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc::UnboundedSender;
+use tracing::{
+    Event, Level, Subscriber,
+    field::{Field, Visit},
+    span::Attributes,
+};
+use tracing_subscriber::{Layer, layer::Context, registry::LookupSpan};
+
+use crate::errors::{CategorizedError, ErrorCategory};
+
+/// The name of the notification that carries a line of build output.
+pub const BUILD_LOG_METHOD: &str = "build/log";
+
+/// The name of the notification that reports the phase of a build.
+pub const BUILD_PROGRESS_METHOD: &str = "build/progress";
+
+/// The name of the span in which rattler-build packages the files of an
+/// output.
+const PACKAGING_SPAN_NAME: &str = "Packaging new files";
+
+/// The number of log lines of the current output that are kept to include in
+/// error reports.
+const LOG_TAIL_LINES: usize = 50;
+
+/// The capabilities of the frontend related to notifications.
+///
+/// These are read from the `capabilities` that are passed to
+/// `negotiate_capabilities`, next to the fields of `FrontendCapabilities`.
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FrontendNotificationCapabilities {
+    /// Whether the frontend wants to receive `build/log` and `build/progress`
+    /// notifications.
+    #[serde(default)]
+    pub build_notifications: bool,
+}
+
+impl FrontendNotificationCapabilities {
+    /// Extracts the notification capabilities from the raw parameters of a
+    /// `negotiate_capabilities` request.
+    pub fn from_params(params: &Params) -> Self {
+        #[derive(Deserialize)]
+        struct Wrapper {
+            #[serde(default)]
+            capabilities: FrontendNotificationCapabilities,
+        }
+
+        params
+            .clone()
+            .parse::<Wrapper>()
+            .map(|wrapper| wrapper.capabilities)
+            .unwrap_or_default()
+    }
+}
+
+/// The stream to which a line of build output was written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LogStream {
+    Stdout,
+    Stderr,
+}
+
+/// The parameters of the `build/log` notification.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BuildLogParams {
+    /// The name of the output that is being built.
+    pub output: String,
+
+    /// The stream to which the line was written.
+    pub stream: LogStream,
+
+    /// The line of output, without a trailing newline.
+    pub line: String,
+}
+
+/// A phase in the build of an output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BuildPhase {
+    /// The recipe of the output is being rendered.
+    Rendering,
+    /// The build script of the output is running.
+    Building,
+    /// The files of the output are packaged.
+    Packaging,
+    /// The tests of the package are running.
+    Testing,
+    /// The output was built successfully.
+    Finished,
+}
+
+/// The parameters of the `build/progress` notification.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BuildProgressParams {
+    /// The name of the output that is being built.
+    pub output: String,
+
+    /// The phase that the build of the output entered.
+    pub phase: BuildPhase,
+}
+
+tokio::task_local! {
+    /// The notifier of the session that processes the current request.
+    static SESSION_NOTIFIER: BuildNotifier;
+
+    /// The build of the output that the current task is working on.
+    static CURRENT_BUILD: OutputBuild;
+}
+
+/// Sends build notifications to the frontend.
+///
+/// Every session creates its own notifier and processes its requests in
+/// [`BuildNotifier::scope`], so that the notifier can be retrieved with
+/// [`BuildNotifier::current`] while a request is processed.
+///
+/// Until the frontend has opted in to notifications, all notifications are
+/// discarded.
+#[derive(Debug, Default, Clone)]
+pub struct BuildNotifier {
+    sender: Option<UnboundedSender<String>>,
+    enabled: Arc<AtomicBool>,
+}
+
+impl BuildNotifier {
+    /// Constructs a notifier that sends notifications to `sender`, the
+    /// connection of a session, once they are enabled. Without a sender all
+    /// notifications are discarded.
+    pub fn new(sender: Option<UnboundedSender<String>>) -> Self {
+        Self {
+            sender,
+            enabled: Default::default(),
+        }
+    }
+
+    /// Returns the notifier of the session that processes the current
+    /// request. Outside of a session a notifier is returned that discards
+    /// all notifications.
+    pub fn current() -> Self {
+        SESSION_NOTIFIER.try_with(Clone::clone).unwrap_or_default()
+    }
+
+    /// Runs `future` with this notifier as the notifier of the current
+    /// session, see [`BuildNotifier::current`].
+    pub fn scope<F: Future>(self, future: F) -> impl Future<Output = F::Output> {
+        SESSION_NOTIFIER.scope(self, future)
+    }
+
+    /// Starts or stops sending notifications, e.g. depending on the
+    /// capabilities that the frontend negotiated.
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Returns true if the frontend receives notifications.
+    pub fn is_enabled(&self) -> bool {
+        self.sender.is_some() && self.enabled.load(Ordering::Relaxed)
+    }
+
+    fn notify<P: Serialize>(&self, method: &str, params: P) {
+        let Some(sender) = self.sender.as_ref().filter(|_| self.is_enabled()) else {
+            return;
+        };
+        let notification = Notification {
+            jsonrpc: Some(Version::V2),
+            method: method.to_string(),
+            params: Params::Map(match serde_json::to_value(params) {
+                Ok(serde_json::Value::Object(map)) => map,
+                _ => unreachable!("notification parameters are always an object"),
+            }),
+        };
+        let notification =
+            serde_json::to_string(&notification).expect("failed to serialize notification");
+        // Logging a failure here would be forwarded as a notification again,
+        // so a disconnected client is silently ignored.
+        let _ = sender.send(notification);
+    }
+}
+
+/// The build of a single output.
+///
+/// Keeps track of the phase of the build and of the last lines that were
+/// logged, and sends them to the frontend through the notifier of the
+/// session that requested the build. Every build has its own state, so
+/// outputs that are built concurrently do not interfere with each other.
+///
+/// Log events are only attributed to a build while they are emitted by a
+/// future that runs in [`OutputBuild::scope`].
+#[derive(Debug, Clone)]
+pub struct OutputBuild {
+    notifier: BuildNotifier,
+    output: String,
+    state: Arc<Mutex<OutputBuildState>>,
+}
+
+#[derive(Debug, Default)]
+struct OutputBuildState {
+    phase: Option<BuildPhase>,
+    log_tail: VecDeque<String>,
+}
+
+impl OutputBuild {
+    /// Starts the build of `output` for the session that processes the
+    /// current request, see [`BuildNotifier::current`].
+    pub fn start(output: impl Into<String>) -> Self {
+        Self::with_notifier(BuildNotifier::current(), output)
+    }
+
+    /// Starts the build of `output` whose notifications are sent to
+    /// `notifier`.
+    pub fn with_notifier(notifier: BuildNotifier, output: impl Into<String>) -> Self {
+        Self {
+            notifier,
+            output: output.into(),
+            state: Default::default(),
+        }
+    }
+
+    /// Returns the build of the output that the current task is working on.
+    pub fn current() -> Option<Self> {
+        CURRENT_BUILD.try_with(Clone::clone).ok()
+    }
+
+    /// Runs `future` as part of this build, so that the events it logs are
+    /// attributed to this build, see [`BuildNotificationLayer`].
+    pub fn scope<F: Future>(&self, future: F) -> impl Future<Output = F::Output> {
+        CURRENT_BUILD.scope(self.clone(), future)
+    }
+
+    /// Returns the name of the output that is built.
+    pub fn output(&self) -> &str {
+        &self.output
+    }
+
+    /// Returns the phase that the build is in.
+    pub fn phase(&self) -> Option<BuildPhase> {
+        self.state().phase
+    }
+
+    /// Returns the last lines that were logged while building the output,
+    /// regardless of whether the frontend receives notifications.
+    pub fn log_tail(&self) -> Vec<String> {
+        self.state().log_tail.iter().cloned().collect()
+    }
+
+    /// Returns the details of an error that occurred during the build, based
+    /// on the phase the build was in.
+    pub fn failure(&self) -> CategorizedError {
+        let category = match self.phase() {
+            Some(BuildPhase::Packaging) => ErrorCategory::Packaging,
+            _ => ErrorCategory::BuildScript,
+        };
+        CategorizedError::new(category)
+            .with_output(self.output.clone())
+            .with_log_excerpt(self.log_tail())
+    }
+
+    /// Reports that the build entered `phase`.
+    pub fn progress(&self, phase: BuildPhase) {
+        self.state().phase = Some(phase);
+        self.notifier.notify(
+            BUILD_PROGRESS_METHOD,
+            BuildProgressParams {
+                output: self.output.clone(),
+                phase,
+            },
+        );
+    }
+
+    /// Sends a line of build output.
+    pub fn log(&self, stream: LogStream, line: impl Into<String>) {
+        let line = line.into();
+        {
+            let mut state = self.state();
+            if state.log_tail.len() == LOG_TAIL_LINES {
+                state.log_tail.pop_front();
+            }
+            state.log_tail.push_back(line.clone());
+        }
+        self.notifier.notify(
+            BUILD_LOG_METHOD,
+            BuildLogParams {
+                output: self.output.clone(),
+                stream,
+                line,
+            },
+        );
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, OutputBuildState> {
+        self.state.lock().expect("lock is poisoned")
+    }
+}
+
+/// A tracing layer that turns the log events of a build into `build/log`
+/// notifications of the session that requested the build. Events are
+/// attributed to the build that the emitting task is working on, see
+/// [`OutputBuild::scope`]. The last lines are also kept to include them in
+/// error reports, see [`OutputBuild::log_tail`].
+///
+/// The build is tracked with a task-local instead of a span, because spans
+/// are subject to the log level filter and are rendered by the log handler
+/// of rattler-build.
+///
+/// rattler-build merges the stdout and stderr of the build script into a
+/// single stream. Warnings and errors are therefore reported on
+/// [`LogStream::Stderr`] and all other lines on [`LogStream::Stdout`].
+#[derive(Debug, Default, Clone, Copy)]
+pub struct BuildNotificationLayer;
+
+impl<S> Layer<S> for BuildNotificationLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, _id: &tracing::span::Id, _ctx: Context<'_, S>) {
+        if attrs.metadata().name() != PACKAGING_SPAN_NAME {
+            return;
+        }
+        if let Some(build) = OutputBuild::current() {
+            build.progress(BuildPhase::Packaging);
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let Some(build) = OutputBuild::current() else {
+            return;
+        };
+
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+        let Some(message) = visitor.message else {
+            return;
+        };
+
+        let stream = if *event.metadata().level() <= Level::WARN {
+            LogStream::Stderr
+        } else {
+            LogStream::Stdout
+        };
+        for line in message.lines() {
+            build.log(stream, line);
+        }
+    }
+}
+
+/// Extracts the message of a tracing event.
+#[derive(Default)]
+struct MessageVisitor {
+    message: Option<String>,
+}
+
+impl Visit for MessageVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message = Some(value.to_string());
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            self.message = Some(format!("{value:?}"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{Value, json};
+    use tokio::sync::mpsc;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use super::*;
+
+    fn received(rx: &mut mpsc::UnboundedReceiver<String>) -> Vec<Value> {
+        std::iter::from_fn(|| rx.try_recv().ok())
+            .map(|notification| serde_json::from_str(&notification).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_notifications_are_discarded_until_enabled() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let notifier = BuildNotifier::new(Some(tx));
+        let build = OutputBuild::with_notifier(notifier.clone(), "foo");
+        build.progress(BuildPhase::Rendering);
+
+        notifier.set_enabled(true);
+        build.progress(BuildPhase::Building);
+
+        assert_eq!(
+            received(&mut rx),
+            vec![json!({
+                "jsonrpc": "2.0",
+                "method": "build/progress",
+                "params": { "output": "foo", "phase": "building" },
+            })]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_layer_forwards_events_of_current_output() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let notifier = BuildNotifier::new(Some(tx));
+        notifier.set_enabled(true);
+
+        let subscriber = tracing_subscriber::registry().with(BuildNotificationLayer);
+        let _default = tracing::subscriber::set_default(subscriber);
+        tracing::info!("not part of a session");
+        notifier
+            .clone()
+            .scope(async {
+                tracing::info!("not part of a build");
+                let build = OutputBuild::start("foo");
+                build
+                    .scope(async {
+                        tracing::info!("compiling");
+                        tracing::warn!("deprecated");
+                        let _span = tracing::info_span!("Packaging new files").entered();
+                    })
+                    .await;
+                tracing::info!("after the build");
+            })
+            .await;
+
+        assert_eq!(
+            received(&mut rx)
+                .into_iter()
+                .map(|notification| notification["params"].clone())
+                .collect::<Vec<_>>(),
+            vec![
+                json!({ "output": "foo", "stream": "stdout", "line": "compiling" }),
+                json!({ "output": "foo", "stream": "stderr", "line": "deprecated" }),
+                json!({ "output": "foo", "phase": "packaging" }),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_builds_are_tracked_separately() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let notifier = BuildNotifier::new(Some(tx));
+        notifier.set_enabled(true);
+
+        let subscriber = tracing_subscriber::registry().with(BuildNotificationLayer);
+        let _default = tracing::subscriber::set_default(subscriber);
+        let (foo, bar) = notifier
+            .clone()
+            .scope(async {
+                let foo = OutputBuild::start("foo");
+                let bar = OutputBuild::start("bar");
+                tokio::join!(
+                    foo.scope(async {
+                        tracing::info!("compiling foo");
+                        tokio::task::yield_now().await;
+                        let _span = tracing::info_span!("Packaging new files").entered();
+                    }),
+                    bar.scope(async {
+                        tokio::task::yield_now().await;
+                        tracing::info!("compiling bar");
+                    }),
+                );
+                (foo, bar)
+            })
+            .await;
+
+        assert_eq!(foo.phase(), Some(BuildPhase::Packaging));
+        assert_eq!(foo.log_tail(), vec![String::from("compiling foo")]);
+        assert_eq!(bar.phase(), None);
+        assert_eq!(bar.log_tail(), vec![String::from("compiling bar")]);
+
+        let params = received(&mut rx)
+            .into_iter()
+            .map(|notification| notification["params"].clone())
+            .collect::<Vec<_>>();
+        let of_output = |output: &str| {
+            params
+                .iter()
+                .filter(|params| params["output"] == output)
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            of_output("foo"),
+            vec![
+                json!({ "output": "foo", "stream": "stdout", "line": "compiling foo" }),
+                json!({ "output": "foo", "phase": "packaging" }),
+            ]
+        );
+        assert_eq!(
+            of_output("bar"),
+            vec![json!({ "output": "bar", "stream": "stdout", "line": "compiling bar" })]
+        );
+    }
+
+    #[test]
+    fn test_build_failure_uses_phase_and_log_tail() {
+        let build = OutputBuild::with_notifier(BuildNotifier::default(), "foo");
+        build.progress(BuildPhase::Building);
+        build.log(LogStream::Stdout, "compiling");
+
+        let failure = build.failure();
+        assert_eq!(failure.category, ErrorCategory::BuildScript);
+        assert_eq!(failure.log_excerpt, vec![String::from("compiling")]);
+
+        build.progress(BuildPhase::Packaging);
+        assert_eq!(build.failure().category, ErrorCategory::Packaging);
+    }
+
+    #[test]
+    fn test_frontend_capabilities_from_params() {
+        let params = Params::Map(
+            json!({ "capabilities": { "buildNotifications": true } })
+                .as_object()
+                .unwrap()
+                .clone(),
+        );
+        assert!(FrontendNotificationCapabilities::from_params(&params).build_notifications);
+
+        let params = Params::Map(json!({ "capabilities": {} }).as_object().unwrap().clone());
+        assert!(!FrontendNotificationCapabilities::from_params(&params).build_notifications);
+    }
+}
@@ -0,0 +1,292 @@
+//! An on-disk cache for the results of `conda/outputs`.
+//!
+//! Determining the outputs of a package requires generating the recipe and
+//! expanding all its variants, which can take a noticeable amount of time for
+//! workspaces with many source packages. The result only changes if the inputs
+//! of the request change, or if one of the files that the metadata was
+//! extracted from changes. The latter are described by the input globs of the
+//! result, which are hashed and stored alongside the cached result.
+
+use std::{
+    collections::BTreeSet,
+    io::Write,
+    path::{Component, Path, PathBuf},
+};
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use miette::{Context, IntoDiagnostic};
+use pixi_build_types::procedures::conda_outputs::CondaOutputsResult;
+use rattler_digest::{Sha256, digest::Digest};
+use serde::{Deserialize, Serialize};
+use walkdir::WalkDir;
+
+/// The name of the directory inside the cache directory that contains the
+/// cached results.
+const CACHE_DIR_NAME: &str = "conda-outputs-v1";
+
+/// Directories that are never searched for files that match the input globs.
+const IGNORED_DIRS: &[&str] = &[".git", ".pixi"];
+
+/// A cached result of `conda/outputs` for a specific set of inputs.
+pub struct CondaOutputsCache {
+    path: PathBuf,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CacheEntry {
+    /// The hash of the files that match the input globs of the result.
+    input_files_hash: String,
+    result: CondaOutputsResult,
+}
+
+impl CondaOutputsCache {
+    /// Constructs the cache entry in `cache_dir` for the given inputs. The
+    /// inputs should contain everything, except for the files matched by the
+    /// input globs, that influences the result.
+    pub fn new(cache_dir: &Path, inputs: &impl Serialize) -> miette::Result<Self> {
+        let inputs = serde_json::to_vec(inputs)
+            .into_diagnostic()
+            .context("failed to serialize the cache key")?;
+        let key = rattler_digest::compute_bytes_digest::<Sha256>(&inputs);
+        Ok(Self {
+            path: cache_dir.join(CACHE_DIR_NAME).join(format!("{key:x}.json")),
+        })
+    }
+
+    /// Returns the cached result if the files that match its input globs did
+    /// not change since it was stored.
+    pub fn get(&self, source_dir: &Path) -> Option<CondaOutputsResult> {
+        let contents = fs_err::read(&self.path).ok()?;
+        let entry: CacheEntry = match serde_json::from_slice(&contents) {
+            Ok(entry) => entry,
+            Err(err) => {
+                tracing::debug!(
+                    "ignoring invalid cache entry {}: {err}",
+                    self.path.display()
+                );
+                return None;
+            }
+        };
+
+        match hash_input_files(source_dir, &entry.result.input_globs) {
+            Ok(hash) if hash == entry.input_files_hash => Some(entry.result),
+            Ok(_) => None,
+            Err(err) => {
+                tracing::debug!("failed to hash the input files: {err}");
+                None
+            }
+        }
+    }
+
+    /// Stores `result` in the cache.
+    pub fn put(&self, source_dir: &Path, result: &CondaOutputsResult) -> miette::Result<()> {
+        #[derive(Serialize)]
+        #[serde(rename_all = "camelCase")]
+        struct CacheEntryRef<'a> {
+            input_files_hash: String,
+            result: &'a CondaOutputsResult,
+        }
+
+        let entry = CacheEntryRef {
+            input_files_hash: hash_input_files(source_dir, &result.input_globs)?,
+            result,
+        };
+
+        let dir = self
+            .path
+            .parent()
+            .expect("the cache entry is inside a directory");
+        fs_err::create_dir_all(dir).into_diagnostic()?;
+
+        // Write to a temporary file first so a concurrent reader never sees a
+        // partially written entry.
+        let mut file = tempfile::NamedTempFile::new_in(dir).into_diagnostic()?;
+        serde_json::to_writer(&mut file, &entry).into_diagnostic()?;
+        file.flush().into_diagnostic()?;
+        file.persist(&self.path)
+            .into_diagnostic()
+            .with_context(|| format!("failed to write {}", self.path.display()))?;
+        Ok(())
+    }
+}
+
+/// Computes a hash of the paths and contents of all files in `root` that
+/// match `globs`. Globs that start with `!` exclude files.
+///
+/// Only the directories that can contain matching files are searched, see
+/// [`walk_roots`].
+pub fn hash_input_files(root: &Path, globs: &BTreeSet<String>) -> miette::Result<String> {
+    let mut include = GlobSetBuilder::new();
+    let mut exclude = GlobSetBuilder::new();
+    for glob in globs {
+        match glob.strip_prefix('!') {
+            Some(glob) => exclude.add(Glob::new(glob).into_diagnostic()?),
+            None => include.add(Glob::new(glob).into_diagnostic()?),
+        };
+    }
+    let include: GlobSet = include.build().into_diagnostic()?;
+    let exclude: GlobSet = exclude.build().into_diagnostic()?;
+
+    let mut files = BTreeSet::new();
+    for walk_root in walk_roots(globs) {
+        let walk_root = root.join(walk_root);
+        if !walk_root.exists() {
+            continue;
+        }
+        let walker = WalkDir::new(&walk_root).into_iter().filter_entry(|entry| {
+            !(entry.file_type().is_dir()
+                && entry
+                    .file_name()
+                    .to_str()
+                    .is_some_and(|name| IGNORED_DIRS.contains(&name)))
+        });
+        for entry in walker {
+            let entry = entry.into_diagnostic()?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let relative_path = entry
+                .path()
+                .strip_prefix(root)
+                .expect("walkdir only returns paths inside the root");
+            if include.is_match(relative_path) && !exclude.is_match(relative_path) {
+                files.insert(relative_path.to_path_buf());
+            }
+        }
+    }
+
+    let mut hasher = Sha256::default();
+    for file in files {
+        let contents = fs_err::read(root.join(&file)).into_diagnostic()?;
+        hasher.update(file.to_string_lossy().replace('\\', "/").as_bytes());
+        hasher.update([0]);
+        hasher.update(rattler_digest::compute_bytes_digest::<Sha256>(&contents));
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Returns the paths, relative to the root, below which files can match one
+/// of the including `globs`. These are the leading components of the globs
+/// that do not contain wildcards. Paths that are nested inside another
+/// returned path are omitted, as are globs that point outside of the root.
+fn walk_roots(globs: &BTreeSet<String>) -> BTreeSet<PathBuf> {
+    let prefixes = globs
+        .iter()
+        .filter(|glob| !glob.starts_with('!'))
+        .map(|glob| {
+            glob.split('/')
+                .take_while(|component| !component.contains(['*', '?', '[', '{', '\\']))
+                .collect::<PathBuf>()
+        })
+        .filter(|prefix| {
+            prefix
+                .components()
+                .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+        })
+        .collect::<BTreeSet<_>>();
+
+    prefixes
+        .iter()
+        .filter(|prefix| {
+            !prefixes
+                .iter()
+                .any(|other| other != *prefix && prefix.starts_with(other))
+        })
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn result_with_globs(globs: &[&str]) -> CondaOutputsResult {
+        CondaOutputsResult {
+            outputs: vec![],
+            input_globs: globs.iter().map(|glob| glob.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_cache_hit_and_invalidation() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let source_dir = tempfile::tempdir().unwrap();
+        fs_err::write(source_dir.path().join("pyproject.toml"), "version = 1").unwrap();
+        fs_err::write(source_dir.path().join("README.md"), "hello").unwrap();
+
+        let cache = CondaOutputsCache::new(cache_dir.path(), &json!({ "name": "foo" })).unwrap();
+        assert!(cache.get(source_dir.path()).is_none());
+
+        cache
+            .put(source_dir.path(), &result_with_globs(&["pyproject.toml"]))
+            .unwrap();
+        assert!(cache.get(source_dir.path()).is_some());
+
+        // Files that do not match the globs do not invalidate the cache.
+        fs_err::write(source_dir.path().join("README.md"), "bye").unwrap();
+        assert!(cache.get(source_dir.path()).is_some());
+
+        fs_err::write(source_dir.path().join("pyproject.toml"), "version = 2").unwrap();
+        assert!(cache.get(source_dir.path()).is_none());
+    }
+
+    #[test]
+    fn test_different_inputs_use_different_entries() {
+        let cache_dir = tempfile::tempdir().unwrap();
+        let source_dir = tempfile::tempdir().unwrap();
+
+        CondaOutputsCache::new(cache_dir.path(), &json!({ "name": "foo" }))
+            .unwrap()
+            .put(source_dir.path(), &result_with_globs(&[]))
+            .unwrap();
+
+        let other = CondaOutputsCache::new(cache_dir.path(), &json!({ "name": "bar" })).unwrap();
+        assert!(other.get(source_dir.path()).is_none());
+    }
+
+    #[test]
+    fn test_hash_input_files_with_exclusions() {
+        let source_dir = tempfile::tempdir().unwrap();
+        fs_err::create_dir_all(source_dir.path().join("src")).unwrap();
+        fs_err::write(source_dir.path().join("src/lib.rs"), "").unwrap();
+        fs_err::write(source_dir.path().join("src/generated.rs"), "").unwrap();
+
+        let globs = BTreeSet::from([String::from("src/**"), String::from("!src/generated.rs")]);
+        let before = hash_input_files(source_dir.path(), &globs).unwrap();
+
+        fs_err::write(source_dir.path().join("src/generated.rs"), "changed").unwrap();
+        assert_eq!(hash_input_files(source_dir.path(), &globs).unwrap(), before);
+
+        fs_err::write(source_dir.path().join("src/lib.rs"), "changed").unwrap();
+        assert_ne!(hash_input_files(source_dir.path(), &globs).unwrap(), before);
+    }
+
+    #[test]
+    fn test_walk_roots_are_the_literal_prefixes_of_the_globs() {
+        let globs = [
+            "src/**/*.rs",
+            "src/lib/**",
+            "pyproject.toml",
+            "docs/*.md",
+            "!target/**",
+            "../shared/**",
+        ]
+        .into_iter()
+        .map(String::from)
+        .collect();
+        assert_eq!(
+            walk_roots(&globs),
+            BTreeSet::from([
+                PathBuf::from("docs"),
+                PathBuf::from("pyproject.toml"),
+                PathBuf::from("src"),
+            ])
+        );
+
+        let globs = BTreeSet::from([String::from("**/*.py"), String::from("src/**")]);
+        assert_eq!(walk_roots(&globs), BTreeSet::from([PathBuf::new()]));
+    }
+}
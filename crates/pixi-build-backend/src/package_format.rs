@@ -0,0 +1,150 @@
+//! The archive format and compression level of the packages that are built.
+
+use std::{fmt::Display, str::FromStr};
+
+use rattler_build::types::PackagingSettings;
+use rattler_conda_types::{compression_level::CompressionLevel, package::ArchiveType};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// The format of the package archive and the compression level that is used
+/// to create it.
+///
+/// This is written as `<archive-type>[:<compression-level>]`, e.g. `conda`,
+/// `tar-bz2:max` or `conda:3`. The compression level is either `min`, `max`,
+/// `default` or a number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PackageFormat {
+    /// The type of archive to create.
+    pub archive_type: ArchiveType,
+
+    /// The compression level to use when creating the archive.
+    pub compression_level: CompressionLevel,
+}
+
+impl Default for PackageFormat {
+    fn default() -> Self {
+        Self {
+            archive_type: ArchiveType::Conda,
+            compression_level: CompressionLevel::Default,
+        }
+    }
+}
+
+impl PackageFormat {
+    /// Returns the [`PackagingSettings`] that rattler-build uses to create the
+    /// package.
+    pub fn packaging_settings(&self) -> PackagingSettings {
+        PackagingSettings::from_args(self.archive_type, self.compression_level)
+    }
+}
+
+impl FromStr for PackageFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (archive_type, compression_level) = match s.split_once(':') {
+            Some((archive_type, compression_level)) => (archive_type, Some(compression_level)),
+            None => (s, None),
+        };
+
+        let archive_type = match archive_type {
+            "conda" | ".conda" => ArchiveType::Conda,
+            "tar-bz2" | "tar.bz2" | ".tar.bz2" => ArchiveType::TarBz2,
+            _ => {
+                return Err(format!(
+                    "unknown archive type '{archive_type}', expected 'conda' or 'tar-bz2'"
+                ));
+            }
+        };
+
+        let compression_level = match compression_level {
+            None | Some("default") => CompressionLevel::Default,
+            Some("min") => CompressionLevel::Lowest,
+            Some("max") => CompressionLevel::Highest,
+            Some(level) => {
+                let level = level.parse().map_err(|_| {
+                    format!(
+                        "invalid compression level '{level}', expected 'min', 'max', 'default' or a number"
+                    )
+                })?;
+                let (min, max) = match archive_type {
+                    ArchiveType::TarBz2 => (1, 9),
+                    ArchiveType::Conda => (-7, 22),
+                };
+                if !(min..=max).contains(&level) {
+                    return Err(format!(
+                        "compression level {level} is out of range, expected a value between {min} and {max}"
+                    ));
+                }
+                CompressionLevel::Numeric(level)
+            }
+        };
+
+        Ok(Self {
+            archive_type,
+            compression_level,
+        })
+    }
+}
+
+impl Display for PackageFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.archive_type {
+            ArchiveType::Conda => write!(f, "conda")?,
+            ArchiveType::TarBz2 => write!(f, "tar-bz2")?,
+        }
+        match self.compression_level {
+            CompressionLevel::Default => Ok(()),
+            CompressionLevel::Lowest => write!(f, ":min"),
+            CompressionLevel::Highest => write!(f, ":max"),
+            CompressionLevel::Numeric(level) => write!(f, ":{level}"),
+        }
+    }
+}
+
+impl Serialize for PackageFormat {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for PackageFormat {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case("conda", ArchiveType::Conda, CompressionLevel::Default)]
+    #[case("tar-bz2", ArchiveType::TarBz2, CompressionLevel::Default)]
+    #[case("conda:max", ArchiveType::Conda, CompressionLevel::Highest)]
+    #[case("tar-bz2:min", ArchiveType::TarBz2, CompressionLevel::Lowest)]
+    #[case("conda:-3", ArchiveType::Conda, CompressionLevel::Numeric(-3))]
+    #[case("tar-bz2:9", ArchiveType::TarBz2, CompressionLevel::Numeric(9))]
+    fn test_parse_package_format(
+        #[case] input: &str,
+        #[case] archive_type: ArchiveType,
+        #[case] compression_level: CompressionLevel,
+    ) {
+        let format: PackageFormat = input.parse().unwrap();
+        assert_eq!(format.archive_type, archive_type);
+        assert_eq!(format.compression_level, compression_level);
+        assert_eq!(format.to_string(), input);
+    }
+
+    #[rstest]
+    #[case("zip")]
+    #[case("conda:fast")]
+    #[case("conda:23")]
+    #[case("tar-bz2:0")]
+    fn test_parse_invalid_package_format(#[case] input: &str) {
+        assert!(input.parse::<PackageFormat>().is_err());
+    }
+}
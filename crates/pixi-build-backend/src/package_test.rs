@@ -0,0 +1,69 @@
+//! Running the tests of a recipe against a freshly built package.
+//!
+//! rattler-build only runs the `package_contents` checks while packaging. The
+//! remaining tests (scripts, python imports, downstream tests, ...) require a
+//! separate test environment which is set up here.
+
+use std::path::{Path, PathBuf};
+
+use miette::Diagnostic;
+use rattler_build::{
+    metadata::Output,
+    package_test::{TestConfiguration, run_test},
+    tool_configuration::Configuration,
+};
+use thiserror::Error;
+
+/// An error that occurred while running the tests of a package.
+#[derive(Debug, Error, Diagnostic)]
+#[error("the tests for the package '{output_name}' failed")]
+#[diagnostic(
+    code(pixi_build_backend::package_test_failed),
+    help("the package was built successfully and written to {}", package_file.display())
+)]
+pub struct PackageTestError {
+    /// The name of the output that was tested.
+    pub output_name: String,
+
+    /// The location of the package that was tested.
+    pub package_file: PathBuf,
+
+    #[source]
+    pub source: rattler_build::package_test::TestError,
+}
+
+/// Runs all the tests defined in the recipe of `output` against the package
+/// located at `package_file`.
+///
+/// The test environments are resolved from the channels of the build
+/// configuration of the output.
+pub async fn run_package_tests(
+    output: &Output,
+    package_file: &Path,
+    tool_configuration: &Configuration,
+) -> Result<(), PackageTestError> {
+    let build_configuration = &output.build_configuration;
+    let test_configuration = TestConfiguration {
+        test_prefix: build_configuration.directories.build_dir.join("test"),
+        target_platform: Some(build_configuration.target_platform),
+        host_platform: Some(build_configuration.host_platform.clone()),
+        current_platform: build_configuration.build_platform.clone(),
+        keep_test_prefix: false,
+        test_index: None,
+        channels: build_configuration.channels.clone(),
+        channel_priority: build_configuration.channel_priority,
+        solve_strategy: build_configuration.solve_strategy,
+        tool_configuration: tool_configuration.clone(),
+        output_dir: build_configuration.directories.output_dir.clone(),
+        debug: build_configuration.debug,
+        exclude_newer: build_configuration.exclude_newer,
+    };
+
+    run_test(package_file, &test_configuration, None)
+        .await
+        .map_err(|source| PackageTestError {
+            output_name: output.name().as_source().to_string(),
+            package_file: package_file.to_path_buf(),
+            source,
+        })
+}
@@ -1,19 +1,42 @@
-use pixi_build_types::procedures::conda_build_v1::{CondaBuildV1Params, CondaBuildV1Result};
-use pixi_build_types::procedures::conda_outputs::{CondaOutputsParams, CondaOutputsResult};
+use std::collections::BTreeMap;
+
+use miette::Diagnostic;
+use pixi_build_types::procedures::conda_build_v1::{self, CondaBuildV1Params, CondaBuildV1Result};
+use pixi_build_types::procedures::conda_outputs::{self, CondaOutputsParams, CondaOutputsResult};
 use pixi_build_types::procedures::{
     initialize::{InitializeParams, InitializeResult},
     negotiate_capabilities::{NegotiateCapabilitiesParams, NegotiateCapabilitiesResult},
 };
+use pixi_build_types::{BackendCapabilities, VariantValue};
+use rattler_conda_types::Platform;
+use thiserror::Error;
+
+use crate::generated_recipe::GeneratedRecipe;
+
+#[doc(hidden)]
+pub use async_trait::async_trait as __async_trait;
+#[doc(hidden)]
+pub use pixi_build_types::procedures as __procedures;
 
 /// A trait that is used to instantiate a new protocol connection
 /// and endpoint that can handle the RPC calls.
 #[async_trait::async_trait]
 pub trait ProtocolInstantiator: Send + Sync + 'static {
+    /// The protocol that is instantiated by [`Self::initialize`].
+    type Protocol: Protocol + Send + Sync + 'static;
+
     /// Called when negotiating capabilities with the client.
     /// This is determine how the rest of the initialization will proceed.
+    ///
+    /// By default the capabilities are derived from the methods that are
+    /// implemented by [`Self::Protocol`], see [`backend_capabilities`].
     async fn negotiate_capabilities(
-        params: NegotiateCapabilitiesParams,
-    ) -> miette::Result<NegotiateCapabilitiesResult>;
+        _params: NegotiateCapabilitiesParams,
+    ) -> miette::Result<NegotiateCapabilitiesResult> {
+        Ok(NegotiateCapabilitiesResult {
+            capabilities: backend_capabilities::<Self::Protocol>(),
+        })
+    }
 
     /// Called when the client requests initialization.
     /// Returns the protocol endpoint and the result of the initialization.
@@ -21,19 +44,56 @@ pub trait ProtocolInstantiator: Send + Sync + 'static {
         &self,
         params: InitializeParams,
     ) -> miette::Result<(Box<dyn Protocol + Send + Sync + 'static>, InitializeResult)>;
+
+    /// Generates the recipe of a package without building it. This is used by
+    /// the `generate-recipe` subcommand to inspect what a backend generates.
+    async fn generate_recipe(
+        &self,
+        _params: GenerateRecipeParams,
+    ) -> miette::Result<GeneratedRecipe> {
+        miette::bail!("this backend does not generate recipes")
+    }
+}
+
+/// The parameters of [`ProtocolInstantiator::generate_recipe`].
+pub struct GenerateRecipeParams {
+    /// The parameters the backend would be initialized with.
+    pub initialize: InitializeParams,
+
+    /// The platform to generate the recipe for.
+    pub host_platform: Platform,
+
+    /// Variants that take precedence over the default variants of the
+    /// backend.
+    pub variant_configuration: Option<BTreeMap<String, Vec<VariantValue>>>,
 }
 
 /// A trait that defines the protocol for a pixi build backend.
 /// These are implemented by the different backends. Which
 /// server as an endpoint for the RPC calls.
+///
+/// Methods that are not implemented by a backend respond with a
+/// [`MethodNotImplemented`] error.
+///
+/// The [`implement_protocol!`](crate::implement_protocol) macro implements
+/// this trait and generates [`Self::implemented_methods`] from the methods
+/// that are defined in the `impl` block.
 #[async_trait::async_trait]
 pub trait Protocol {
+    /// The names of the methods that are implemented by this protocol, e.g.
+    /// [`conda_outputs::METHOD_NAME`]. This must list every method that is
+    /// overridden, it determines the capabilities that are reported to the
+    /// client.
+    fn implemented_methods() -> &'static [&'static str]
+    where
+        Self: Sized;
+
     /// Called when the client requests outputs for a Conda package.
     async fn conda_outputs(
         &self,
         _params: CondaOutputsParams,
     ) -> miette::Result<CondaOutputsResult> {
-        unimplemented!("conda_outputs not implemented");
+        Err(MethodNotImplemented(conda_outputs::METHOD_NAME).into())
     }
 
     /// Called when the client calls `conda/build_v1`.
@@ -41,6 +101,112 @@ pub trait Protocol {
         &self,
         _params: CondaBuildV1Params,
     ) -> miette::Result<CondaBuildV1Result> {
-        unimplemented!("conda_build_v1 not implemented");
+        Err(MethodNotImplemented(conda_build_v1::METHOD_NAME).into())
+    }
+}
+
+/// The error that is returned when the client calls a method that is not
+/// implemented by the backend. This is reported to the client with the
+/// JSON-RPC `MethodNotFound` error code.
+#[derive(Debug, Error, Diagnostic)]
+#[error("the backend does not implement `{0}`")]
+pub struct MethodNotImplemented(pub &'static str);
+
+/// Implements [`Protocol`] for a type and derives the methods that are
+/// reported to the client as capabilities, see [`backend_capabilities`], from
+/// the methods that are defined in the `impl` block.
+///
+/// ```ignore
+/// pixi_build_backend::implement_protocol! {
+///     impl Protocol for MyBackend {
+///         async fn conda_outputs(
+///             &self,
+///             params: CondaOutputsParams,
+///         ) -> miette::Result<CondaOutputsResult> {
+///             todo!()
+///         }
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! implement_protocol {
+    (impl $($rest:tt)+) => {
+        $crate::implement_protocol!(@header [impl] $($rest)+);
+    };
+    (@header [$($header:tt)*] {
+        $($(#[$meta:meta])* async fn $method:ident $args:tt -> $ret:ty $body:block)*
+    }) => {
+        #[$crate::protocol::__async_trait]
+        $($header)* {
+            fn implemented_methods() -> &'static [&'static str] {
+                &[$($crate::protocol::__procedures::$method::METHOD_NAME),*]
+            }
+
+            $($(#[$meta])* async fn $method $args -> $ret $body)*
+        }
+    };
+    (@header [$($header:tt)*] $token:tt $($rest:tt)+) => {
+        $crate::implement_protocol!(@header [$($header)* $token] $($rest)+);
+    };
+}
+
+/// Returns the capabilities of a backend that uses the protocol `P`.
+pub fn backend_capabilities<P: Protocol>() -> BackendCapabilities {
+    let implemented_methods = P::implemented_methods();
+    BackendCapabilities {
+        provides_conda_outputs: Some(implemented_methods.contains(&conda_outputs::METHOD_NAME)),
+        provides_conda_build_v1: Some(implemented_methods.contains(&conda_build_v1::METHOD_NAME)),
+        highest_supported_project_model: Some(
+            pixi_build_types::VersionedProjectModel::highest_version(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct OutputsOnly;
+
+    crate::implement_protocol! {
+        impl Protocol for OutputsOnly {
+            async fn conda_outputs(
+                &self,
+                _params: CondaOutputsParams,
+            ) -> miette::Result<CondaOutputsResult> {
+                miette::bail!("not needed by the tests")
+            }
+        }
+    }
+
+    #[test]
+    fn test_capabilities_are_derived_from_implemented_methods() {
+        let capabilities = backend_capabilities::<OutputsOnly>();
+        assert_eq!(capabilities.provides_conda_outputs, Some(true));
+        assert_eq!(capabilities.provides_conda_build_v1, Some(false));
+    }
+
+    #[tokio::test]
+    async fn test_unimplemented_method_returns_error() {
+        let params = CondaBuildV1Params {
+            channels: vec![],
+            build_prefix: None,
+            host_prefix: None,
+            run_constraints: None,
+            run_dependencies: None,
+            run_exports: None,
+            output: conda_build_v1::CondaBuildV1Output {
+                name: "foo".parse().unwrap(),
+                version: None,
+                build: None,
+                subdir: rattler_conda_types::Platform::NoArch,
+                variant: Default::default(),
+            },
+            work_directory: std::path::PathBuf::from("work"),
+            output_directory: None,
+            editable: None,
+        };
+        let err = OutputsOnly.conda_build_v1(params).await.unwrap_err();
+        assert!(err.downcast_ref::<MethodNotImplemented>().is_some());
     }
 }
@@ -44,7 +44,7 @@ pub async fn get_build_output(
         code: Arc::from(
             generated_recipe
                 .recipe
-                .to_yaml_pretty()
+                .to_rattler_build_yaml()
                 .into_diagnostic()?
                 .as_str(),
         ),
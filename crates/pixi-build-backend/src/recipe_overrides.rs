@@ -0,0 +1,473 @@
+//! Overrides for generated recipes.
+//!
+//! The `recipe-overrides` option of the backend configuration points to a
+//! YAML file with a partial recipe that is merged onto the generated recipe.
+//! This allows fixing small mistakes in a generated recipe, like a wrong
+//! license or a missing test, without writing the full recipe by hand.
+//!
+//! The overrides are merged as follows:
+//!
+//! - Mappings are merged recursively.
+//! - Lists are appended to the generated list. To replace a list instead,
+//!   wrap it in a mapping with a single [`REPLACE_KEY`] key.
+//! - All other values replace the generated value.
+//!
+//! ```yaml
+//! about:
+//!   license: MIT
+//! requirements:
+//!   host:
+//!     - extra-dependency
+//!   run:
+//!     $replace:
+//!       - foo >=1.0
+//! ```
+
+use std::path::{Path, PathBuf};
+
+use marked_yaml::{Node, Span};
+use miette::{Diagnostic, NamedSource, SourceSpan};
+use recipe_stage0::recipe::IntermediateRecipe;
+use serde_yaml::Value;
+use thiserror::Error;
+
+/// The key that replaces a list instead of appending to it.
+pub const REPLACE_KEY: &str = "$replace";
+
+/// The top-level sections of a recipe that can be overridden.
+const RECIPE_SECTIONS: &[&str] = &[
+    "context",
+    "package",
+    "recipe",
+    "source",
+    "build",
+    "requirements",
+    "tests",
+    "about",
+    "extra",
+    "cache",
+    "outputs",
+];
+
+/// An error that occurred while applying recipe overrides.
+#[derive(Debug, Error, Diagnostic)]
+pub enum RecipeOverridesError {
+    #[error("failed to read the recipe overrides from '{}'", path.display())]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("failed to parse the recipe overrides")]
+    Parse {
+        #[source_code]
+        src: NamedSource<String>,
+        #[label("{message}")]
+        span: SourceSpan,
+        message: String,
+    },
+
+    #[error("invalid recipe overrides")]
+    Invalid {
+        #[source_code]
+        src: NamedSource<String>,
+        #[label("{message}")]
+        span: SourceSpan,
+        message: String,
+        #[help]
+        help: Option<String>,
+    },
+}
+
+/// A partial recipe that is merged onto a generated recipe.
+#[derive(Debug)]
+pub struct RecipeOverrides {
+    path: PathBuf,
+    source: String,
+    value: Value,
+    /// The same document with source locations, used for error reporting.
+    node: Option<Node>,
+}
+
+impl RecipeOverrides {
+    /// Reads the recipe overrides from a YAML file.
+    pub fn from_path(path: &Path) -> Result<Self, RecipeOverridesError> {
+        let source = fs_err::read_to_string(path).map_err(|source| RecipeOverridesError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        Self::from_source(path.to_path_buf(), source)
+    }
+
+    /// Parses the recipe overrides from the contents of `path`.
+    pub fn from_source(path: PathBuf, source: String) -> Result<Self, RecipeOverridesError> {
+        let value = match serde_yaml::from_str(&source) {
+            Ok(value) => value,
+            Err(err) => {
+                let offset = err.location().map_or(0, |location| location.index());
+                return Err(RecipeOverridesError::Parse {
+                    span: SourceSpan::from((offset, 0)),
+                    message: err.to_string(),
+                    src: NamedSource::new(path.display().to_string(), source),
+                });
+            }
+        };
+        let node = marked_yaml::parse_yaml(0, &source).ok();
+        Ok(Self {
+            path,
+            source,
+            value,
+            node,
+        })
+    }
+
+    /// Merges the overrides onto `recipe` and returns the resulting recipe.
+    pub fn apply(
+        &self,
+        recipe: &IntermediateRecipe,
+    ) -> Result<IntermediateRecipe, RecipeOverridesError> {
+        let overrides = match &self.value {
+            Value::Null => return Ok(recipe.clone()),
+            Value::Mapping(overrides) => overrides,
+            _ => {
+                return Err(self.invalid(
+                    self.node.as_ref().map(Node::span),
+                    "expected a mapping of recipe sections",
+                    None,
+                ));
+            }
+        };
+
+        let mut merged = serde_yaml::to_value(recipe).expect("a recipe can always be serialized");
+        let mut result = recipe.clone();
+        for (key, value) in overrides {
+            let Some(section) = key.as_str().filter(|key| RECIPE_SECTIONS.contains(key)) else {
+                return Err(self.invalid(
+                    key_span(self.node.as_ref(), key),
+                    "unknown recipe section",
+                    Some(format!("expected one of {}", RECIPE_SECTIONS.join(", "))),
+                ));
+            };
+            let node = child(self.node.as_ref(), section);
+
+            // Multi-output recipes store the package as `recipe`.
+            let section = match section {
+                "package" | "recipe" if merged.get("recipe").is_some() => "recipe",
+                "package" | "recipe" => "package",
+                section => section,
+            };
+            let Value::Mapping(merged_sections) = &mut merged else {
+                unreachable!("a recipe is always serialized as a mapping")
+            };
+            let target = merged_sections
+                .entry(Value::from(section))
+                .or_insert(Value::Null);
+            self.merge(target, value, node)?;
+
+            // Validate after every section so that errors point at the section
+            // that caused them.
+            result = serde_yaml::from_value(merged.clone()).map_err(|err| {
+                self.invalid(
+                    node.map(Node::span),
+                    &format!("the overrides result in an invalid `{section}` section: {err}"),
+                    None,
+                )
+            })?;
+            let normalized =
+                serde_yaml::to_value(&result).expect("a recipe can always be serialized");
+            self.check_known_keys(normalized.get(section).unwrap_or(&Value::Null), value, node)?;
+        }
+
+        Ok(result)
+    }
+
+    fn merge(
+        &self,
+        base: &mut Value,
+        overlay: &Value,
+        node: Option<&Node>,
+    ) -> Result<(), RecipeOverridesError> {
+        match overlay {
+            Value::Mapping(overlay_map) if overlay_map.contains_key(REPLACE_KEY) => {
+                if overlay_map.len() != 1 {
+                    return Err(self.invalid(
+                        node.map(Node::span),
+                        &format!("`{REPLACE_KEY}` cannot be combined with other keys"),
+                        None,
+                    ));
+                }
+                *base = overlay_map[REPLACE_KEY].clone();
+            }
+            Value::Mapping(overlay_map) => {
+                if base.is_null() {
+                    *base = Value::Mapping(Default::default());
+                }
+                let Value::Mapping(base_map) = base else {
+                    return Err(self.invalid(
+                        node.map(Node::span),
+                        &format!("expected {}, found a mapping", describe(base)),
+                        None,
+                    ));
+                };
+                for (key, value) in overlay_map {
+                    let child_node = key.as_str().and_then(|key| child(node, key));
+                    let target = base_map.entry(key.clone()).or_insert(Value::Null);
+                    self.merge(target, value, child_node)?;
+                }
+            }
+            Value::Sequence(items) => match base {
+                Value::Sequence(base_items) => base_items.extend(items.iter().cloned()),
+                Value::Null => *base = overlay.clone(),
+                _ => {
+                    return Err(self.invalid(
+                        node.map(Node::span),
+                        &format!("expected {}, found a list", describe(base)),
+                        None,
+                    ));
+                }
+            },
+            _ => *base = overlay.clone(),
+        }
+        Ok(())
+    }
+
+    /// Reports keys of the overrides that are not part of the resulting
+    /// recipe. These would otherwise be silently ignored.
+    fn check_known_keys(
+        &self,
+        result: &Value,
+        overlay: &Value,
+        node: Option<&Node>,
+    ) -> Result<(), RecipeOverridesError> {
+        let Value::Mapping(overlay_map) = overlay else {
+            return Ok(());
+        };
+        if overlay_map.contains_key(REPLACE_KEY) {
+            return Ok(());
+        }
+        for (key, value) in overlay_map {
+            if is_empty(value) {
+                continue;
+            }
+            let Some(result_value) = result.get(key) else {
+                return Err(self.invalid(
+                    key_span(node, key),
+                    "unknown key, it would be ignored",
+                    None,
+                ));
+            };
+            let child_node = key.as_str().and_then(|key| child(node, key));
+            self.check_known_keys(result_value, value, child_node)?;
+        }
+        Ok(())
+    }
+
+    fn invalid(
+        &self,
+        span: Option<&Span>,
+        message: &str,
+        help: Option<String>,
+    ) -> RecipeOverridesError {
+        RecipeOverridesError::Invalid {
+            src: NamedSource::new(self.path.display().to_string(), self.source.clone()),
+            span: span
+                .map(|span| source_span(&self.source, span))
+                .unwrap_or_else(|| SourceSpan::from((0, 0))),
+            message: message.to_string(),
+            help,
+        }
+    }
+}
+
+fn child<'a>(node: Option<&'a Node>, key: &str) -> Option<&'a Node> {
+    node?.as_mapping()?.get_node(key)
+}
+
+fn key_span<'a>(node: Option<&'a Node>, key: &Value) -> Option<&'a Span> {
+    let key = key.as_str()?;
+    node?
+        .as_mapping()?
+        .iter()
+        .find(|(candidate, _)| candidate.as_str() == key)
+        .map(|(candidate, _)| candidate.span())
+}
+
+/// Converts a span of a marked YAML node to a span in `source`.
+fn source_span(source: &str, span: &Span) -> SourceSpan {
+    let Some(start) = span.start() else {
+        return SourceSpan::from((0, 0));
+    };
+    let start = offset(source, start.line(), start.column());
+    let end = span
+        .end()
+        .map_or(start, |end| offset(source, end.line(), end.column()));
+    SourceSpan::from((start, end.saturating_sub(start)))
+}
+
+/// Converts a 1-based line and column to a byte offset in `source`.
+fn offset(source: &str, line: usize, column: usize) -> usize {
+    let line_start: usize = source
+        .split_inclusive('\n')
+        .take(line.saturating_sub(1))
+        .map(str::len)
+        .sum();
+    let line_text = &source[line_start..];
+    line_start
+        + line_text
+            .char_indices()
+            .nth(column.saturating_sub(1))
+            .map_or(line_text.len(), |(index, _)| index)
+}
+
+fn describe(value: &Value) -> &'static str {
+    match value {
+        Value::Mapping(_) => "a mapping",
+        Value::Sequence(_) => "a list",
+        _ => "a value",
+    }
+}
+
+fn is_empty(value: &Value) -> bool {
+    match value {
+        Value::Null => true,
+        Value::Mapping(mapping) => mapping.is_empty(),
+        Value::Sequence(sequence) => sequence.is_empty(),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indexmap::IndexMap;
+
+    use super::*;
+    use crate::specs_conversion::from_constraint_specs;
+
+    const RECIPE: &str = r#"
+package:
+  name: foo
+  version: 0.1.0
+requirements:
+  host:
+    - python
+  run:
+    - python
+    - numpy
+about:
+  license: BSD-3-Clause
+"#;
+
+    fn apply(overrides: &str) -> Result<IntermediateRecipe, RecipeOverridesError> {
+        let recipe = IntermediateRecipe::from_yaml(RECIPE).unwrap();
+        RecipeOverrides::from_source(PathBuf::from("overrides.yaml"), overrides.to_string())?
+            .apply(&recipe)
+    }
+
+    fn names<T: ToString>(items: &[T]) -> Vec<String> {
+        items.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn test_merge_semantics() {
+        let recipe = apply(
+            r#"
+about:
+  license: MIT
+requirements:
+  host:
+    - pip
+  run:
+    $replace:
+      - numpy >=2
+  ignore_run_exports:
+    from_package:
+      - libfoo
+tests:
+  - python:
+      imports:
+        - foo
+"#,
+        )
+        .unwrap();
+
+        let about = recipe.about.unwrap();
+        assert_eq!(about.license.unwrap().to_string(), "MIT");
+        assert_eq!(names(&recipe.requirements.host), ["python", "pip"]);
+        assert_eq!(names(&recipe.requirements.run), ["numpy >=2"]);
+        assert_eq!(
+            recipe.requirements.ignore_run_exports.from_package,
+            ["libfoo"]
+        );
+        assert_eq!(recipe.tests.len(), 1);
+        assert_eq!(recipe.package.name.to_string(), "foo");
+    }
+
+    #[test]
+    fn test_constraints_are_kept() {
+        let mut recipe = IntermediateRecipe::from_yaml(RECIPE).unwrap();
+        recipe.requirements.build_constraints =
+            from_constraint_specs(&IndexMap::from([("cmake".to_string(), "<4".to_string())]))
+                .unwrap();
+        recipe.requirements.host_constraints =
+            from_constraint_specs(&IndexMap::from([("numpy".to_string(), ">=2".to_string())]))
+                .unwrap();
+
+        let recipe = RecipeOverrides::from_source(
+            PathBuf::from("overrides.yaml"),
+            "about:\n  license: MIT\n".to_string(),
+        )
+        .unwrap()
+        .apply(&recipe)
+        .unwrap();
+
+        assert_eq!(names(&recipe.requirements.build_constraints), ["cmake <4"]);
+        assert_eq!(names(&recipe.requirements.host_constraints), ["numpy >=2"]);
+    }
+
+    #[test]
+    fn test_empty_overrides() {
+        let recipe = apply("").unwrap();
+        assert_eq!(names(&recipe.requirements.run), ["python", "numpy"]);
+    }
+
+    #[test]
+    fn test_unknown_section() {
+        let err = apply("requirement:\n  run:\n    - foo\n").unwrap_err();
+        let RecipeOverridesError::Invalid { span, .. } = err else {
+            panic!("expected an invalid overrides error, got {err:?}");
+        };
+        assert_eq!(span.offset(), 0);
+    }
+
+    #[test]
+    fn test_unknown_nested_key() {
+        let err = apply("about:\n  licence: MIT\n").unwrap_err();
+        let RecipeOverridesError::Invalid { span, message, .. } = err else {
+            panic!("expected an invalid overrides error, got {err:?}");
+        };
+        assert!(message.contains("unknown key"));
+        assert_eq!(span.offset(), "about:\n  ".len());
+    }
+
+    #[test]
+    fn test_type_mismatch() {
+        let err = apply("requirements:\n  run:\n    foo: bar\n").unwrap_err();
+        let RecipeOverridesError::Invalid { message, .. } = err else {
+            panic!("expected an invalid overrides error, got {err:?}");
+        };
+        assert!(message.contains("expected a list"), "{message}");
+    }
+
+    #[test]
+    fn test_replace_with_other_keys() {
+        let err = apply("tests:\n  $replace: []\n  other: []\n").unwrap_err();
+        assert!(matches!(err, RecipeOverridesError::Invalid { .. }));
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let err = apply("about: [\n").unwrap_err();
+        assert!(matches!(err, RecipeOverridesError::Parse { .. }));
+    }
+}
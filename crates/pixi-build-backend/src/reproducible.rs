@@ -0,0 +1,168 @@
+//! Support for reproducible builds.
+//!
+//! A build is reproducible if building the same sources twice results in
+//! bit-for-bit identical packages. This requires a fixed timestamp for the
+//! package metadata and the files in the archive, as well as a normalized
+//! environment for the build script.
+//!
+//! See <https://reproducible-builds.org/docs/source-date-epoch/>.
+
+use std::{path::Path, process::Command};
+
+use chrono::{DateTime, Utc};
+use indexmap::IndexMap;
+use miette::Diagnostic;
+use rattler_build::recipe::Recipe;
+use thiserror::Error;
+
+/// The name of the environment variable that holds the timestamp to use for
+/// reproducible builds, as seconds since the UNIX epoch.
+pub const SOURCE_DATE_EPOCH: &str = "SOURCE_DATE_EPOCH";
+
+/// An error that occurred while determining the timestamp of a reproducible
+/// build.
+#[derive(Debug, Error, Diagnostic)]
+pub enum SourceDateEpochError {
+    #[error("the value of `SOURCE_DATE_EPOCH` ('{0}') is not a valid number of seconds")]
+    #[diagnostic(help("`SOURCE_DATE_EPOCH` must be an integer, e.g. `1700000000`"))]
+    InvalidEnvironmentVariable(String),
+
+    #[error("the timestamp {0} is out of range")]
+    OutOfRange(i64),
+}
+
+/// Determines the timestamp to use for a reproducible build.
+///
+/// The timestamp is taken from the first of the following that is available:
+///
+/// 1. The `SOURCE_DATE_EPOCH` environment variable.
+/// 2. The `source-date-epoch` value from the backend configuration.
+/// 3. The time of the last commit if `source_dir` is part of a git
+///    repository.
+///
+/// If none of these are available the UNIX epoch is used.
+pub fn source_date_epoch(
+    configured: Option<i64>,
+    source_dir: &Path,
+) -> Result<DateTime<Utc>, SourceDateEpochError> {
+    resolve_source_date_epoch(
+        std::env::var(SOURCE_DATE_EPOCH).ok(),
+        configured,
+        source_dir,
+    )
+}
+
+fn resolve_source_date_epoch(
+    from_env: Option<String>,
+    configured: Option<i64>,
+    source_dir: &Path,
+) -> Result<DateTime<Utc>, SourceDateEpochError> {
+    let seconds = match from_env.filter(|value| !value.trim().is_empty()) {
+        Some(value) => value
+            .trim()
+            .parse()
+            .map_err(|_| SourceDateEpochError::InvalidEnvironmentVariable(value))?,
+        None => match configured.or_else(|| git_commit_time(source_dir)) {
+            Some(seconds) => seconds,
+            None => {
+                tracing::warn!(
+                    "could not determine a timestamp for the reproducible build, set `{SOURCE_DATE_EPOCH}` or `source-date-epoch` to provide one. Falling back to the UNIX epoch."
+                );
+                0
+            }
+        },
+    };
+
+    DateTime::from_timestamp(seconds, 0).ok_or(SourceDateEpochError::OutOfRange(seconds))
+}
+
+/// Returns the commit time of `HEAD` of the git repository that contains
+/// `dir`, or `None` if `dir` is not part of a git repository.
+fn git_commit_time(dir: &Path) -> Option<i64> {
+    let output = Command::new("git")
+        .args(["log", "-1", "--format=%ct"])
+        .current_dir(dir)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8(output.stdout).ok()?.trim().parse().ok()
+}
+
+/// Returns the environment variables that are passed to the build script of a
+/// reproducible build.
+///
+/// These pin the timestamp, timezone, locale and hash seeds that tools
+/// commonly embed in their outputs.
+pub fn normalized_build_env(timestamp: DateTime<Utc>) -> IndexMap<String, String> {
+    IndexMap::from_iter(
+        [
+            (SOURCE_DATE_EPOCH, timestamp.timestamp().to_string()),
+            ("TZ", String::from("UTC")),
+            ("LC_ALL", String::from("C")),
+            ("LANG", String::from("C")),
+            ("PYTHONHASHSEED", String::from("0")),
+            // Prevents `ar` on macOS from embedding the current time.
+            ("ZERO_AR_DATE", String::from("1")),
+        ]
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value)),
+    )
+}
+
+/// Adds the normalized environment of a reproducible build to the build
+/// script of `recipe`. Variables that are explicitly set by the recipe take
+/// precedence.
+pub fn apply_normalized_build_env(recipe: &mut Recipe, timestamp: DateTime<Utc>) {
+    for (key, value) in normalized_build_env(timestamp) {
+        recipe.build.script.env.entry(key).or_insert(value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_environment_variable_takes_precedence() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let timestamp =
+            resolve_source_date_epoch(Some(String::from("1700000000")), Some(42), temp_dir.path())
+                .unwrap();
+        assert_eq!(timestamp.timestamp(), 1700000000);
+    }
+
+    #[test]
+    fn test_configured_value_is_used() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let timestamp = resolve_source_date_epoch(None, Some(42), temp_dir.path()).unwrap();
+        assert_eq!(timestamp.timestamp(), 42);
+    }
+
+    #[test]
+    fn test_falls_back_to_unix_epoch() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let timestamp = resolve_source_date_epoch(None, None, temp_dir.path()).unwrap();
+        assert_eq!(timestamp.timestamp(), 0);
+    }
+
+    #[test]
+    fn test_invalid_environment_variable() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let result =
+            resolve_source_date_epoch(Some(String::from("yesterday")), None, temp_dir.path());
+        assert!(matches!(
+            result,
+            Err(SourceDateEpochError::InvalidEnvironmentVariable(_))
+        ));
+    }
+
+    #[test]
+    fn test_normalized_build_env() {
+        let timestamp = DateTime::from_timestamp(1700000000, 0).unwrap();
+        let env = normalized_build_env(timestamp);
+        assert_eq!(env[SOURCE_DATE_EPOCH], "1700000000");
+        assert_eq!(env["TZ"], "UTC");
+    }
+}
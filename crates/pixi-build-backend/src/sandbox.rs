@@ -0,0 +1,88 @@
+//! Configuration of the sandbox in which the build script is executed.
+
+use std::path::{Path, PathBuf};
+
+use rattler_build::script::{SandboxArguments, SandboxConfiguration};
+use serde::{Deserialize, Serialize};
+
+/// Restricts the network and filesystem access of the build script.
+///
+/// The sandbox always allows access to the directories that are required for
+/// the build itself (e.g. the work directory and the prefixes). Additional
+/// paths can be made accessible through this configuration. Relative paths
+/// are resolved against the source directory of the package.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct SandboxConfig {
+    /// Whether the build script is allowed to access the network.
+    #[serde(default)]
+    pub allow_network: bool,
+
+    /// Additional paths that the build script is allowed to read.
+    #[serde(default)]
+    pub allow_read: Vec<PathBuf>,
+
+    /// Additional paths that the build script is allowed to read and execute.
+    #[serde(default)]
+    pub allow_read_execute: Vec<PathBuf>,
+
+    /// Additional paths that the build script is allowed to read and write.
+    #[serde(default)]
+    pub allow_read_write: Vec<PathBuf>,
+}
+
+impl SandboxConfig {
+    /// Converts this configuration into the sandbox configuration of
+    /// rattler-build.
+    pub fn to_rattler_build(&self, source_dir: &Path) -> Option<SandboxConfiguration> {
+        let resolve = |paths: &[PathBuf]| -> Vec<PathBuf> {
+            paths.iter().map(|path| source_dir.join(path)).collect()
+        };
+
+        SandboxArguments {
+            sandbox: true,
+            allow_network: self.allow_network,
+            allow_read: resolve(&self.allow_read),
+            allow_read_execute: resolve(&self.allow_read_execute),
+            allow_read_write: resolve(&self.allow_read_write),
+            overwrite_default_sandbox_config: false,
+        }
+        .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_deserialize_sandbox_config() {
+        let config: SandboxConfig = serde_json::from_value(json!({
+            "allow-network": true,
+            "allow-read": ["/opt/data", "vendor"],
+        }))
+        .unwrap();
+
+        assert!(config.allow_network);
+        assert_eq!(
+            config.allow_read,
+            vec![PathBuf::from("/opt/data"), PathBuf::from("vendor")]
+        );
+        assert!(config.allow_read_write.is_empty());
+    }
+
+    #[test]
+    fn test_network_is_denied_by_default() {
+        let config: SandboxConfig = serde_json::from_value(json!({})).unwrap();
+        assert!(!config.allow_network);
+        assert!(config.to_rattler_build(Path::new("/src")).is_some());
+    }
+
+    #[test]
+    fn test_unknown_fields_are_rejected() {
+        let result = serde_json::from_value::<SandboxConfig>(json!({ "network": true }));
+        assert!(result.is_err());
+    }
+}
@@ -1,7 +1,8 @@
-use std::{net::SocketAddr, path::Path, sync::Arc};
+use std::{net::SocketAddr, path::Path, pin::pin, sync::Arc};
 
 use fs_err::tokio as tokio_fs;
-use jsonrpc_core::{Error, IoHandler, Params, serde_json, to_value};
+use futures_util::{SinkExt, Stream, StreamExt, stream};
+use jsonrpc_core::{Call, Error, MetaIoHandler, Params, Request, serde_json, to_value};
 use miette::{Context, IntoDiagnostic, JSONReportHandler};
 use pixi_build_types::{
     VersionedProjectModel,
@@ -14,19 +15,31 @@ use pixi_build_types::{
     },
 };
 use serde::Serialize;
-use tokio::sync::{Mutex, RwLock};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+    sync::{Mutex, RwLock, mpsc},
+    task::JoinSet,
+};
+use tokio_tungstenite::tungstenite::Message;
 
+use crate::cancellation::{CancellationMiddleware, register_process_directory};
 use crate::consts::DEBUG_OUTPUT_DIR;
-use crate::protocol::{Protocol, ProtocolInstantiator};
+use crate::errors::{CategorizedError, UNCATEGORIZED_ERROR_CODE};
+use crate::notifications::{BuildNotifier, FrontendNotificationCapabilities};
+use crate::protocol::{MethodNotImplemented, Protocol, ProtocolInstantiator};
+use crate::transcript::{ReplayOutcome, ReplayedEntry, SessionRecorder, Transcript};
+use crate::work_directory_lock::WorkDirectoryLock;
 
 /// A JSONRPC server that can be used to communicate with a client.
 pub struct Server<T: ProtocolInstantiator> {
-    instatiator: T,
+    instatiator: Arc<T>,
+    record: bool,
 }
 
 enum ServerState<T: ProtocolInstantiator> {
     /// Server has not been initialized yet.
-    Uninitialized(T),
+    Uninitialized(Arc<T>),
     /// Server has been initialized, with a protocol.
     Initialized(Box<dyn Protocol + Send + Sync + 'static>),
 }
@@ -45,19 +58,71 @@ impl<T: ProtocolInstantiator> ServerState<T> {
 
 impl<T: ProtocolInstantiator> Server<T> {
     pub fn new(instatiator: T) -> Self {
-        Self { instatiator }
+        Self {
+            instatiator: Arc::new(instatiator),
+            record: false,
+        }
+    }
+
+    /// Record the requests and responses of every session, see
+    /// [`crate::transcript`].
+    pub fn with_recording(self, record: bool) -> Self {
+        Self { record, ..self }
+    }
+
+    /// Re-executes the requests of a recorded session and returns how the
+    /// response to every request compares to the recording.
+    pub async fn replay(self, transcript: &Path) -> miette::Result<Vec<ReplayedEntry>> {
+        let transcript = Transcript::from_path(transcript)?;
+        let io = self.setup_io(None);
+
+        let mut replayed = Vec::with_capacity(transcript.entries.len());
+        for (id, entry) in transcript.entries.into_iter().enumerate() {
+            let request = serde_json::json!({
+                "jsonrpc": "2.0",
+                "method": &entry.method,
+                "params": &entry.params,
+                "id": id,
+            });
+            let response = io
+                .handle_request(&request.to_string(), ())
+                .await
+                .ok_or_else(|| miette::miette!("no response to '{}'", entry.method))?;
+            let mut response: serde_json::Value =
+                serde_json::from_str(&response).into_diagnostic()?;
+
+            let outcome = match (&entry.error, response.get_mut("error")) {
+                (None, Some(error)) => ReplayOutcome::UnexpectedFailure(error.take()),
+                (Some(_), None) => ReplayOutcome::UnexpectedSuccess,
+                (Some(_), Some(_)) => ReplayOutcome::FailedAsRecorded,
+                (None, None) => match response.get_mut("result") {
+                    Some(result) if Some(&*result) == entry.result.as_ref() => {
+                        ReplayOutcome::SameResult
+                    }
+                    result => ReplayOutcome::DifferentResult(
+                        result.map(serde_json::Value::take).unwrap_or_default(),
+                    ),
+                },
+            };
+            replayed.push(ReplayedEntry {
+                method: entry.method,
+                outcome,
+            });
+        }
+
+        Ok(replayed)
     }
 
     /// Run the server, communicating over stdin/stdout.
     pub async fn run(self) -> miette::Result<()> {
-        let io = self.setup_io();
-        jsonrpc_stdio_server::ServerBuilder::new(io).build().await;
-        Ok(())
+        self.serve_lines(tokio::io::stdin(), tokio::io::stdout())
+            .await
     }
 
     /// Run the server, communicating over HTTP.
     pub fn run_over_http(self, port: u16) -> miette::Result<()> {
-        let io = self.setup_io();
+        // There is no way to send notifications to the client over HTTP.
+        let io = self.setup_io(None);
         jsonrpc_http_server::ServerBuilder::new(io)
             .start_http(&SocketAddr::from(([127, 0, 0, 1], port)))
             .into_diagnostic()?
@@ -65,33 +130,226 @@ impl<T: ProtocolInstantiator> Server<T> {
         Ok(())
     }
 
+    /// Run the server, listening for connections on a Unix domain socket at
+    /// `path`.
+    ///
+    /// Every connection is a separate session that has to be initialized by
+    /// the client, which allows multiple clients to share the same backend
+    /// process.
+    #[cfg(unix)]
+    pub async fn run_over_unix_socket(self, path: &Path) -> miette::Result<()> {
+        use std::os::unix::fs::FileTypeExt;
+
+        // Remove a socket that was left behind by a previous backend process.
+        if let Ok(metadata) = fs_err::symlink_metadata(path) {
+            if !metadata.file_type().is_socket() {
+                miette::bail!("'{}' already exists and is not a socket", path.display());
+            }
+            fs_err::remove_file(path).into_diagnostic()?;
+        }
+
+        let listener = tokio::net::UnixListener::bind(path)
+            .into_diagnostic()
+            .with_context(|| format!("failed to bind to '{}'", path.display()))?;
+
+        let server = Arc::new(self);
+        loop {
+            let (stream, _) = listener.accept().await.into_diagnostic()?;
+            let server = server.clone();
+            tokio::spawn(async move {
+                let (reader, writer) = stream.into_split();
+                if let Err(err) = server.serve_lines(reader, writer).await {
+                    tracing::warn!("unix socket connection failed: {err:?}");
+                }
+            });
+        }
+    }
+
+    /// Run the server, accepting WebSocket connections on `port`.
+    ///
+    /// Every text message is a single JSON-RPC message. Like with the Unix
+    /// domain socket, every connection is a separate session.
+    pub async fn run_over_websocket(self, port: u16) -> miette::Result<()> {
+        let listener = TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], port)))
+            .await
+            .into_diagnostic()
+            .with_context(|| format!("failed to bind to port {port}"))?;
+
+        let server = Arc::new(self);
+        loop {
+            let (stream, _) = listener.accept().await.into_diagnostic()?;
+            let server = server.clone();
+            tokio::spawn(async move {
+                if let Err(err) = server.serve_websocket(stream).await {
+                    tracing::warn!("websocket connection failed: {err:?}");
+                }
+            });
+        }
+    }
+
+    /// Serves a single client that sends newline-delimited messages.
+    async fn serve_lines<R, W>(&self, reader: R, mut writer: W) -> miette::Result<()>
+    where
+        R: AsyncRead + Unpin + Send + 'static,
+        W: AsyncWrite + Unpin + Send + 'static,
+    {
+        let (outgoing_tx, mut outgoing_rx) = mpsc::unbounded_channel::<String>();
+        let writer = tokio::spawn(async move {
+            while let Some(message) = outgoing_rx.recv().await {
+                let mut line = message.replace('\n', "");
+                line.push('\n');
+                writer.write_all(line.as_bytes()).await.into_diagnostic()?;
+                writer.flush().await.into_diagnostic()?;
+            }
+            Ok::<_, miette::Report>(())
+        });
+
+        let incoming = stream::unfold(BufReader::new(reader).lines(), |mut lines| async move {
+            let line = lines.next_line().await.into_diagnostic().transpose()?;
+            Some((line, lines))
+        });
+
+        let result = self.serve_connection(incoming, outgoing_tx).await;
+        writer.await.into_diagnostic()??;
+        result
+    }
+
+    /// Serves a single WebSocket client.
+    async fn serve_websocket(&self, stream: TcpStream) -> miette::Result<()> {
+        let websocket = tokio_tungstenite::accept_async(stream)
+            .await
+            .into_diagnostic()?;
+        let (mut sink, incoming) = websocket.split();
+
+        let (outgoing_tx, mut outgoing_rx) = mpsc::unbounded_channel::<String>();
+        let writer = tokio::spawn(async move {
+            while let Some(message) = outgoing_rx.recv().await {
+                sink.send(Message::text(message)).await.into_diagnostic()?;
+            }
+            sink.close().await.into_diagnostic()
+        });
+
+        let incoming = incoming.filter_map(|message| async move {
+            match message {
+                Ok(Message::Text(text)) => Some(Ok(text.to_string())),
+                // Control frames are handled by tungstenite itself.
+                Ok(_) => None,
+                Err(err) => Some(Err(err).into_diagnostic()),
+            }
+        });
+
+        let result = self.serve_connection(incoming, outgoing_tx).await;
+        writer.await.into_diagnostic()??;
+        result
+    }
+
+    /// Processes the messages of a single client until it disconnects.
+    /// Responses and notifications are sent to `outgoing`.
+    ///
+    /// Requests are processed concurrently and their responses are sent as
+    /// soon as they are available, so the client has to wait for the response
+    /// to `initialize` before sending other requests. Requests that share a
+    /// work directory wait for each other, see [`WorkDirectoryLock`].
+    /// Notifications are processed immediately, so that a `$/cancelRequest`
+    /// notification can abort a request that is being processed.
+    async fn serve_connection(
+        &self,
+        incoming: impl Stream<Item = miette::Result<String>> + Send,
+        outgoing: mpsc::UnboundedSender<String>,
+    ) -> miette::Result<()> {
+        let io = Arc::new(self.setup_io(Some(outgoing.clone())));
+        let mut requests = JoinSet::new();
+
+        let mut incoming = pin!(incoming);
+        let result = async {
+            while let Some(message) = incoming.next().await {
+                let message = message?;
+                if is_notification(&message) {
+                    io.handle_request(&message, ()).await;
+                } else {
+                    let io = io.clone();
+                    let outgoing = outgoing.clone();
+                    requests.spawn(async move {
+                        if let Some(response) = io.handle_request(&message, ()).await {
+                            let _ = outgoing.send(response);
+                        }
+                    });
+                }
+            }
+            Ok(())
+        }
+        .await;
+
+        // Finish the requests that were already received before closing the
+        // connection.
+        while let Some(request) = requests.join_next().await {
+            request.into_diagnostic()?;
+        }
+        result
+    }
+
     /// Setup the IO inner handler.
-    fn setup_io(self) -> IoHandler {
-        // Construct a server
-        let mut io = IoHandler::new();
+    ///
+    /// `notifications` is used to send notifications to the client, if the
+    /// transport supports it.
+    fn setup_io(
+        &self,
+        notifications: Option<mpsc::UnboundedSender<String>>,
+    ) -> MetaIoHandler<(), CancellationMiddleware> {
+        // Construct a server whose requests can be cancelled
+        let mut io = CancellationMiddleware::default().io_handler();
+        let recorder = SessionRecorder::new(self.record);
+        // The notifications of this session are only sent to the connection
+        // of this session, regardless of what other clients negotiate.
+        let notifier = BuildNotifier::new(notifications);
+
+        let negotiate_recorder = recorder.clone();
+        let negotiate_notifier = notifier.clone();
         io.add_method(
             procedures::negotiate_capabilities::METHOD_NAME,
-            move |params: Params| async move {
-                let params: NegotiateCapabilitiesParams = params.parse()?;
-                let result = T::negotiate_capabilities(params)
-                    .await
-                    .map_err(convert_error)?;
-                Ok(to_value(result).expect("failed to convert to json"))
+            move |params: Params| {
+                let recorder = negotiate_recorder.clone();
+                let notifier = negotiate_notifier.clone();
+                async move {
+                    let capabilities = FrontendNotificationCapabilities::from_params(&params);
+                    notifier.set_enabled(capabilities.build_notifications);
+
+                    let raw_params = params.clone();
+                    recorder
+                        .record(
+                            procedures::negotiate_capabilities::METHOD_NAME,
+                            raw_params,
+                            None,
+                            async move {
+                                let params: NegotiateCapabilitiesParams = params.parse()?;
+                                let result = T::negotiate_capabilities(params)
+                                    .await
+                                    .map_err(convert_error)?;
+                                Ok(to_value(result).expect("failed to convert to json"))
+                            },
+                        )
+                        .await
+                }
             },
         );
 
         let project_model = Arc::new(Mutex::new(None));
 
-        let state = Arc::new(RwLock::new(ServerState::Uninitialized(self.instatiator)));
+        let state = Arc::new(RwLock::new(ServerState::Uninitialized(
+            self.instatiator.clone(),
+        )));
         let initialize_state = state.clone();
         let initialize_project_model = project_model.clone();
+        let initialize_recorder = recorder.clone();
         io.add_method(
             procedures::initialize::METHOD_NAME,
             move |params: Params| {
                 let pm = initialize_project_model.clone();
                 let state = initialize_state.clone();
+                let recorder = initialize_recorder.clone();
 
-                async move {
+                let raw_params = params.clone();
+                let response = async move {
                     let params: InitializeParams = params.parse()?;
 
                     if let Some(project_model) = &params.project_model {
@@ -111,96 +369,143 @@ impl<T: ProtocolInstantiator> Server<T> {
                     *state = ServerState::Initialized(protocol_endpoint);
 
                     Ok(to_value(result).expect("failed to convert to json"))
+                };
+                async move {
+                    recorder
+                        .record(
+                            procedures::initialize::METHOD_NAME,
+                            raw_params,
+                            None,
+                            response,
+                        )
+                        .await
                 }
             },
         );
 
         let conda_outputs = state.clone();
+        let conda_outputs_recorder = recorder.clone();
         let conda_outputs_project_model = project_model.clone();
         io.add_method(
             procedures::conda_outputs::METHOD_NAME,
             move |params: Params| {
                 let pm = conda_outputs_project_model.clone();
                 let state = conda_outputs.clone();
+                let recorder = conda_outputs_recorder.clone();
 
                 async move {
+                    let raw_params = params.clone();
                     let params: CondaOutputsParams = params.parse()?;
-                    let state = state.read().await;
-                    let endpoint = state.as_endpoint()?;
+                    let work_directory = params.work_directory.clone();
+                    let response = async move {
+                        let state = state.read().await;
+                        let endpoint = state.as_endpoint()?;
 
-                    let debug_dir = params.work_directory.join(DEBUG_OUTPUT_DIR);
-
-                    if let Some(project_model) = pm.lock().await.take() {
-                        log_project_model(&debug_dir, project_model)
+                        let _lock = WorkDirectoryLock::acquire(&params.work_directory)
                             .await
                             .map_err(convert_error)?;
-                    }
-
-                    log_conda_outputs(&debug_dir, &params)
-                        .await
-                        .map_err(convert_error)?;
+                        let debug_dir = params.work_directory.join(DEBUG_OUTPUT_DIR);
 
-                    match endpoint.conda_outputs(params).await {
-                        Ok(result) => {
-                            log_conda_outputs_response(&debug_dir, &result)
+                        if let Some(project_model) = pm.lock().await.take() {
+                            log_project_model(&debug_dir, project_model)
                                 .await
                                 .map_err(convert_error)?;
-
-                            Ok(to_value(result).expect("failed to convert to json"))
                         }
-                        Err(err) => {
-                            let json_error = convert_error(err);
-                            log_conda_outputs_error(&debug_dir, &json_error)
-                                .await
-                                .map_err(convert_error)?;
-                            Err(json_error)
+
+                        log_conda_outputs(&debug_dir, &params)
+                            .await
+                            .map_err(convert_error)?;
+
+                        match endpoint.conda_outputs(params).await {
+                            Ok(result) => {
+                                log_conda_outputs_response(&debug_dir, &result)
+                                    .await
+                                    .map_err(convert_error)?;
+
+                                Ok(to_value(result).expect("failed to convert to json"))
+                            }
+                            Err(err) => {
+                                let json_error = convert_error(err);
+                                log_conda_outputs_error(&debug_dir, &json_error)
+                                    .await
+                                    .map_err(convert_error)?;
+                                Err(json_error)
+                            }
                         }
-                    }
+                    };
+                    recorder
+                        .record(
+                            procedures::conda_outputs::METHOD_NAME,
+                            raw_params,
+                            Some(&work_directory),
+                            response,
+                        )
+                        .await
                 }
             },
         );
 
         let conda_build_v1 = state.clone();
+        let conda_build_v1_recorder = recorder.clone();
         let conda_build_project_model = project_model.clone();
+        let conda_build_notifier = notifier.clone();
         io.add_method(
             procedures::conda_build_v1::METHOD_NAME,
             move |params: Params| {
                 let pm = conda_build_project_model.clone();
                 let state = conda_build_v1.clone();
+                let recorder = conda_build_v1_recorder.clone();
+                let notifier = conda_build_notifier.clone();
 
                 async move {
+                    let raw_params = params.clone();
                     let params: CondaBuildV1Params = params.parse()?;
-                    let state = state.read().await;
-                    let endpoint = state.as_endpoint()?;
-
-                    let debug_dir = params.work_directory.join(DEBUG_OUTPUT_DIR);
+                    let work_directory = params.work_directory.clone();
+                    register_process_directory(&work_directory);
+                    let response = async move {
+                        let state = state.read().await;
+                        let endpoint = state.as_endpoint()?;
 
-                    if let Some(project_model) = pm.lock().await.take() {
-                        log_project_model(&debug_dir, project_model)
+                        let _lock = WorkDirectoryLock::acquire(&params.work_directory)
                             .await
                             .map_err(convert_error)?;
-                    }
-
-                    log_conda_build_v1(&debug_dir, &params)
-                        .await
-                        .map_err(convert_error)?;
+                        let debug_dir = params.work_directory.join(DEBUG_OUTPUT_DIR);
 
-                    match endpoint.conda_build_v1(params).await {
-                        Ok(result) => {
-                            log_conda_build_v1_response(&debug_dir, &result)
+                        if let Some(project_model) = pm.lock().await.take() {
+                            log_project_model(&debug_dir, project_model)
                                 .await
                                 .map_err(convert_error)?;
-
-                            Ok(to_value(result).expect("failed to convert to json"))
                         }
-                        Err(err) => {
-                            let json_error = convert_error(err);
-                            log_conda_build_v1_error(&debug_dir, &json_error)
-                                .await
-                                .map_err(convert_error)?;
-                            Err(json_error)
+
+                        log_conda_build_v1(&debug_dir, &params)
+                            .await
+                            .map_err(convert_error)?;
+
+                        match endpoint.conda_build_v1(params).await {
+                            Ok(result) => {
+                                log_conda_build_v1_response(&debug_dir, &result)
+                                    .await
+                                    .map_err(convert_error)?;
+
+                                Ok(to_value(result).expect("failed to convert to json"))
+                            }
+                            Err(err) => {
+                                let json_error = convert_error(err);
+                                log_conda_build_v1_error(&debug_dir, &json_error)
+                                    .await
+                                    .map_err(convert_error)?;
+                                Err(json_error)
+                            }
                         }
-                    }
+                    };
+                    recorder
+                        .record(
+                            procedures::conda_build_v1::METHOD_NAME,
+                            raw_params,
+                            Some(&work_directory),
+                            notifier.scope(response),
+                        )
+                        .await
                 }
             },
         );
@@ -209,15 +514,44 @@ impl<T: ProtocolInstantiator> Server<T> {
     }
 }
 
+/// Returns true if `line` contains a single JSON-RPC notification.
+fn is_notification(line: &str) -> bool {
+    matches!(
+        serde_json::from_str::<Request>(line),
+        Ok(Request::Single(Call::Notification(_)))
+    )
+}
+
+/// Converts an error into a JSON-RPC error.
+///
+/// The `data` of the error contains the rendered diagnostic. If the error was
+/// categorized, the code of its [`ErrorCategory`](crate::errors::ErrorCategory)
+/// is used and the details of the [`CategorizedError`] are added to `data`.
 fn convert_error(err: miette::Report) -> jsonrpc_core::Error {
     let rendered = JSONReportHandler::new();
     let mut json_str = String::new();
     rendered
         .render_report(&mut json_str, err.as_ref())
         .expect("failed to convert error to json");
-    let data = serde_json::from_str(&json_str).expect("failed to parse json error");
+    let mut data: serde_json::Value =
+        serde_json::from_str(&json_str).expect("failed to parse json error");
+
+    let code = if err.downcast_ref::<MethodNotImplemented>().is_some() {
+        jsonrpc_core::ErrorCode::MethodNotFound
+    } else if let Some(categorized) = CategorizedError::find(&err) {
+        if let (Some(data), serde_json::Value::Object(details)) = (
+            data.as_object_mut(),
+            to_value(categorized).expect("failed to convert to json"),
+        ) {
+            data.extend(details);
+        }
+        jsonrpc_core::ErrorCode::ServerError(categorized.category.code())
+    } else {
+        jsonrpc_core::ErrorCode::ServerError(UNCATEGORIZED_ERROR_CODE)
+    };
+
     jsonrpc_core::Error {
-        code: jsonrpc_core::ErrorCode::ServerError(-32000),
+        code,
         message: err.to_string(),
         data: Some(data),
     }
@@ -291,3 +625,372 @@ async fn write_json_file<T: Serialize>(
         .context("failed to write JSON to file")?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        path::PathBuf,
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Duration,
+    };
+
+    use pixi_build_types::{
+        VariantValue,
+        procedures::{conda_build_v1::CondaBuildV1Output, initialize::InitializeResult},
+    };
+    use rattler_conda_types::Platform;
+    use serde_json::{Value, json};
+
+    use crate::{
+        errors::{CategorizeError, ErrorCategory},
+        notifications::{BuildPhase, OutputBuild},
+    };
+
+    use super::*;
+
+    /// Keeps track of the builds that are running in each work directory.
+    #[derive(Default)]
+    struct BuildRecorder {
+        active: std::sync::Mutex<HashMap<PathBuf, usize>>,
+        max_parallel: AtomicUsize,
+    }
+
+    /// A protocol whose builds write their variant to the work directory and
+    /// check that it was not overwritten by another build before finishing.
+    struct RecordingProtocol(Arc<BuildRecorder>);
+
+    crate::implement_protocol! {
+        impl Protocol for RecordingProtocol {
+            async fn conda_build_v1(
+                &self,
+                params: CondaBuildV1Params,
+            ) -> miette::Result<CondaBuildV1Result> {
+                OutputBuild::start(params.output.name.as_normalized())
+                    .progress(BuildPhase::Building);
+                {
+                    let mut active = self.0.active.lock().unwrap();
+                    *active.entry(params.work_directory.clone()).or_default() += 1;
+                    self.0
+                        .max_parallel
+                        .fetch_max(active.values().sum(), Ordering::SeqCst);
+                }
+
+                let variant = serde_json::to_string(&params.output.variant).into_diagnostic()?;
+                let work_dir = params.work_directory.join("work");
+                fs_err::create_dir_all(&work_dir).into_diagnostic()?;
+                fs_err::write(work_dir.join("variant.json"), &variant).into_diagnostic()?;
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                let written =
+                    fs_err::read_to_string(work_dir.join("variant.json")).into_diagnostic()?;
+
+                *self
+                    .0
+                    .active
+                    .lock()
+                    .unwrap()
+                    .get_mut(&params.work_directory)
+                    .unwrap() -= 1;
+                miette::ensure!(written == variant, "the work directory was modified");
+
+                Ok(CondaBuildV1Result {
+                    output_file: params.work_directory.join("foo.conda"),
+                    input_globs: Default::default(),
+                    name: params.output.name.as_normalized().to_string(),
+                    version: "1.0.0".parse().unwrap(),
+                    build: String::from("0"),
+                    subdir: params.output.subdir,
+                })
+            }
+        }
+    }
+
+    struct RecordingInstantiator(Arc<BuildRecorder>);
+
+    #[async_trait::async_trait]
+    impl ProtocolInstantiator for RecordingInstantiator {
+        type Protocol = RecordingProtocol;
+
+        async fn initialize(
+            &self,
+            _params: InitializeParams,
+        ) -> miette::Result<(Box<dyn Protocol + Send + Sync + 'static>, InitializeResult)> {
+            Ok((
+                Box::new(RecordingProtocol(self.0.clone())),
+                InitializeResult {},
+            ))
+        }
+    }
+
+    async fn request(
+        io: &MetaIoHandler<(), CancellationMiddleware>,
+        id: u64,
+        method: &str,
+        params: impl Serialize,
+    ) -> Value {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "method": method,
+            "params": params,
+            "id": id,
+        });
+        let response = io.handle_request(&request.to_string(), ()).await.unwrap();
+        serde_json::from_str(&response).unwrap()
+    }
+
+    /// Initializes a server that uses a [`RecordingProtocol`].
+    async fn initialized_io(
+        recorder: &Arc<BuildRecorder>,
+        record: bool,
+    ) -> MetaIoHandler<(), CancellationMiddleware> {
+        let io = Server::new(RecordingInstantiator(recorder.clone()))
+            .with_recording(record)
+            .setup_io(None);
+        let response = request(
+            &io,
+            0,
+            procedures::initialize::METHOD_NAME,
+            InitializeParams {
+                workspace_root: None,
+                source_dir: None,
+                manifest_path: PathBuf::from("pixi.toml"),
+                project_model: None,
+                configuration: None,
+                target_configuration: None,
+                cache_directory: None,
+            },
+        )
+        .await;
+        assert!(response.get("result").is_some(), "{response}");
+        io
+    }
+
+    fn build_params(work_directory: PathBuf, python: &str) -> CondaBuildV1Params {
+        CondaBuildV1Params {
+            channels: vec![],
+            build_prefix: None,
+            host_prefix: None,
+            run_constraints: None,
+            run_dependencies: None,
+            run_exports: None,
+            output: CondaBuildV1Output {
+                name: "foo".parse().unwrap(),
+                version: None,
+                build: None,
+                subdir: Platform::current(),
+                variant: [(
+                    String::from("python"),
+                    VariantValue::String(python.to_string()),
+                )]
+                .into_iter()
+                .collect(),
+            },
+            work_directory,
+            output_directory: None,
+            editable: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_parallel_builds_of_distinct_variants() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let recorder = Arc::new(BuildRecorder::default());
+        let io = initialized_io(&recorder, false).await;
+
+        let first = tmp_dir.path().join("py312");
+        let second = tmp_dir.path().join("py313");
+        let (first_response, second_response) = tokio::join!(
+            request(
+                &io,
+                1,
+                procedures::conda_build_v1::METHOD_NAME,
+                build_params(first.clone(), "3.12"),
+            ),
+            request(
+                &io,
+                2,
+                procedures::conda_build_v1::METHOD_NAME,
+                build_params(second.clone(), "3.13"),
+            ),
+        );
+        assert!(first_response.get("result").is_some(), "{first_response}");
+        assert!(second_response.get("result").is_some(), "{second_response}");
+
+        // Both builds ran at the same time, each in its own work directory.
+        assert_eq!(recorder.max_parallel.load(Ordering::SeqCst), 2);
+        for (work_directory, python) in [(first, "3.12"), (second, "3.13")] {
+            let variant = fs_err::read_to_string(work_directory.join("work/variant.json")).unwrap();
+            assert!(variant.contains(python), "{variant}");
+            let logged_params = fs_err::read_to_string(
+                work_directory
+                    .join(DEBUG_OUTPUT_DIR)
+                    .join("conda_build_v1_params.json"),
+            )
+            .unwrap();
+            assert!(logged_params.contains(python), "{logged_params}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_builds_in_the_same_work_directory_are_serialized() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let recorder = Arc::new(BuildRecorder::default());
+        let io = initialized_io(&recorder, false).await;
+
+        let work_directory = tmp_dir.path().join("work");
+        let (first_response, second_response) = tokio::join!(
+            request(
+                &io,
+                1,
+                procedures::conda_build_v1::METHOD_NAME,
+                build_params(work_directory.clone(), "3.12"),
+            ),
+            request(
+                &io,
+                2,
+                procedures::conda_build_v1::METHOD_NAME,
+                build_params(work_directory.clone(), "3.13"),
+            ),
+        );
+        assert!(first_response.get("result").is_some(), "{first_response}");
+        assert!(second_response.get("result").is_some(), "{second_response}");
+        assert_eq!(recorder.max_parallel.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_notifications_are_sent_to_the_negotiating_connection() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let recorder = Arc::new(BuildRecorder::default());
+        let server = Server::new(RecordingInstantiator(recorder));
+        let negotiate = |build_notifications: bool| json!({ "capabilities": { "buildNotifications": build_notifications } });
+
+        let (first_tx, mut first_rx) = mpsc::unbounded_channel();
+        let first = server.setup_io(Some(first_tx));
+        let (second_tx, mut second_rx) = mpsc::unbounded_channel();
+        let second = server.setup_io(Some(second_tx));
+        for io in [&first, &second] {
+            let response = request(
+                io,
+                0,
+                procedures::initialize::METHOD_NAME,
+                InitializeParams {
+                    workspace_root: None,
+                    source_dir: None,
+                    manifest_path: PathBuf::from("pixi.toml"),
+                    project_model: None,
+                    configuration: None,
+                    target_configuration: None,
+                    cache_directory: None,
+                },
+            )
+            .await;
+            assert!(response.get("result").is_some(), "{response}");
+        }
+
+        // The second client opting in or out does not affect the first one.
+        let method = procedures::negotiate_capabilities::METHOD_NAME;
+        request(&first, 1, method, negotiate(true)).await;
+        request(&second, 1, method, negotiate(true)).await;
+        request(&second, 2, method, negotiate(false)).await;
+
+        for io in [&first, &second] {
+            let response = request(
+                io,
+                3,
+                procedures::conda_build_v1::METHOD_NAME,
+                build_params(tmp_dir.path().join("work"), "3.12"),
+            )
+            .await;
+            assert!(response.get("result").is_some(), "{response}");
+        }
+
+        let notification: Value = serde_json::from_str(&first_rx.try_recv().unwrap()).unwrap();
+        assert_eq!(
+            notification["params"],
+            json!({ "output": "foo", "phase": "building" })
+        );
+        assert!(first_rx.try_recv().is_err());
+        assert!(second_rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_convert_categorized_error() {
+        let result: miette::Result<()> = Err(miette::miette!("exit code 1"));
+        let err = result
+            .categorize_with(|| {
+                CategorizedError::new(ErrorCategory::BuildScript)
+                    .with_output("foo")
+                    .with_log_excerpt(vec![String::from("error: foo.c:1: syntax error")])
+            })
+            .unwrap_err();
+
+        let error = convert_error(err);
+        assert_eq!(
+            error.code,
+            jsonrpc_core::ErrorCode::ServerError(ErrorCategory::BuildScript.code())
+        );
+        let data = error.data.unwrap();
+        assert_eq!(data["category"], "build-script");
+        assert_eq!(data["output"], "foo");
+        assert_eq!(data["logExcerpt"][0], "error: foo.c:1: syntax error");
+        assert_eq!(data["message"], "the build script failed for 'foo'");
+    }
+
+    #[test]
+    fn test_convert_uncategorized_error() {
+        let error = convert_error(miette::miette!("something went wrong"));
+        assert_eq!(
+            error.code,
+            jsonrpc_core::ErrorCode::ServerError(UNCATEGORIZED_ERROR_CODE)
+        );
+        assert!(error.data.unwrap().get("category").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_record_and_replay_session() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let recorder = Arc::new(BuildRecorder::default());
+        let io = initialized_io(&recorder, true).await;
+
+        let work_directory = tmp_dir.path().join("work");
+        let response = request(
+            &io,
+            1,
+            procedures::conda_build_v1::METHOD_NAME,
+            build_params(work_directory.clone(), "3.12"),
+        )
+        .await;
+        assert!(response.get("result").is_some(), "{response}");
+
+        let transcript_path = work_directory
+            .join(DEBUG_OUTPUT_DIR)
+            .join(crate::transcript::TRANSCRIPT_FILE_NAME);
+        let transcript = Transcript::from_path(&transcript_path).unwrap();
+        assert_eq!(
+            transcript
+                .entries
+                .iter()
+                .map(|entry| entry.method.as_str())
+                .collect::<Vec<_>>(),
+            [
+                procedures::initialize::METHOD_NAME,
+                procedures::conda_build_v1::METHOD_NAME
+            ]
+        );
+
+        // Replaying the session builds the package again.
+        let replay_recorder = Arc::new(BuildRecorder::default());
+        let replayed = Server::new(RecordingInstantiator(replay_recorder.clone()))
+            .replay(&transcript_path)
+            .await
+            .unwrap();
+        assert_eq!(replay_recorder.max_parallel.load(Ordering::SeqCst), 1);
+        assert_eq!(replayed.len(), 2);
+        assert!(
+            replayed
+                .iter()
+                .all(|entry| entry.outcome.matches_recording()),
+            "{replayed:?}"
+        );
+    }
+}
@@ -1,6 +1,6 @@
 use std::sync::Arc;
 
-use minijinja::Value;
+use indexmap::IndexMap;
 use ordermap::OrderMap;
 use pixi_build_types::{
     BinaryPackageSpecV1, PackageSpecV1, SourcePackageSpecV1, TargetSelectorV1, TargetV1, TargetsV1,
@@ -10,18 +10,18 @@ use pixi_build_types::{
     },
 };
 use rattler_build::{
-    recipe::variable::Variable,
+    recipe::{Jinja, parser::Dependency, variable::Variable},
     render::resolved_dependencies::{
         DependencyInfo, FinalizedDependencies, FinalizedRunDependencies, ResolvedDependencies,
         RunExportDependency, SourceDependency,
     },
 };
 use rattler_conda_types::{
-    Channel, MatchSpec, PackageName, PackageNameMatcher, package::RunExportsJson,
+    Channel, MatchSpec, PackageName, PackageNameMatcher, ParseStrictness, package::RunExportsJson,
 };
 use recipe_stage0::{
     matchspec::{PackageDependency, SourceMatchSpec},
-    recipe::{Conditional, ConditionalList, ConditionalRequirements, Item, ListOrItem},
+    recipe::{Conditional, ConditionalList, ConditionalRequirements, Item, ListOrItem, Value},
     requirements::PackageSpecDependencies,
 };
 use serde::Deserialize;
@@ -71,7 +71,7 @@ pub fn convert_variant_from_pixi_build_types(variant: pixi_build_types::VariantV
 pub fn convert_variant_to_pixi_build_types(
     variant: Variable,
 ) -> Result<pixi_build_types::VariantValue, minijinja::Error> {
-    let value = Value::from(variant);
+    let value = minijinja::Value::from(variant);
     pixi_build_types::VariantValue::deserialize(value)
 }
 
@@ -179,7 +179,93 @@ pub fn from_targets_v1_to_conditional_requirements(targets: &TargetsV1) -> Condi
         host: host_items,
         run: run_items,
         run_constraints: run_constraints_items,
+        ..Default::default()
+    }
+}
+
+/// Converts constraints from the backend configuration into conditional
+/// constraints of the recipe.
+///
+/// The constraints map a package name to a version spec, e.g.
+/// `numpy = ">=2"`. The project model has no notion of constraints, so they are
+/// declared in the configuration instead. Target specific constraints are
+/// already merged into the configuration for the host platform, which is why
+/// the constraints are not guarded by a selector.
+pub fn from_constraint_specs(
+    specs: &IndexMap<String, String>,
+) -> miette::Result<ConditionalList<PackageDependency>> {
+    specs
+        .iter()
+        .map(|(name, spec)| {
+            let spec = spec.trim();
+            let match_spec = if spec.is_empty() || spec == "*" {
+                name.clone()
+            } else {
+                format!("{name} {spec}")
+            };
+            let match_spec = MatchSpec::from_str(&match_spec, ParseStrictness::Lenient)
+                .map_err(|err| miette::miette!("invalid constraint '{match_spec}': {err}"))?;
+            Ok(Item::Value(Value::Concrete(PackageDependency::Binary(
+                match_spec,
+            ))))
+        })
+        .collect()
+}
+
+/// Resolves a list of conditional constraints into concrete dependencies.
+///
+/// Constraints are not part of the rattler-build recipe format, so unlike the
+/// other requirements they are not evaluated by rattler-build. Instead, the
+/// conditions and templates are evaluated here with the given [`Jinja`]
+/// context. Constraints can only refer to binary packages.
+pub fn resolve_conditional_constraints(
+    items: &ConditionalList<PackageDependency>,
+    jinja: &Jinja,
+) -> miette::Result<Vec<Dependency>> {
+    fn to_dependency(dependency: &PackageDependency) -> miette::Result<Dependency> {
+        match dependency {
+            PackageDependency::Binary(spec) => Ok(Dependency::Spec(spec.clone())),
+            PackageDependency::Source(_) => miette::bail!(
+                "constraints can only refer to binary packages, found source package '{}'",
+                dependency
+            ),
+        }
     }
+
+    let mut dependencies = Vec::new();
+    for item in items {
+        match item {
+            Item::Value(Value::Concrete(dependency)) => {
+                dependencies.push(to_dependency(dependency)?);
+            }
+            Item::Value(Value::Template(template)) => {
+                let rendered = jinja.render_str(template).map_err(|err| {
+                    miette::miette!("failed to render constraint '{template}': {err}")
+                })?;
+                let spec = MatchSpec::from_str(&rendered, ParseStrictness::Strict)
+                    .map_err(|err| miette::miette!("invalid constraint '{rendered}': {err}"))?;
+                dependencies.push(Dependency::Spec(spec));
+            }
+            Item::Conditional(conditional) => {
+                let condition = jinja.eval(&conditional.condition).map_err(|err| {
+                    miette::miette!(
+                        "failed to evaluate condition '{}': {err}",
+                        conditional.condition
+                    )
+                })?;
+                let selected = if condition.is_true() {
+                    &conditional.then
+                } else {
+                    &conditional.else_value
+                };
+                for dependency in selected.iter() {
+                    dependencies.push(to_dependency(dependency)?);
+                }
+            }
+        }
+    }
+
+    Ok(dependencies)
 }
 
 pub(crate) fn source_package_spec_to_package_dependency(
@@ -201,6 +287,15 @@ fn binary_package_spec_to_package_dependency(
     name: PackageName,
     binary_spec: BinaryPackageSpecV1,
 ) -> PackageDependency {
+    PackageDependency::Binary(binary_package_spec_to_match_spec(name, binary_spec))
+}
+
+/// Converts a binary package spec into a match spec for the package with the
+/// given name.
+pub(crate) fn binary_package_spec_to_match_spec(
+    name: PackageName,
+    binary_spec: BinaryPackageSpecV1,
+) -> MatchSpec {
     let BinaryPackageSpecV1 {
         version,
         build,
@@ -218,7 +313,7 @@ fn binary_package_spec_to_package_dependency(
     // so later rattler-build can detect the PackageDependency as a variant.
     let version = version.filter(|v| v != &rattler_conda_types::VersionSpec::Any);
 
-    PackageDependency::Binary(MatchSpec {
+    MatchSpec {
         name: Some(PackageNameMatcher::Exact(name)),
         version,
         build,
@@ -233,7 +328,20 @@ fn binary_package_spec_to_package_dependency(
         url,
         license,
         condition: None,
-    })
+    }
+}
+
+/// Converts a package spec into a match spec for the package with the given
+/// name. A source spec only constrains the name, the package that satisfies it
+/// is built from its source separately.
+pub(crate) fn package_spec_to_match_spec(name: PackageName, spec: PackageSpecV1) -> MatchSpec {
+    match spec {
+        PackageSpecV1::Binary(binary_spec) => binary_package_spec_to_match_spec(name, *binary_spec),
+        PackageSpecV1::Source(_) => MatchSpec {
+            name: Some(PackageNameMatcher::Exact(name)),
+            ..Default::default()
+        },
+    }
 }
 
 fn package_spec_to_package_dependency(
@@ -421,4 +529,64 @@ mod test {
         let match_spec = binary_package_spec_to_package_dependency(name, spec);
         assert_eq!(match_spec.to_string(), "python");
     }
+
+    #[test]
+    fn test_from_constraint_specs() {
+        let specs = IndexMap::from([
+            (String::from("numpy"), String::from(">=2")),
+            (String::from("openssl"), String::from("*")),
+        ]);
+        let constraints = from_constraint_specs(&specs).unwrap();
+        assert_eq!(
+            constraints
+                .iter()
+                .map(|item| match item {
+                    Item::Value(Value::Concrete(dependency)) => dependency.to_string(),
+                    other => panic!("unexpected item {other:?}"),
+                })
+                .collect::<Vec<_>>(),
+            vec!["numpy >=2", "openssl"]
+        );
+
+        let invalid = IndexMap::from([(String::from("numpy"), String::from(">=>2"))]);
+        assert!(from_constraint_specs(&invalid).is_err());
+    }
+
+    #[test]
+    fn test_resolve_conditional_constraints() {
+        let items: ConditionalList<PackageDependency> = vec![
+            Item::Value(Value::Concrete("openssl >=3".parse().unwrap())),
+            Item::Conditional(Conditional {
+                condition: "host_platform == 'linux-64'".to_string(),
+                then: ListOrItem(vec!["libgcc >=13".parse().unwrap()]),
+                else_value: ListOrItem(vec!["libcxx >=17".parse().unwrap()]),
+            }),
+        ];
+
+        let resolve = |platform: rattler_conda_types::Platform| {
+            let jinja = Jinja::new(rattler_build::selectors::SelectorConfig {
+                target_platform: platform,
+                host_platform: platform,
+                build_platform: platform,
+                ..Default::default()
+            });
+            resolve_conditional_constraints(&items, &jinja)
+                .unwrap()
+                .into_iter()
+                .map(|dep| match dep {
+                    Dependency::Spec(spec) => spec.to_string(),
+                    other => panic!("unexpected dependency {other:?}"),
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            resolve(rattler_conda_types::Platform::Linux64),
+            vec!["openssl >=3", "libgcc >=13"]
+        );
+        assert_eq!(
+            resolve(rattler_conda_types::Platform::OsxArm64),
+            vec!["openssl >=3", "libcxx >=17"]
+        );
+    }
 }
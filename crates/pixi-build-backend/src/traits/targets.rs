@@ -17,6 +17,18 @@ use pixi_build_types::{self as pbt};
 pub trait TargetSelector {
     /// Does the target selector match the platform?
     fn matches(&self, platform: Platform) -> bool;
+
+    /// The precedence of the selector when multiple selectors match the same
+    /// platform. Target specific values of a selector with a higher precedence
+    /// override those of a selector with a lower precedence.
+    ///
+    /// Family selectors (`unix`, `win`) have the lowest precedence, followed
+    /// by operating system selectors (`linux`, `osx`) and finally exact
+    /// platforms. By default all selectors have the same precedence, in which
+    /// case they are applied in the order in which they are declared.
+    fn precedence(&self) -> u8 {
+        0
+    }
 }
 
 #[derive(Debug)]
@@ -142,6 +154,14 @@ impl TargetSelector for pbt::TargetSelectorV1 {
             pbt::TargetSelectorV1::MacOs => platform.is_osx(),
         }
     }
+
+    fn precedence(&self) -> u8 {
+        match self {
+            pbt::TargetSelectorV1::Unix | pbt::TargetSelectorV1::Win => 0,
+            pbt::TargetSelectorV1::Linux | pbt::TargetSelectorV1::MacOs => 1,
+            pbt::TargetSelectorV1::Platform(_) => 2,
+        }
+    }
 }
 
 impl Targets for pbt::TargetsV1 {
@@ -0,0 +1,252 @@
+//! Recording of JSON-RPC sessions so they can be replayed later.
+//!
+//! When recording is enabled, every request and its response are recorded.
+//! Whenever a request that refers to a work directory completes, the
+//! transcript of the session is written to [`TRANSCRIPT_FILE_NAME`] in the
+//! `debug/` directory of that work directory. The transcript contains the
+//! requests that initialized the session and the requests for that work
+//! directory, so a failing build can be reproduced with the `replay`
+//! subcommand of the backend.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use jsonrpc_core::{Params, Value};
+use miette::{Context, IntoDiagnostic};
+use serde::{Deserialize, Serialize};
+
+use crate::consts::DEBUG_OUTPUT_DIR;
+
+/// The name of the file that transcripts are written to.
+pub const TRANSCRIPT_FILE_NAME: &str = "transcript.json";
+
+/// The environment variable that enables recording.
+pub const RECORD_ENV_VAR: &str = "PIXI_BUILD_BACKEND_RECORD";
+
+/// A recorded JSON-RPC session.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Transcript {
+    pub entries: Vec<TranscriptEntry>,
+}
+
+/// A single request and the response to it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscriptEntry {
+    pub method: String,
+    pub params: Params,
+
+    /// The work directory the request refers to, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub work_directory: Option<PathBuf>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<jsonrpc_core::Error>,
+}
+
+/// The outcome of replaying a single [`TranscriptEntry`].
+#[derive(Debug, Clone)]
+pub struct ReplayedEntry {
+    pub method: String,
+    pub outcome: ReplayOutcome,
+}
+
+/// How the response to a replayed request compares to the recorded one.
+#[derive(Debug, Clone)]
+pub enum ReplayOutcome {
+    /// The request succeeded with the recorded result.
+    SameResult,
+    /// The request succeeded, but with a different result.
+    DifferentResult(Value),
+    /// The request failed, like when it was recorded.
+    FailedAsRecorded,
+    /// The request failed, but it succeeded when it was recorded.
+    UnexpectedFailure(Value),
+    /// The request succeeded, but it failed when it was recorded.
+    UnexpectedSuccess,
+}
+
+impl ReplayOutcome {
+    /// Returns true if the request succeeded or failed like when it was
+    /// recorded.
+    pub fn matches_recording(&self) -> bool {
+        !matches!(
+            self,
+            ReplayOutcome::UnexpectedFailure(_) | ReplayOutcome::UnexpectedSuccess
+        )
+    }
+}
+
+impl std::fmt::Display for ReplayOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReplayOutcome::SameResult => write!(f, "succeeded with the recorded result"),
+            ReplayOutcome::DifferentResult(result) => {
+                write!(f, "succeeded with a different result: {result}")
+            }
+            ReplayOutcome::FailedAsRecorded => write!(f, "failed, like when recorded"),
+            ReplayOutcome::UnexpectedFailure(error) => {
+                write!(f, "failed, but succeeded when recorded: {error}")
+            }
+            ReplayOutcome::UnexpectedSuccess => write!(f, "succeeded, but failed when recorded"),
+        }
+    }
+}
+
+impl Transcript {
+    /// Reads a transcript from a file.
+    pub fn from_path(path: &Path) -> miette::Result<Self> {
+        let contents = fs_err::read_to_string(path).into_diagnostic()?;
+        serde_json::from_str(&contents)
+            .into_diagnostic()
+            .with_context(|| format!("failed to parse the transcript '{}'", path.display()))
+    }
+}
+
+/// Records the requests of a single session. Recording is a no-op if it is
+/// disabled.
+#[derive(Debug, Default, Clone)]
+pub struct SessionRecorder {
+    entries: Option<Arc<Mutex<Vec<TranscriptEntry>>>>,
+}
+
+impl SessionRecorder {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            entries: enabled.then(Default::default),
+        }
+    }
+
+    /// Awaits `response` and records it together with the request. If the
+    /// request refers to a `work_directory`, the transcript is written to its
+    /// debug directory.
+    pub async fn record(
+        &self,
+        method: &str,
+        params: Params,
+        work_directory: Option<&Path>,
+        response: impl Future<Output = jsonrpc_core::Result<Value>>,
+    ) -> jsonrpc_core::Result<Value> {
+        let response = response.await;
+        let Some(entries) = &self.entries else {
+            return response;
+        };
+
+        let (result, error) = match &response {
+            Ok(result) => (Some(result.clone()), None),
+            Err(error) => (None, Some(error.clone())),
+        };
+        let transcript = {
+            let mut entries = entries.lock().expect("lock is poisoned");
+            entries.push(TranscriptEntry {
+                method: method.to_string(),
+                params,
+                work_directory: work_directory.map(Path::to_path_buf),
+                result,
+                error,
+            });
+            work_directory.map(|work_directory| Transcript {
+                entries: entries
+                    .iter()
+                    .filter(|entry| {
+                        entry
+                            .work_directory
+                            .as_deref()
+                            .is_none_or(|dir| dir == work_directory)
+                    })
+                    .cloned()
+                    .collect(),
+            })
+        };
+
+        if let (Some(work_directory), Some(transcript)) = (work_directory, transcript)
+            && let Err(err) = write_transcript(work_directory, &transcript)
+        {
+            tracing::warn!("failed to write the transcript: {err:?}");
+        }
+
+        response
+    }
+}
+
+fn write_transcript(work_directory: &Path, transcript: &Transcript) -> miette::Result<()> {
+    let debug_dir = work_directory.join(DEBUG_OUTPUT_DIR);
+    fs_err::create_dir_all(&debug_dir).into_diagnostic()?;
+    let json = serde_json::to_string_pretty(transcript).into_diagnostic()?;
+    fs_err::write(debug_dir.join(TRANSCRIPT_FILE_NAME), json).into_diagnostic()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn params(value: Value) -> Params {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_transcript_per_work_directory() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let first = tmp_dir.path().join("first");
+        let second = tmp_dir.path().join("second");
+
+        let recorder = SessionRecorder::new(true);
+        recorder
+            .record("initialize", params(json!({})), None, async {
+                Ok(json!({}))
+            })
+            .await
+            .unwrap();
+        recorder
+            .record(
+                "conda/build_v1",
+                params(json!({"n": 1})),
+                Some(&first),
+                async { Ok(json!(1)) },
+            )
+            .await
+            .unwrap();
+        recorder
+            .record(
+                "conda/build_v1",
+                params(json!({"n": 2})),
+                Some(&second),
+                async { Err(jsonrpc_core::Error::internal_error()) },
+            )
+            .await
+            .unwrap_err();
+
+        let transcript =
+            Transcript::from_path(&second.join("debug").join(TRANSCRIPT_FILE_NAME)).unwrap();
+        let methods = transcript
+            .entries
+            .iter()
+            .map(|entry| entry.method.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(methods, ["initialize", "conda/build_v1"]);
+        assert!(transcript.entries[1].error.is_some());
+        assert_eq!(transcript.entries[1].params, params(json!({"n": 2})));
+    }
+
+    #[tokio::test]
+    async fn test_disabled_recorder_writes_nothing() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        SessionRecorder::default()
+            .record(
+                "conda/build_v1",
+                Params::None,
+                Some(tmp_dir.path()),
+                async { Ok(json!(1)) },
+            )
+            .await
+            .unwrap();
+        assert!(!tmp_dir.path().join("debug").exists());
+    }
+}
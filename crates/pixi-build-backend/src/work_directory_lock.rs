@@ -0,0 +1,106 @@
+//! Exclusive access to the work directory of a request.
+//!
+//! The frontend may send multiple `conda/build_v1` requests at the same time,
+//! e.g. to build different variants of the same package. Requests for
+//! different work directories run in parallel, but requests that share a work
+//! directory would overwrite each other's `debug/` and `work/` directories.
+//! Such requests wait for each other by taking a file lock inside the work
+//! directory, which also protects against other backend processes.
+
+use std::path::{Path, PathBuf};
+
+use miette::{Context, IntoDiagnostic};
+
+/// The name of the lock file inside the work directory.
+pub const LOCK_FILE_NAME: &str = ".pixi-build.lock";
+
+/// An exclusive lock on a work directory. The lock is released when this is
+/// dropped.
+#[derive(Debug)]
+pub struct WorkDirectoryLock {
+    path: PathBuf,
+    _file: std::fs::File,
+}
+
+impl WorkDirectoryLock {
+    /// Acquires the lock on `work_directory`, creating the directory if it
+    /// does not exist yet. Waits until the lock is released if it is held by
+    /// another request or process.
+    pub async fn acquire(work_directory: &Path) -> miette::Result<Self> {
+        let path = work_directory.join(LOCK_FILE_NAME);
+        tokio::task::spawn_blocking(move || Self::acquire_blocking(path))
+            .await
+            .into_diagnostic()?
+    }
+
+    fn acquire_blocking(path: PathBuf) -> miette::Result<Self> {
+        let work_directory = path.parent().expect("the lock file is inside a directory");
+        fs_err::create_dir_all(work_directory)
+            .into_diagnostic()
+            .context("failed to create the work directory")?;
+
+        let file = fs_err::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&path)
+            .into_diagnostic()?
+            .into_parts()
+            .0;
+
+        if !matches!(file.try_lock(), Ok(())) {
+            tracing::info!(
+                "waiting for another build in '{}' to finish",
+                work_directory.display()
+            );
+            file.lock()
+                .into_diagnostic()
+                .with_context(|| format!("failed to lock '{}'", path.display()))?;
+        }
+
+        Ok(Self { path, _file: file })
+    }
+
+    /// Returns the path of the lock file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_lock_is_exclusive() {
+        let work_directory = tempfile::tempdir().unwrap();
+        let lock = WorkDirectoryLock::acquire(work_directory.path())
+            .await
+            .unwrap();
+        assert!(lock.path().exists());
+
+        let second = tokio::spawn({
+            let work_directory = work_directory.path().to_path_buf();
+            async move { WorkDirectoryLock::acquire(&work_directory).await }
+        });
+
+        // The second lock can only be acquired once the first one is dropped.
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(!second.is_finished());
+        drop(lock);
+        second.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_different_directories_do_not_block() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let _first = WorkDirectoryLock::acquire(&tmp_dir.path().join("a"))
+            .await
+            .unwrap();
+        let _second = WorkDirectoryLock::acquire(&tmp_dir.path().join("b"))
+            .await
+            .unwrap();
+    }
+}
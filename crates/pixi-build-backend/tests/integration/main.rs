@@ -1,3 +1,4 @@
 mod common;
 
 mod protocol;
+mod transports;
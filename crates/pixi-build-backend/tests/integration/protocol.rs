@@ -1,17 +1,28 @@
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 use crate::common::model::{convert_test_model_to_project_model_v1, load_project_model_from_json};
 use imp::TestGenerateRecipe;
-use pixi_build_backend::{intermediate_backend::IntermediateBackend, protocol::Protocol};
-use pixi_build_types::procedures::conda_build_v1::{CondaBuildV1Output, CondaBuildV1Params};
+use pixi_build_backend::{
+    intermediate_backend::{IntermediateBackend, IntermediateBackendInstantiator},
+    notifications::{BuildNotificationLayer, BuildNotifier},
+    protocol::{GenerateRecipeParams, Protocol, ProtocolInstantiator},
+};
+use pixi_build_types::{
+    VariantValue,
+    procedures::{
+        conda_build_v1::{CondaBuildV1Output, CondaBuildV1Params},
+        initialize::InitializeParams,
+    },
+};
 use rattler_build::console_utils::LoggingOutputHandler;
 use rattler_conda_types::{ChannelUrl, Platform};
-use serde_json::json;
+use serde_json::{Value, json};
 use tempfile::TempDir;
+use tracing_subscriber::layer::SubscriberExt;
 use url::Url;
 
 #[cfg(test)]
-mod imp {
+pub(crate) mod imp {
     use miette::IntoDiagnostic;
     use pixi_build_backend::generated_recipe::{
         BackendConfig, DefaultMetadataProvider, GenerateRecipe, GeneratedRecipe, PythonParams,
@@ -29,6 +40,8 @@ mod imp {
         /// If set, internal state will be logged as files in that directory
         #[serde(alias = "debug_dir")]
         pub debug_dir: Option<PathBuf>,
+        pub reproducible: Option<bool>,
+        pub source_date_epoch: Option<i64>,
     }
 
     #[cfg(test)]
@@ -40,6 +53,14 @@ mod imp {
             self.debug_dir.as_deref()
         }
 
+        fn reproducible(&self) -> bool {
+            self.reproducible.unwrap_or(false)
+        }
+
+        fn source_date_epoch(&self) -> Option<i64> {
+            self.source_date_epoch
+        }
+
         fn merge_with_target_config(&self, target_config: &Self) -> miette::Result<Self> {
             if target_config.debug_dir.is_some() {
                 miette::bail!("`debug_dir` cannot have a target specific value");
@@ -47,6 +68,8 @@ mod imp {
 
             Ok(Self {
                 debug_dir: self.debug_dir.clone(),
+                reproducible: target_config.reproducible.or(self.reproducible),
+                source_date_epoch: target_config.source_date_epoch.or(self.source_date_epoch),
             })
         }
     }
@@ -142,3 +165,268 @@ async fn test_conda_build_v1() {
 
     assert!(build_dir.join("debug").join("recipe.yaml").exists());
 }
+
+#[tokio::test]
+async fn test_conda_build_v1_is_reproducible() {
+    let tmp_dir = TempDir::new().unwrap();
+    let tmp_dir_path = tmp_dir.path().to_path_buf();
+
+    let pixi_manifest = tmp_dir_path.join("pixi.toml");
+
+    let original_model = load_project_model_from_json("minimal_project_model_for_build.json");
+    let project_model_v1 = convert_test_model_to_project_model_v1(original_model);
+    fs_err::write(&pixi_manifest, toml::to_string(&project_model_v1).unwrap()).unwrap();
+
+    let config = json!({
+        "reproducible": true,
+        "source-date-epoch": 1700000000,
+    });
+
+    let intermediate_backend: IntermediateBackend<TestGenerateRecipe> = IntermediateBackend::new(
+        pixi_manifest.clone(),
+        Some(tmp_dir_path.clone()),
+        project_model_v1,
+        Arc::default(),
+        config,
+        Default::default(),
+        LoggingOutputHandler::default(),
+        None,
+    )
+    .unwrap();
+
+    // Build the same package twice in different directories.
+    let mut digests = Vec::new();
+    for attempt in ["first", "second"] {
+        let build_params = CondaBuildV1Params {
+            channels: vec![],
+            build_prefix: None,
+            host_prefix: None,
+            run_constraints: None,
+            run_dependencies: None,
+            run_exports: None,
+            output: CondaBuildV1Output {
+                name: "minimal-package".parse().unwrap(),
+                version: None,
+                build: None,
+                subdir: Platform::current(),
+                variant: Default::default(),
+            },
+            work_directory: tmp_dir_path.join(attempt).join("work"),
+            output_directory: Some(tmp_dir_path.join(attempt).join("output")),
+            editable: None,
+        };
+
+        let result = intermediate_backend
+            .conda_build_v1(build_params)
+            .await
+            .unwrap();
+
+        digests.push(
+            rattler_digest::compute_file_digest::<rattler_digest::Sha256>(&result.output_file)
+                .unwrap(),
+        );
+    }
+
+    assert_eq!(
+        digests[0], digests[1],
+        "building the same package twice should result in identical artifacts"
+    );
+}
+
+#[tokio::test]
+async fn test_generate_recipe() {
+    let tmp_dir = TempDir::new().unwrap();
+    let pixi_manifest = tmp_dir.path().join("pixi.toml");
+
+    let original_model = load_project_model_from_json("minimal_project_model_for_build.json");
+    let project_model_v1 = convert_test_model_to_project_model_v1(original_model);
+
+    let instantiator = IntermediateBackendInstantiator::new(
+        LoggingOutputHandler::default(),
+        Arc::new(TestGenerateRecipe::default()),
+    );
+    let generated_recipe = instantiator
+        .generate_recipe(GenerateRecipeParams {
+            initialize: InitializeParams {
+                workspace_root: None,
+                source_dir: None,
+                manifest_path: pixi_manifest,
+                project_model: Some(project_model_v1.into()),
+                configuration: None,
+                target_configuration: None,
+                cache_directory: None,
+            },
+            host_platform: Platform::current(),
+            variant_configuration: None,
+        })
+        .await
+        .unwrap();
+
+    let recipe = generated_recipe.recipe.to_yaml_pretty().unwrap();
+    assert!(recipe.contains("minimal-package"), "{recipe}");
+}
+
+#[tokio::test]
+async fn test_concurrent_builds_report_to_their_own_output() {
+    let tmp_dir = TempDir::new().unwrap();
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let notifier = BuildNotifier::new(Some(tx));
+    notifier.set_enabled(true);
+    let subscriber = tracing_subscriber::registry().with(BuildNotificationLayer);
+    let _default = tracing::subscriber::set_default(subscriber);
+
+    let names = ["first-package", "second-package"];
+    let [first, second] = names.map(|name| {
+        let package_dir = tmp_dir.path().join(name);
+        fs_err::create_dir_all(&package_dir).unwrap();
+        let pixi_manifest = package_dir.join("pixi.toml");
+
+        let mut model = load_project_model_from_json("minimal_project_model_for_build.json");
+        model.name = name.to_string();
+        let project_model_v1 = convert_test_model_to_project_model_v1(model);
+        fs_err::write(&pixi_manifest, toml::to_string(&project_model_v1).unwrap()).unwrap();
+
+        let backend: IntermediateBackend<TestGenerateRecipe> = IntermediateBackend::new(
+            pixi_manifest,
+            Some(package_dir.clone()),
+            project_model_v1,
+            Arc::default(),
+            json!({}),
+            Default::default(),
+            LoggingOutputHandler::default(),
+            None,
+        )
+        .unwrap();
+        let params = CondaBuildV1Params {
+            channels: vec![],
+            build_prefix: None,
+            host_prefix: None,
+            run_constraints: None,
+            run_dependencies: None,
+            run_exports: None,
+            output: CondaBuildV1Output {
+                name: name.parse().unwrap(),
+                version: None,
+                build: None,
+                subdir: Platform::current(),
+                variant: Default::default(),
+            },
+            work_directory: package_dir.join("work"),
+            output_directory: Some(package_dir.join("output")),
+            editable: None,
+        };
+        (backend, params)
+    });
+
+    let (first_result, second_result) = notifier
+        .scope(async {
+            tokio::join!(
+                first.0.conda_build_v1(first.1),
+                second.0.conda_build_v1(second.1),
+            )
+        })
+        .await;
+    first_result.unwrap();
+    second_result.unwrap();
+
+    // Every output only receives the phases and log lines of its own build.
+    let mut phases = HashMap::<String, Vec<String>>::new();
+    let mut log_lines = HashMap::<String, usize>::new();
+    while let Ok(notification) = rx.try_recv() {
+        let notification: Value = serde_json::from_str(&notification).unwrap();
+        let output = notification["params"]["output"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        match notification["method"].as_str().unwrap() {
+            "build/progress" => phases.entry(output).or_default().push(
+                notification["params"]["phase"]
+                    .as_str()
+                    .unwrap()
+                    .to_string(),
+            ),
+            "build/log" => *log_lines.entry(output).or_default() += 1,
+            method => panic!("unexpected notification {method}"),
+        }
+    }
+
+    assert_eq!(phases.len(), names.len(), "{phases:?}");
+    for name in names {
+        let mut output_phases = phases[name].clone();
+        output_phases.dedup();
+        assert_eq!(
+            output_phases,
+            ["rendering", "building", "packaging", "finished"],
+            "{name}"
+        );
+        assert!(
+            log_lines.get(name).is_some_and(|lines| *lines > 0),
+            "{name}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_concurrent_builds_write_their_own_debug_files() {
+    let tmp_dir = TempDir::new().unwrap();
+    let pixi_manifest = tmp_dir.path().join("pixi.toml");
+
+    let original_model = load_project_model_from_json("minimal_project_model_for_build.json");
+    let project_model_v1 = convert_test_model_to_project_model_v1(original_model);
+    fs_err::write(&pixi_manifest, toml::to_string(&project_model_v1).unwrap()).unwrap();
+
+    let backend: IntermediateBackend<TestGenerateRecipe> = IntermediateBackend::new(
+        pixi_manifest,
+        Some(tmp_dir.path().to_path_buf()),
+        project_model_v1,
+        Arc::default(),
+        json!({}),
+        Default::default(),
+        LoggingOutputHandler::default(),
+        None,
+    )
+    .unwrap();
+
+    let pythons = ["3.11", "3.12"];
+    let [first, second] = pythons.map(|python| CondaBuildV1Params {
+        channels: vec![],
+        build_prefix: None,
+        host_prefix: None,
+        run_constraints: None,
+        run_dependencies: None,
+        run_exports: None,
+        output: CondaBuildV1Output {
+            name: "minimal-package".parse().unwrap(),
+            version: None,
+            build: None,
+            subdir: Platform::current(),
+            variant: [(
+                String::from("python"),
+                VariantValue::String(python.to_string()),
+            )]
+            .into_iter()
+            .collect(),
+        },
+        work_directory: tmp_dir.path().join(python).join("work"),
+        output_directory: Some(tmp_dir.path().join(python).join("output")),
+        editable: None,
+    });
+
+    let (first_result, second_result) = tokio::join!(
+        backend.conda_build_v1(first),
+        backend.conda_build_v1(second)
+    );
+    first_result.unwrap();
+    second_result.unwrap();
+
+    // The debug files of every build only contain the variant of that build.
+    for (python, other) in [(pythons[0], pythons[1]), (pythons[1], pythons[0])] {
+        let debug_dir = tmp_dir.path().join(python).join("work").join("debug");
+        assert!(debug_dir.join("recipe.yaml").exists(), "{python}");
+
+        let variants = fs_err::read_to_string(debug_dir.join("variants.yaml")).unwrap();
+        assert!(variants.contains(python), "{python}: {variants}");
+        assert!(!variants.contains(other), "{python}: {variants}");
+    }
+}
@@ -0,0 +1,113 @@
+use std::{sync::Arc, time::Duration};
+
+use futures_util::{SinkExt, StreamExt};
+use pixi_build_backend::{intermediate_backend::IntermediateBackendInstantiator, server::Server};
+use pixi_build_types::procedures::negotiate_capabilities;
+use rattler_build::console_utils::LoggingOutputHandler;
+use serde_json::{Value, json};
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::protocol::imp::TestGenerateRecipe;
+
+fn test_server() -> Server<IntermediateBackendInstantiator<TestGenerateRecipe>> {
+    Server::new(IntermediateBackendInstantiator::new(
+        LoggingOutputHandler::default(),
+        Arc::default(),
+    ))
+}
+
+fn negotiate_capabilities_request(id: u64) -> String {
+    json!({
+        "jsonrpc": "2.0",
+        "method": negotiate_capabilities::METHOD_NAME,
+        "params": { "capabilities": {} },
+        "id": id,
+    })
+    .to_string()
+}
+
+fn assert_negotiate_capabilities_response(response: &str, id: u64) {
+    let response: Value = serde_json::from_str(response).unwrap();
+    assert_eq!(response["id"], json!(id), "unexpected response: {response}");
+    assert!(
+        response["result"]["capabilities"].is_object(),
+        "unexpected response: {response}"
+    );
+}
+
+/// Retries `connect` until the server is accepting connections.
+async fn connect<T, E: std::fmt::Debug, F: Future<Output = Result<T, E>>>(
+    mut connect: impl FnMut() -> F,
+) -> T {
+    let mut attempts = 0;
+    loop {
+        match connect().await {
+            Ok(connection) => return connection,
+            Err(err) if attempts >= 100 => panic!("failed to connect to the server: {err:?}"),
+            Err(_) => {
+                attempts += 1;
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_unix_socket_transport() {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    let tmp_dir = tempfile::TempDir::new().unwrap();
+    let socket_path = tmp_dir.path().join("backend.sock");
+
+    let server = tokio::spawn({
+        let socket_path = socket_path.clone();
+        async move { test_server().run_over_unix_socket(&socket_path).await }
+    });
+
+    // Every client gets its own session on the same backend process.
+    for id in [1, 2] {
+        let stream = connect(|| tokio::net::UnixStream::connect(&socket_path)).await;
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = BufReader::new(reader).lines();
+
+        writer
+            .write_all(format!("{}\n", negotiate_capabilities_request(id)).as_bytes())
+            .await
+            .unwrap();
+        let response = lines.next_line().await.unwrap().unwrap();
+        assert_negotiate_capabilities_response(&response, id);
+    }
+
+    server.abort();
+}
+
+#[tokio::test]
+async fn test_websocket_transport() {
+    // Find a free port for the server.
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+
+    let server = tokio::spawn(test_server().run_over_websocket(port));
+
+    let (mut websocket, _) =
+        connect(|| tokio_tungstenite::connect_async(format!("ws://127.0.0.1:{port}"))).await;
+
+    websocket
+        .send(Message::text(negotiate_capabilities_request(1)))
+        .await
+        .unwrap();
+    let response = loop {
+        match websocket.next().await.unwrap().unwrap() {
+            Message::Text(text) => break text.to_string(),
+            _ => continue,
+        }
+    };
+    assert_negotiate_capabilities_response(&response, 1);
+
+    websocket.close(None).await.unwrap();
+    server.abort();
+}
@@ -1,4 +1,4 @@
-use minijinja::Environment;
+use pixi_build_backend::build_script::BuildScriptOptions;
 use serde::Serialize;
 
 #[derive(Serialize)]
@@ -22,12 +22,10 @@ pub enum BuildPlatform {
 }
 
 impl BuildScriptContext {
-    pub fn render(&self) -> String {
-        let env = Environment::new();
-        let template = env
-            .template_from_str(include_str!("build_script.j2"))
-            .unwrap();
-        template.render(self).unwrap().trim().to_string()
+    /// Renders the build script, taking the user customizations in `options`
+    /// into account.
+    pub fn render(&self, options: &BuildScriptOptions) -> miette::Result<String> {
+        options.render(include_str!("build_script.j2"), self)
     }
 }
 
@@ -49,7 +47,7 @@ mod test {
             extra_args: extra_args.clone(),
             has_host_python,
         };
-        let script = context.render();
+        let script = context.render(&BuildScriptOptions::default()).unwrap();
 
         let mut settings = insta::Settings::clone_current();
         settings.set_snapshot_suffix(format!(
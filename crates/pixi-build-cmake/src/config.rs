@@ -1,7 +1,13 @@
-use std::path::{Path, PathBuf};
+use std::path::Path;
 
 use indexmap::IndexMap;
-use pixi_build_backend::generated_recipe::BackendConfig;
+use pixi_build_backend::{
+    config::{CommonBackendConfig, GeneratorConfig, merge},
+    generated_recipe::BackendConfig,
+    merge_target_config,
+    package_format::PackageFormat,
+    sandbox::SandboxConfig,
+};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Default, Deserialize, Serialize, Clone)]
@@ -10,66 +16,87 @@ pub struct CMakeBackendConfig {
     /// Extra args for CMake invocation
     #[serde(default)]
     pub extra_args: Vec<String>,
-    /// Environment Variables
-    #[serde(default)]
-    pub env: IndexMap<String, String>,
-    /// Deprecated. Setting this has no effect; debug data is always written to
-    /// the `debug` subdirectory of the work directory.
-    #[serde(alias = "debug_dir")]
-    pub debug_dir: Option<PathBuf>,
-    /// Extra input globs to include in addition to the default ones
-    #[serde(default)]
-    pub extra_input_globs: Vec<String>,
-    /// List of compilers to use (e.g., ["c", "cxx", "cuda"])
-    /// If not specified, a default will be used
-    pub compilers: Option<Vec<String>>,
+    /// Options that every backend supports.
+    #[serde(flatten)]
+    pub common: CommonBackendConfig,
+    /// Options of the backends that generate a recipe.
+    #[serde(flatten)]
+    pub generator: GeneratorConfig,
 }
 
 impl BackendConfig for CMakeBackendConfig {
     fn debug_dir(&self) -> Option<&Path> {
-        self.debug_dir.as_deref()
+        self.common.debug_dir()
+    }
+
+    fn run_tests(&self) -> bool {
+        self.common.run_tests()
+    }
+
+    fn package_format(&self) -> PackageFormat {
+        self.common.package_format()
+    }
+
+    fn reproducible(&self) -> bool {
+        self.common.reproducible()
+    }
+
+    fn source_date_epoch(&self) -> Option<i64> {
+        self.common.source_date_epoch()
+    }
+
+    fn sandbox(&self) -> Option<&SandboxConfig> {
+        self.common.sandbox()
+    }
+
+    fn build_constraints(&self) -> Option<&IndexMap<String, String>> {
+        Some(&self.generator.build_constraints)
+    }
+
+    fn host_constraints(&self) -> Option<&IndexMap<String, String>> {
+        Some(&self.generator.host_constraints)
+    }
+
+    fn recipe_overrides(&self) -> Option<&Path> {
+        self.generator.recipe_overrides.as_deref()
+    }
+
+    fn build_script_template(&self) -> Option<&Path> {
+        self.generator.build_script_template.as_deref()
+    }
+
+    fn pre_build_script(&self) -> Option<&str> {
+        self.generator.pre_build_script.as_deref()
+    }
+
+    fn post_build_script(&self) -> Option<&str> {
+        self.generator.post_build_script.as_deref()
     }
 
     /// Merge this configuration with a target-specific configuration.
     /// Target-specific values override base values using the following rules:
     /// - extra_args: Platform-specific completely replaces base
-    /// - env: Platform env vars override base, others merge
-    /// - debug_dir: Not allowed to have target specific value
-    /// - extra_input_globs: Platform-specific completely replaces base
-    /// - compilers: Platform-specific completely replaces base
+    /// - common: See [`CommonBackendConfig`]
+    /// - generator: See [`GeneratorConfig`]
     fn merge_with_target_config(&self, target_config: &Self) -> miette::Result<Self> {
-        if target_config.debug_dir.is_some() {
-            miette::bail!("`debug_dir` cannot have a target specific value");
-        }
-
-        Ok(Self {
-            extra_args: if target_config.extra_args.is_empty() {
-                self.extra_args.clone()
-            } else {
-                target_config.extra_args.clone()
-            },
-            env: {
-                let mut merged_env = self.env.clone();
-                merged_env.extend(target_config.env.clone());
-                merged_env
-            },
-            debug_dir: self.debug_dir.clone(),
-            extra_input_globs: if target_config.extra_input_globs.is_empty() {
-                self.extra_input_globs.clone()
-            } else {
-                target_config.extra_input_globs.clone()
-            },
-            compilers: target_config
-                .compilers
-                .clone()
-                .or_else(|| self.compilers.clone()),
-        })
+        Ok(merge_target_config!(
+            self,
+            target_config,
+            Self {
+                extra_args: merge::replace,
+                common: merge::deep_merge,
+                generator: merge::deep_merge,
+            }
+        ))
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use pixi_build_backend::generated_recipe::BackendConfig;
+    use pixi_build_backend::{
+        config::{CommonBackendConfig, GeneratorConfig},
+        generated_recipe::BackendConfig,
+    };
     use serde_json::json;
     use std::path::PathBuf;
 
@@ -89,10 +116,16 @@ mod tests {
 
         let base_config = CMakeBackendConfig {
             extra_args: vec!["--base-arg".to_string()],
-            env: base_env,
-            debug_dir: Some(PathBuf::from("/base/debug")),
-            extra_input_globs: vec!["*.base".to_string()],
-            compilers: Some(vec!["cxx".to_string()]),
+            common: CommonBackendConfig {
+                debug_dir: Some(PathBuf::from("/base/debug")),
+                extra_input_globs: vec!["*.base".to_string()],
+                ..Default::default()
+            },
+            generator: GeneratorConfig {
+                env: base_env,
+                compilers: Some(vec!["cxx".to_string()]),
+                ..Default::default()
+            },
         };
 
         let mut target_env = indexmap::IndexMap::new();
@@ -101,10 +134,15 @@ mod tests {
 
         let target_config = CMakeBackendConfig {
             extra_args: vec!["--target-arg".to_string()],
-            env: target_env,
-            debug_dir: None,
-            extra_input_globs: vec!["*.target".to_string()],
-            compilers: Some(vec!["c".to_string(), "cuda".to_string()]),
+            common: CommonBackendConfig {
+                extra_input_globs: vec!["*.target".to_string()],
+                ..Default::default()
+            },
+            generator: GeneratorConfig {
+                env: target_env,
+                compilers: Some(vec!["c".to_string(), "cuda".to_string()]),
+                ..Default::default()
+            },
         };
 
         let merged = base_config
@@ -115,25 +153,31 @@ mod tests {
         assert_eq!(merged.extra_args, vec!["--target-arg".to_string()]);
 
         // env should merge with target taking precedence
-        assert_eq!(merged.env.get("BASE_VAR"), Some(&"base_value".to_string()));
         assert_eq!(
-            merged.env.get("TARGET_VAR"),
+            merged.generator.env.get("BASE_VAR"),
+            Some(&"base_value".to_string())
+        );
+        assert_eq!(
+            merged.generator.env.get("TARGET_VAR"),
             Some(&"target_value".to_string())
         );
         assert_eq!(
-            merged.env.get("SHARED_VAR"),
+            merged.generator.env.get("SHARED_VAR"),
             Some(&"target_shared".to_string())
         );
 
         // debug_dir should use base value
-        assert_eq!(merged.debug_dir, Some(PathBuf::from("/base/debug")));
+        assert_eq!(merged.common.debug_dir, Some(PathBuf::from("/base/debug")));
 
         // extra_input_globs should be completely overridden
-        assert_eq!(merged.extra_input_globs, vec!["*.target".to_string()]);
+        assert_eq!(
+            merged.common.extra_input_globs,
+            vec!["*.target".to_string()]
+        );
 
         // compilers should be completely overridden by target
         assert_eq!(
-            merged.compilers,
+            merged.generator.compilers,
             Some(vec!["c".to_string(), "cuda".to_string()])
         );
     }
@@ -145,10 +189,16 @@ mod tests {
 
         let base_config = CMakeBackendConfig {
             extra_args: vec!["--base-arg".to_string()],
-            env: base_env,
-            debug_dir: Some(PathBuf::from("/base/debug")),
-            extra_input_globs: vec!["*.base".to_string()],
-            compilers: Some(vec!["cxx".to_string()]),
+            common: CommonBackendConfig {
+                debug_dir: Some(PathBuf::from("/base/debug")),
+                extra_input_globs: vec!["*.base".to_string()],
+                ..Default::default()
+            },
+            generator: GeneratorConfig {
+                env: base_env,
+                compilers: Some(vec!["cxx".to_string()]),
+                ..Default::default()
+            },
         };
 
         let empty_target_config = CMakeBackendConfig::default();
@@ -159,21 +209,30 @@ mod tests {
 
         // Should keep base values when target is empty
         assert_eq!(merged.extra_args, vec!["--base-arg".to_string()]);
-        assert_eq!(merged.env.get("BASE_VAR"), Some(&"base_value".to_string()));
-        assert_eq!(merged.debug_dir, Some(PathBuf::from("/base/debug")));
-        assert_eq!(merged.extra_input_globs, vec!["*.base".to_string()]);
-        assert_eq!(merged.compilers, Some(vec!["cxx".to_string()]));
+        assert_eq!(
+            merged.generator.env.get("BASE_VAR"),
+            Some(&"base_value".to_string())
+        );
+        assert_eq!(merged.common.debug_dir, Some(PathBuf::from("/base/debug")));
+        assert_eq!(merged.common.extra_input_globs, vec!["*.base".to_string()]);
+        assert_eq!(merged.generator.compilers, Some(vec!["cxx".to_string()]));
     }
 
     #[test]
     fn test_merge_target_debug_dir_error() {
         let base_config = CMakeBackendConfig {
-            debug_dir: Some(PathBuf::from("/base/debug")),
+            common: CommonBackendConfig {
+                debug_dir: Some(PathBuf::from("/base/debug")),
+                ..Default::default()
+            },
             ..Default::default()
         };
 
         let target_config = CMakeBackendConfig {
-            debug_dir: Some(PathBuf::from("/target/debug")),
+            common: CommonBackendConfig {
+                debug_dir: Some(PathBuf::from("/target/debug")),
+                ..Default::default()
+            },
             ..Default::default()
         };
 
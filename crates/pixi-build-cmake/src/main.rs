@@ -5,6 +5,7 @@ use build_script::{BuildPlatform, BuildScriptContext};
 use config::CMakeBackendConfig;
 use miette::IntoDiagnostic;
 use pixi_build_backend::{
+    build_script::BuildScriptOptions,
     generated_recipe::{DefaultMetadataProvider, GenerateRecipe, GeneratedRecipe, PythonParams},
     intermediate_backend::IntermediateBackendInstantiator,
     traits::ProjectModel,
@@ -101,24 +102,31 @@ impl GenerateRecipe for CMakeGenerator {
             .host
             .contains_key(&SourcePackageName::from("python"));
 
-        let build_script = BuildScriptContext {
-            build_platform: if Platform::current().is_windows() {
-                BuildPlatform::Windows
-            } else {
-                BuildPlatform::Unix
-            },
-            source_dir: manifest_root.display().to_string(),
-            extra_args: config.extra_args.clone(),
-            has_host_python,
-        }
-        .render();
+        let build_script =
+            BuildScriptOptions::render_for_config(config, &manifest_root, |options| {
+                BuildScriptContext {
+                    build_platform: if Platform::current().is_windows() {
+                        BuildPlatform::Windows
+                    } else {
+                        BuildPlatform::Unix
+                    },
+                    source_dir: manifest_root.display().to_string(),
+                    extra_args: config.extra_args.clone(),
+                    has_host_python,
+                }
+                .render(options)
+            })?;
 
         generated_recipe.recipe.build.script = Script {
-            content: build_script,
-            env: config.env.clone(),
+            content: build_script.script,
+            env: config.generator.env.clone(),
             ..Default::default()
         };
 
+        generated_recipe
+            .build_input_globs
+            .extend(build_script.input_globs);
+
         Ok(generated_recipe)
     }
 
@@ -137,7 +145,7 @@ impl GenerateRecipe for CMakeGenerator {
         ]
         .iter()
         .map(|s: &&str| s.to_string())
-        .chain(config.extra_input_globs.clone())
+        .chain(config.common.extra_input_globs.clone())
         .collect())
     }
 
@@ -178,7 +186,9 @@ mod tests {
 
     use indexmap::IndexMap;
     use pixi_build_backend::{
-        protocol::ProtocolInstantiator, utils::test::intermediate_conda_outputs,
+        config::{CommonBackendConfig, GeneratorConfig},
+        protocol::ProtocolInstantiator,
+        utils::test::intermediate_conda_outputs,
     };
     use pixi_build_types::{
         ProjectModelV1, VariantValue,
@@ -193,7 +203,10 @@ mod tests {
     #[test]
     fn test_input_globs_includes_extra_globs() {
         let config = CMakeBackendConfig {
-            extra_input_globs: vec!["custom/*.c".to_string()],
+            common: CommonBackendConfig {
+                extra_input_globs: vec!["custom/*.c".to_string()],
+                ..Default::default()
+            },
             ..Default::default()
         };
 
@@ -273,7 +286,10 @@ mod tests {
             .generate_recipe(
                 &project_model,
                 &CMakeBackendConfig {
-                    env: env.clone(),
+                    generator: GeneratorConfig {
+                        env: env.clone(),
+                        ..Default::default()
+                    },
                     ..Default::default()
                 },
                 PathBuf::from("."),
@@ -520,7 +536,14 @@ mod tests {
             .generate_recipe(
                 &project_model,
                 &CMakeBackendConfig {
-                    compilers: Some(vec!["c".to_string(), "cxx".to_string(), "cuda".to_string()]),
+                    generator: GeneratorConfig {
+                        compilers: Some(vec![
+                            "c".to_string(),
+                            "cxx".to_string(),
+                            "cuda".to_string(),
+                        ]),
+                        ..Default::default()
+                    },
                     ..Default::default()
                 },
                 PathBuf::from("."),
@@ -573,10 +596,7 @@ mod tests {
         let generated_recipe = CMakeGenerator::default()
             .generate_recipe(
                 &project_model,
-                &CMakeBackendConfig {
-                    compilers: None,
-                    ..Default::default()
-                },
+                &CMakeBackendConfig::default(),
                 PathBuf::from("."),
                 Platform::Linux64,
                 None,
@@ -617,10 +637,7 @@ mod tests {
         let generated_recipe = CMakeGenerator::default()
             .generate_recipe(
                 &project_model,
-                &CMakeBackendConfig {
-                    compilers: None,
-                    ..Default::default()
-                },
+                &CMakeBackendConfig::default(),
                 PathBuf::from("."),
                 Platform::Linux64,
                 None,
@@ -1,5 +1,5 @@
 use super::config::{MojoBinConfig, MojoPkgConfig};
-use minijinja::Environment;
+use pixi_build_backend::build_script::BuildScriptOptions;
 use serde::Serialize;
 
 #[derive(Debug, Serialize)]
@@ -13,11 +13,9 @@ pub struct BuildScriptContext {
 }
 
 impl BuildScriptContext {
-    pub fn render(&self) -> String {
-        let env = Environment::new();
-        let template = env
-            .template_from_str(include_str!("build_script.j2"))
-            .unwrap();
-        template.render(self).unwrap().trim().to_string()
+    /// Renders the build script, taking the user customizations in `options`
+    /// into account.
+    pub fn render(&self, options: &BuildScriptOptions) -> miette::Result<String> {
+        options.render(include_str!("build_script.j2"), self)
     }
 }
@@ -5,133 +5,141 @@ use std::{
 
 use indexmap::IndexMap;
 use miette::Error;
-use pixi_build_backend::generated_recipe::BackendConfig;
+use pixi_build_backend::{
+    config::{
+        CommonBackendConfig, GeneratorConfig,
+        merge::{self, DeepMerge},
+    },
+    generated_recipe::BackendConfig,
+    merge_target_config,
+    package_format::PackageFormat,
+    sandbox::SandboxConfig,
+};
 use serde::{Deserialize, Serialize};
 
 /// Top level config struct for the Mojo backend.
 #[derive(Debug, Default, Deserialize, Clone)]
 #[serde(rename_all = "kebab-case")]
 pub struct MojoBackendConfig {
-    /// Environment Variables
-    #[serde(default)]
-    pub env: IndexMap<String, String>,
-
-    /// Dir that can be specified for outputting pixi debug state.
-    #[serde(alias = "debug_dir")]
-    pub debug_dir: Option<PathBuf>,
-
-    /// Extra input globs to include in addition to the default ones.
-    #[serde(default)]
-    pub extra_input_globs: Vec<String>,
-
     /// Binary executables to produce.
     pub bins: Option<Vec<MojoBinConfig>>,
 
     /// Packages to produce.
     pub pkg: Option<MojoPkgConfig>,
 
-    /// List of compilers to use (e.g., ["mojo", "c", "cxx"])
-    /// If not specified, defaults to ["mojo"]
-    pub compilers: Option<Vec<String>>,
+    /// Options that every backend supports.
+    #[serde(flatten)]
+    pub common: CommonBackendConfig,
+
+    /// Options of the backends that generate a recipe.
+    #[serde(flatten)]
+    pub generator: GeneratorConfig,
 }
 
 impl BackendConfig for MojoBackendConfig {
     fn debug_dir(&self) -> Option<&Path> {
-        self.debug_dir.as_deref()
+        self.common.debug_dir()
+    }
+
+    fn run_tests(&self) -> bool {
+        self.common.run_tests()
+    }
+
+    fn package_format(&self) -> PackageFormat {
+        self.common.package_format()
+    }
+
+    fn reproducible(&self) -> bool {
+        self.common.reproducible()
+    }
+
+    fn source_date_epoch(&self) -> Option<i64> {
+        self.common.source_date_epoch()
+    }
+
+    fn sandbox(&self) -> Option<&SandboxConfig> {
+        self.common.sandbox()
+    }
+
+    fn build_constraints(&self) -> Option<&IndexMap<String, String>> {
+        Some(&self.generator.build_constraints)
+    }
+
+    fn host_constraints(&self) -> Option<&IndexMap<String, String>> {
+        Some(&self.generator.host_constraints)
+    }
+
+    fn recipe_overrides(&self) -> Option<&Path> {
+        self.generator.recipe_overrides.as_deref()
+    }
+
+    fn build_script_template(&self) -> Option<&Path> {
+        self.generator.build_script_template.as_deref()
+    }
+
+    fn pre_build_script(&self) -> Option<&str> {
+        self.generator.pre_build_script.as_deref()
+    }
+
+    fn post_build_script(&self) -> Option<&str> {
+        self.generator.post_build_script.as_deref()
     }
 
     /// Merge this configuration with a target-specific configuration.
     /// Target-specific values override base values using the following rules:
     ///
-    /// - env: Platform env vars override base, others merge
-    /// - debug_dir: Not allowed to have target specific value
-    /// - extra_input_globs: Platform-specific completely replaces base
     /// - bins: Any bins with matching not-None names will be merged,
     ///   Any set-settings on the platform specific pkg override base
     ///   Any bins found only in target_config will be kept
     /// - pkg: Any set-settings on the platform specific pkg override base
+    /// - common: See [`CommonBackendConfig`]
+    /// - generator: See [`GeneratorConfig`]
     fn merge_with_target_config(&self, target_config: &Self) -> miette::Result<Self> {
-        if target_config.debug_dir.is_some() {
-            miette::bail!("`debug_dir` cannot have a target specific value");
-        }
-
-        let pkg = if target_config.pkg.is_some() {
-            if self.pkg.is_some() {
-                Some(
-                    self.pkg
-                        .as_ref()
-                        .unwrap()
-                        .merge_with_target_config(target_config.pkg.as_ref().unwrap())?,
-                )
-            } else {
-                target_config.pkg.clone()
+        Ok(merge_target_config!(
+            self,
+            target_config,
+            Self {
+                bins: merge_bins,
+                pkg: merge::deep_merge,
+                common: merge::deep_merge,
+                generator: merge::deep_merge,
             }
-        } else {
-            self.pkg.clone()
-        };
-
-        let bins = if target_config.bins.is_some() {
-            if self.bins.is_some() {
-                // Both base and target have binaries configured
-                // Override base with anything found in both target and base.
-                // If something is found only in base, drop it.
-                // If something is found only in target, drop it.
-                let base_bins: HashMap<_, _> = self
-                    .bins
-                    .as_ref()
-                    .unwrap()
-                    .iter()
-                    .filter(|p| p.name.is_some())
-                    .map(|p| (p.name.clone().unwrap(), p.clone()))
-                    .collect();
-
-                Some(
-                    target_config
-                        .bins
-                        .as_ref()
-                        .unwrap()
-                        .iter()
-                        .map(|p| match p.name.as_ref() {
-                            Some(name) => {
-                                if let Some(base_bin) = base_bins.get(name) {
-                                    base_bin.merge_with_target_config(p)
-                                } else {
-                                    Ok(p.clone())
-                                }
-                            }
-                            None => Ok(p.clone()),
-                        })
-                        .collect::<miette::Result<_>>()?,
-                )
-            } else {
-                target_config.bins.clone()
-            }
-        } else {
-            self.bins.clone()
-        };
-
-        Ok(Self {
-            env: {
-                let mut merged_env = self.env.clone();
-                merged_env.extend(target_config.env.clone());
-                merged_env
-            },
-            debug_dir: self.debug_dir.clone(),
-            extra_input_globs: if target_config.extra_input_globs.is_empty() {
-                self.extra_input_globs.clone()
-            } else {
-                target_config.extra_input_globs.clone()
-            },
-            bins,
-            pkg,
-            compilers: target_config
-                .compilers
-                .clone()
-                .or_else(|| self.compilers.clone()),
-        })
+        ))
     }
 }
 
+/// Merges the binaries of a target-specific configuration with the binaries of
+/// the base configuration.
+fn merge_bins(
+    _field: &str,
+    base: &Option<Vec<MojoBinConfig>>,
+    target: &Option<Vec<MojoBinConfig>>,
+) -> miette::Result<Option<Vec<MojoBinConfig>>> {
+    let (Some(base), Some(target)) = (base, target) else {
+        return Ok(target.clone().or_else(|| base.clone()));
+    };
+
+    // Both base and target have binaries configured
+    // Override base with anything found in both target and base.
+    // If something is found only in base, drop it.
+    // If something is found only in target, keep it.
+    let base_bins: HashMap<_, _> = base
+        .iter()
+        .filter_map(|p| p.name.as_ref().map(|name| (name, p)))
+        .collect();
+
+    target
+        .iter()
+        .map(
+            |p| match p.name.as_ref().and_then(|name| base_bins.get(name)) {
+                Some(base_bin) => base_bin.merge_with_target_config(p),
+                None => Ok(p.clone()),
+            },
+        )
+        .collect::<miette::Result<_>>()
+        .map(Some)
+}
+
 impl MojoBackendConfig {
     /// Auto-derive the bins and pkg config if they have not been specified by the user,
     /// or if they have only been partially specified.
@@ -290,31 +298,19 @@ impl MojoBinConfig {
     ///
     /// **Note** bins must have the same name to be merged.
     fn merge_with_target_config(&self, target_config: &Self) -> miette::Result<Self> {
-        if self.name.is_some() && target_config.name.is_some() {
-            if self.name.as_ref().unwrap() != target_config.name.as_ref().unwrap() {
-                miette::bail!("Both bins must have a set name to be merged");
-            }
-        } else {
+        if self.name.is_none() || self.name != target_config.name {
             miette::bail!("Both bins must have a set name to be merged");
         }
 
-        let path = if target_config.path.is_some() {
-            target_config.path.clone()
-        } else {
-            self.path.clone()
-        };
-
-        let extra_args = if target_config.extra_args.is_some() {
-            target_config.extra_args.clone()
-        } else {
-            self.extra_args.clone()
-        };
-
-        Ok(Self {
-            name: self.name.clone(),
-            path,
-            extra_args,
-        })
+        Ok(merge_target_config!(
+            self,
+            target_config,
+            Self {
+                name: merge::replace,
+                path: merge::replace,
+                extra_args: merge::replace,
+            }
+        ))
     }
 }
 
@@ -405,34 +401,22 @@ impl MojoPkgConfig {
         }
         None
     }
+}
 
+impl DeepMerge for MojoPkgConfig {
     /// Merge with a target-specific configuration.
     ///
     /// All target-settings that are not None will override base.
-    fn merge_with_target_config(&self, target_config: &Self) -> miette::Result<Self> {
-        let name = if target_config.name.is_some() {
-            target_config.name.clone()
-        } else {
-            self.name.clone()
-        };
-
-        let path = if target_config.path.is_some() {
-            target_config.path.clone()
-        } else {
-            self.path.clone()
-        };
-
-        let extra_args = if target_config.extra_args.is_some() {
-            target_config.extra_args.clone()
-        } else {
-            self.extra_args.clone()
-        };
-
-        Ok(Self {
-            name,
-            path,
-            extra_args,
-        })
+    fn deep_merge(&self, target: &Self) -> miette::Result<Self> {
+        Ok(merge_target_config!(
+            self,
+            target,
+            Self {
+                name: merge::replace,
+                path: merge::replace,
+                extra_args: merge::replace,
+            }
+        ))
     }
 }
 
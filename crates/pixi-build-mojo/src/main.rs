@@ -6,6 +6,7 @@ use config::{MojoBackendConfig, clean_project_name};
 use miette::{Error, IntoDiagnostic};
 use pixi_build_backend::generated_recipe::DefaultMetadataProvider;
 use pixi_build_backend::{
+    build_script::BuildScriptOptions,
     generated_recipe::{GenerateRecipe, GeneratedRecipe, PythonParams},
     intermediate_backend::IntermediateBackendInstantiator,
     traits::ProjectModel,
@@ -79,7 +80,7 @@ impl GenerateRecipe for MojoGenerator {
         // rattler-build selectors with simple string comparison.
         let model_dependencies = model.dependencies(Some(host_platform));
 
-        let compilers = config.compilers.clone().unwrap_or_default();
+        let compilers = config.generator.compilers.clone().unwrap_or_default();
 
         pixi_build_backend::compilers::add_compilers_to_requirements(
             &compilers,
@@ -93,21 +94,28 @@ impl GenerateRecipe for MojoGenerator {
             variants,
         );
 
-        let build_script = BuildScriptContext {
-            source_dir: manifest_root.display().to_string(),
-            bins,
-            pkg,
-        }
-        .render();
+        let build_script =
+            BuildScriptOptions::render_for_config(config, &manifest_root, |options| {
+                BuildScriptContext {
+                    source_dir: manifest_root.display().to_string(),
+                    bins,
+                    pkg,
+                }
+                .render(options)
+            })?;
 
         generated_recipe.recipe.build.script = Script {
-            content: build_script,
-            env: config.env.clone(),
+            content: build_script.script,
+            env: config.generator.env.clone(),
             ..Default::default()
         };
 
         generated_recipe.build_input_globs = Self::globs().collect::<BTreeSet<_>>();
 
+        generated_recipe
+            .build_input_globs
+            .extend(build_script.input_globs);
+
         Ok(generated_recipe)
     }
 
@@ -118,7 +126,7 @@ impl GenerateRecipe for MojoGenerator {
         _editable: bool,
     ) -> miette::Result<BTreeSet<String>> {
         Ok(Self::globs()
-            .chain(config.extra_input_globs.clone())
+            .chain(config.common.extra_input_globs.clone())
             .collect())
     }
 
@@ -170,6 +178,7 @@ mod tests {
 
     use crate::config::{MojoBinConfig, MojoPkgConfig};
     use indexmap::IndexMap;
+    use pixi_build_backend::config::{CommonBackendConfig, GeneratorConfig};
     use pixi_build_types::ProjectModelV1;
     use recipe_stage0::recipe::{Item, Value};
 
@@ -178,7 +187,10 @@ mod tests {
     #[test]
     fn test_input_globs_includes_extra_globs() {
         let config = MojoBackendConfig {
-            extra_input_globs: vec![String::from("**/.c")],
+            common: CommonBackendConfig {
+                extra_input_globs: vec![String::from("**/.c")],
+                ..Default::default()
+            },
             ..Default::default()
         };
 
@@ -355,7 +367,10 @@ mod tests {
             .generate_recipe(
                 &project_model,
                 &MojoBackendConfig {
-                    env: env.clone(),
+                    generator: GeneratorConfig {
+                        env: env.clone(),
+                        ..Default::default()
+                    },
                     ..Default::default()
                 },
                 temp.path().to_path_buf(),
@@ -445,7 +460,10 @@ mod tests {
             .generate_recipe(
                 &project_model,
                 &MojoBackendConfig {
-                    compilers: Some(vec!["c".to_string(), "cxx".to_string()]),
+                    generator: GeneratorConfig {
+                        compilers: Some(vec!["c".to_string(), "cxx".to_string()]),
+                        ..Default::default()
+                    },
                     ..Default::default()
                 },
                 temp.path().to_path_buf(),
@@ -517,10 +535,7 @@ mod tests {
         let generated_recipe = MojoGenerator::default()
             .generate_recipe(
                 &project_model,
-                &MojoBackendConfig {
-                    compilers: None,
-                    ..Default::default()
-                },
+                &MojoBackendConfig::default(),
                 temp.path().to_path_buf(),
                 Platform::Linux64,
                 None,
@@ -575,7 +590,10 @@ mod tests {
             .generate_recipe(
                 &project_model,
                 &MojoBackendConfig {
-                    compilers: Some(vec!["c".to_string(), "cxx".to_string()]),
+                    generator: GeneratorConfig {
+                        compilers: Some(vec!["c".to_string(), "cxx".to_string()]),
+                        ..Default::default()
+                    },
                     ..Default::default()
                 },
                 temp.path().to_path_buf(),
@@ -8,6 +8,7 @@ use miette::IntoDiagnostic;
 use pixi_build_backend::variants::NormalizedKey;
 use pixi_build_backend::{
     Variable,
+    build_script::BuildScriptOptions,
     generated_recipe::{GenerateRecipe, GeneratedRecipe, PythonParams},
     intermediate_backend::IntermediateBackendInstantiator,
     traits::ProjectModel,
@@ -138,7 +139,7 @@ impl GenerateRecipe for PythonGenerator {
 
         // Get the list of compilers from config, defaulting to no compilers for pure
         // Python packages and add them to the build requirements.
-        let compilers = config.compilers.clone().unwrap_or_default();
+        let compilers = config.generator.compilers.clone().unwrap_or_default();
         pixi_build_backend::compilers::add_compilers_to_requirements(
             &compilers,
             &mut requirements.build,
@@ -158,18 +159,21 @@ impl GenerateRecipe for PythonGenerator {
             .map(|val| val == "true")
             .unwrap_or(params.editable);
 
-        let build_script = BuildScriptContext {
-            installer,
-            build_platform: if build_platform.is_windows() {
-                BuildPlatform::Windows
-            } else {
-                BuildPlatform::Unix
-            },
-            editable,
-            extra_args: config.extra_args.clone(),
-            manifest_root: manifest_root.clone(),
-        }
-        .render();
+        let build_script =
+            BuildScriptOptions::render_for_config(config, &manifest_root, |options| {
+                BuildScriptContext {
+                    installer,
+                    build_platform: if build_platform.is_windows() {
+                        BuildPlatform::Windows
+                    } else {
+                        BuildPlatform::Unix
+                    },
+                    editable,
+                    extra_args: config.extra_args.clone(),
+                    manifest_root: manifest_root.clone(),
+                }
+                .render(options)
+            })?;
 
         // Determine whether the package should be built as a noarch package or as a
         // generic package.
@@ -203,14 +207,15 @@ impl GenerateRecipe for PythonGenerator {
         // Construct python specific settings
         let python = Python {
             entry_points: PythonGenerator::entry_points(pyproject_manifest),
+            site_packages_path: config.site_packages_path.clone(),
         };
 
         generated_recipe.recipe.build.python = python;
         generated_recipe.recipe.build.noarch = noarch_kind;
 
         generated_recipe.recipe.build.script = Script {
-            content: build_script,
-            env: config.env.clone(),
+            content: build_script.script,
+            env: config.generator.env.clone(),
             ..Script::default()
         };
 
@@ -219,6 +224,10 @@ impl GenerateRecipe for PythonGenerator {
             .metadata_input_globs
             .extend(pyproject_metadata_provider.input_globs());
 
+        generated_recipe
+            .build_input_globs
+            .extend(build_script.input_globs);
+
         Ok(generated_recipe)
     }
 
@@ -267,7 +276,7 @@ impl GenerateRecipe for PythonGenerator {
             .chain(python_globs.iter())
             .chain(compiler_based_globs.iter())
             .map(|s| s.to_string())
-            .chain(config.extra_input_globs.clone())
+            .chain(config.common.extra_input_globs.clone())
             .collect())
     }
 
@@ -307,7 +316,10 @@ mod tests {
     use std::collections::BTreeMap;
 
     use indexmap::IndexMap;
-    use pixi_build_backend::utils::test::intermediate_conda_outputs;
+    use pixi_build_backend::{
+        config::{CommonBackendConfig, GeneratorConfig},
+        utils::test::intermediate_conda_outputs,
+    };
     use pixi_build_types::VariantValue;
     use recipe_stage0::recipe::{Item, Value};
     use tokio::fs;
@@ -317,7 +329,10 @@ mod tests {
     #[test]
     fn test_input_globs_includes_extra_globs() {
         let config = PythonBackendConfig {
-            extra_input_globs: vec!["custom/*.py".to_string()],
+            common: CommonBackendConfig {
+                extra_input_globs: vec!["custom/*.py".to_string()],
+                ..Default::default()
+            },
             ..Default::default()
         };
 
@@ -331,7 +346,10 @@ mod tests {
     #[test]
     fn test_input_globs_includes_extra_globs_editable() {
         let config = PythonBackendConfig {
-            extra_input_globs: vec!["custom/*.py".to_string()],
+            common: CommonBackendConfig {
+                extra_input_globs: vec!["custom/*.py".to_string()],
+                ..Default::default()
+            },
             ..Default::default()
         };
 
@@ -411,6 +429,12 @@ version = "0.1.0"
             result.outputs[0].metadata.variant["target_platform"],
             VariantValue::from("noarch")
         );
+        assert!(
+            result.outputs[0]
+                .input_globs
+                .as_ref()
+                .is_some_and(|globs| globs.contains("pyproject.toml"))
+        );
     }
 
     #[tokio::test]
@@ -584,8 +608,11 @@ version = "0.1.0"
             .generate_recipe(
                 &project_model,
                 &PythonBackendConfig {
-                    env: env.clone(),
                     ignore_pyproject_manifest: Some(true),
+                    generator: GeneratorConfig {
+                        env: env.clone(),
+                        ..Default::default()
+                    },
                     ..Default::default()
                 },
                 PathBuf::from("."),
@@ -624,8 +651,15 @@ version = "0.1.0"
             .generate_recipe(
                 &project_model,
                 &PythonBackendConfig {
-                    compilers: Some(vec!["c".to_string(), "cxx".to_string(), "rust".to_string()]),
                     ignore_pyproject_manifest: Some(true),
+                    generator: GeneratorConfig {
+                        compilers: Some(vec![
+                            "c".to_string(),
+                            "cxx".to_string(),
+                            "rust".to_string(),
+                        ]),
+                        ..Default::default()
+                    },
                     ..Default::default()
                 },
                 PathBuf::from("."),
@@ -690,7 +724,6 @@ version = "0.1.0"
             .generate_recipe(
                 &project_model,
                 &PythonBackendConfig {
-                    compilers: None,
                     ignore_pyproject_manifest: Some(true),
                     ..Default::default()
                 },
@@ -763,8 +796,11 @@ version = "0.1.0"
     #[test]
     fn test_noarch_defaults_to_false_when_compilers_present() {
         let config = PythonBackendConfig {
-            compilers: Some(vec!["c".to_string()]),
             ignore_pyproject_manifest: Some(true),
+            generator: GeneratorConfig {
+                compilers: Some(vec!["c".to_string()]),
+                ..Default::default()
+            },
             ..Default::default()
         };
 
@@ -780,8 +816,11 @@ version = "0.1.0"
     fn test_noarch_explicit_true_overrides_compilers() {
         let config = PythonBackendConfig {
             noarch: Some(true),
-            compilers: Some(vec!["c".to_string()]),
             ignore_pyproject_manifest: Some(true),
+            generator: GeneratorConfig {
+                compilers: Some(vec!["c".to_string()]),
+                ..Default::default()
+            },
             ..Default::default()
         };
 
@@ -797,7 +836,6 @@ version = "0.1.0"
     fn test_noarch_explicit_false_overrides_no_compilers() {
         let config = PythonBackendConfig {
             noarch: Some(false),
-            compilers: None,
             ignore_pyproject_manifest: Some(true),
             ..Default::default()
         };
@@ -813,8 +851,11 @@ version = "0.1.0"
     #[test]
     fn test_c_compilers_create_extra_input_globs() {
         let config = PythonBackendConfig {
-            compilers: Some(vec!["c".to_string()]),
             ignore_pyproject_manifest: Some(true),
+            generator: GeneratorConfig {
+                compilers: Some(vec!["c".to_string()]),
+                ..Default::default()
+            },
             ..Default::default()
         };
         let generator = PythonGenerator::default();
@@ -825,8 +866,11 @@ version = "0.1.0"
     #[test]
     fn test_cxx_compilers_create_extra_input_globs() {
         let config = PythonBackendConfig {
-            compilers: Some(vec!["cxx".to_string()]),
             ignore_pyproject_manifest: Some(true),
+            generator: GeneratorConfig {
+                compilers: Some(vec!["cxx".to_string()]),
+                ..Default::default()
+            },
             ..Default::default()
         };
         let generator = PythonGenerator::default();
@@ -837,8 +881,11 @@ version = "0.1.0"
     #[test]
     fn test_rust_compilers_create_extra_input_globs() {
         let config = PythonBackendConfig {
-            compilers: Some(vec!["rust".to_string()]),
             ignore_pyproject_manifest: Some(true),
+            generator: GeneratorConfig {
+                compilers: Some(vec!["rust".to_string()]),
+                ..Default::default()
+            },
             ..Default::default()
         };
         let generator = PythonGenerator::default();
@@ -3,6 +3,7 @@ use std::{collections::BTreeSet, path::PathBuf, str::FromStr};
 use miette::Diagnostic;
 use once_cell::unsync::OnceCell;
 use pixi_build_backend::generated_recipe::MetadataProvider;
+use purl::PackageUrl;
 use pyproject_toml::PyProjectToml;
 use rattler_conda_types::{ParseVersionError, Version};
 
@@ -14,6 +15,8 @@ pub enum MetadataError {
     ParseVersion(ParseVersionError),
     #[error(transparent)]
     Io(#[from] std::io::Error),
+    #[error("failed to construct a package url, {0}")]
+    InvalidPurl(purl::ParseError),
 }
 
 /// An implementation of [`MetadataProvider`] that reads metadata from a
@@ -223,6 +226,41 @@ impl MetadataProvider for PyprojectMetadataProvider {
                     .cloned()
             }))
     }
+
+    /// Returns the package URL of the project on PyPI.
+    ///
+    /// If `ignore_pyproject_manifest` is true, returns `None`. Otherwise,
+    /// returns a `pkg:pypi/<name>` package URL based on the normalized
+    /// project name.
+    fn purls(&mut self) -> Result<Option<BTreeSet<PackageUrl>>, Self::Error> {
+        let Some(name) = self.name()? else {
+            return Ok(None);
+        };
+        let purl = format!("pkg:pypi/{}", normalize_pypi_name(&name))
+            .parse()
+            .map_err(MetadataError::InvalidPurl)?;
+        Ok(Some(BTreeSet::from([purl])))
+    }
+}
+
+/// Normalizes a Python package name as described in PEP 503, which is also
+/// what the `pypi` package URL type expects: the name is lowercased and runs
+/// of `-`, `_` and `.` are replaced by a single `-`.
+fn normalize_pypi_name(name: &str) -> String {
+    let mut normalized = String::with_capacity(name.len());
+    let mut in_separator = false;
+    for c in name.chars() {
+        if matches!(c, '-' | '_' | '.') {
+            if !in_separator {
+                normalized.push('-');
+            }
+            in_separator = true;
+        } else {
+            normalized.extend(c.to_lowercase());
+            in_separator = false;
+        }
+    }
+    normalized
 }
 
 impl PyprojectMetadataProvider {
@@ -385,6 +423,26 @@ description = "Test description"
         assert_eq!(provider.requires_python().unwrap(), None);
     }
 
+    #[test]
+    fn test_purls_use_normalized_name() {
+        let pyproject_toml_content = r#"
+[project]
+name = "My_Package.Foo"
+version = "1.0.0"
+"#;
+
+        let temp_dir = create_temp_pyproject_project(pyproject_toml_content);
+        let mut provider = create_metadata_provider(temp_dir.path());
+        let purls = provider.purls().unwrap().unwrap();
+        assert_eq!(
+            purls.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            ["pkg:pypi/my-package-foo"]
+        );
+
+        let mut provider = PyprojectMetadataProvider::new(temp_dir.path(), true);
+        assert_eq!(provider.purls().unwrap(), None);
+    }
+
     #[test]
     fn test_alternative_url_keys() {
         let pyproject_toml_content = r#"
@@ -1,57 +1,71 @@
-use pixi_build_backend::generated_recipe::BackendConfig;
+use pixi_build_backend::{
+    config::{CommonBackendConfig, merge},
+    generated_recipe::BackendConfig,
+    merge_target_config,
+    package_format::PackageFormat,
+    sandbox::SandboxConfig,
+};
 use serde::{Deserialize, Serialize};
-use std::path::{Path, PathBuf};
+use std::path::Path;
 
 #[derive(Debug, Default, Deserialize, Serialize, Clone)]
 #[serde(rename_all = "kebab-case", deny_unknown_fields)]
 pub struct RattlerBuildBackendConfig {
-    /// Deprecated. Setting this has no effect; debug data is always written to
-    /// the `debug` subdirectory of the work directory.
-    #[serde(alias = "debug_dir")]
-    pub debug_dir: Option<PathBuf>,
-    /// Extra input globs to include in addition to the default ones
-    #[serde(default)]
-    pub extra_input_globs: Vec<String>,
     /// Enable experimental features in rattler-build (e.g., cache support for multi-output recipes)
     #[serde(default)]
     pub experimental: Option<bool>,
+    /// Options that every backend supports.
+    #[serde(flatten)]
+    pub common: CommonBackendConfig,
 }
 
 impl BackendConfig for RattlerBuildBackendConfig {
     fn debug_dir(&self) -> Option<&Path> {
-        self.debug_dir.as_deref()
+        self.common.debug_dir()
+    }
+
+    fn run_tests(&self) -> bool {
+        self.common.run_tests()
+    }
+
+    fn package_format(&self) -> PackageFormat {
+        self.common.package_format()
+    }
+
+    fn reproducible(&self) -> bool {
+        self.common.reproducible()
+    }
+
+    fn source_date_epoch(&self) -> Option<i64> {
+        self.common.source_date_epoch()
+    }
+
+    fn sandbox(&self) -> Option<&SandboxConfig> {
+        self.common.sandbox()
     }
 
     /// Merge this configuration with a target-specific configuration.
     /// Target-specific values override base values using the following rules:
-    /// - debug_dir: Not allowed to have target specific value
-    /// - extra_input_globs: Platform-specific completely replaces base
     /// - experimental: Not allowed to have target specific value
+    /// - common: See [`CommonBackendConfig`]
     fn merge_with_target_config(&self, target_config: &Self) -> miette::Result<Self> {
-        if target_config.debug_dir.is_some() {
-            miette::bail!("`debug_dir` cannot have a target specific value");
-        }
-
-        if target_config.experimental.is_some() {
-            miette::bail!("`experimental` cannot have a target specific value");
-        }
-
-        Ok(Self {
-            debug_dir: self.debug_dir.clone(),
-            extra_input_globs: if target_config.extra_input_globs.is_empty() {
-                self.extra_input_globs.clone()
-            } else {
-                target_config.extra_input_globs.clone()
-            },
-            experimental: self.experimental,
-        })
+        Ok(merge_target_config!(
+            self,
+            target_config,
+            Self {
+                experimental: merge::base_only,
+                common: merge::deep_merge,
+            }
+        ))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::RattlerBuildBackendConfig;
-    use pixi_build_backend::generated_recipe::BackendConfig;
+    use pixi_build_backend::{
+        config::CommonBackendConfig, generated_recipe::BackendConfig, package_format::PackageFormat,
+    };
     use serde_json::json;
     use std::path::PathBuf;
 
@@ -64,15 +78,21 @@ mod tests {
     #[test]
     fn test_merge_with_target_config() {
         let base_config = RattlerBuildBackendConfig {
-            debug_dir: Some(PathBuf::from("/base/debug")),
-            extra_input_globs: vec!["*.base".to_string()],
             experimental: Some(false),
+            common: CommonBackendConfig {
+                debug_dir: Some(PathBuf::from("/base/debug")),
+                extra_input_globs: vec!["*.base".to_string()],
+                ..Default::default()
+            },
         };
 
         let target_config = RattlerBuildBackendConfig {
-            debug_dir: None,
-            extra_input_globs: vec!["*.target".to_string()],
             experimental: None, // Not specified in target
+            common: CommonBackendConfig {
+                extra_input_globs: vec!["*.target".to_string()],
+                run_tests: Some(true),
+                ..Default::default()
+            },
         };
 
         let merged = base_config
@@ -80,21 +100,30 @@ mod tests {
             .unwrap();
 
         // debug_dir should use base value
-        assert_eq!(merged.debug_dir, Some(PathBuf::from("/base/debug")));
+        assert_eq!(merged.common.debug_dir, Some(PathBuf::from("/base/debug")));
 
         // extra_input_globs should be completely overridden
-        assert_eq!(merged.extra_input_globs, vec!["*.target".to_string()]);
+        assert_eq!(
+            merged.common.extra_input_globs,
+            vec!["*.target".to_string()]
+        );
 
         // experimental should be preserved from base
         assert_eq!(merged.experimental, Some(false));
+
+        // run_tests should use the target value
+        assert_eq!(merged.common.run_tests, Some(true));
     }
 
     #[test]
     fn test_merge_with_empty_target_config() {
         let base_config = RattlerBuildBackendConfig {
-            debug_dir: Some(PathBuf::from("/base/debug")),
-            extra_input_globs: vec!["*.base".to_string()],
             experimental: Some(true),
+            common: CommonBackendConfig {
+                debug_dir: Some(PathBuf::from("/base/debug")),
+                extra_input_globs: vec!["*.base".to_string()],
+                ..Default::default()
+            },
         };
 
         let empty_target_config = RattlerBuildBackendConfig::default();
@@ -104,8 +133,8 @@ mod tests {
             .unwrap();
 
         // Should keep base values when target is empty
-        assert_eq!(merged.debug_dir, Some(PathBuf::from("/base/debug")));
-        assert_eq!(merged.extra_input_globs, vec!["*.base".to_string()]);
+        assert_eq!(merged.common.debug_dir, Some(PathBuf::from("/base/debug")));
+        assert_eq!(merged.common.extra_input_globs, vec!["*.base".to_string()]);
         // experimental should be true when base has it enabled
         assert_eq!(merged.experimental, Some(true));
     }
@@ -113,12 +142,18 @@ mod tests {
     #[test]
     fn test_merge_target_debug_dir_error() {
         let base_config = RattlerBuildBackendConfig {
-            debug_dir: Some(PathBuf::from("/base/debug")),
+            common: CommonBackendConfig {
+                debug_dir: Some(PathBuf::from("/base/debug")),
+                ..Default::default()
+            },
             ..Default::default()
         };
 
         let target_config = RattlerBuildBackendConfig {
-            debug_dir: Some(PathBuf::from("/target/debug")),
+            common: CommonBackendConfig {
+                debug_dir: Some(PathBuf::from("/target/debug")),
+                ..Default::default()
+            },
             ..Default::default()
         };
 
@@ -217,4 +252,57 @@ mod tests {
         let config: RattlerBuildBackendConfig = serde_json::from_value(json_data).unwrap();
         assert_eq!(config.experimental, None);
     }
+
+    #[test]
+    fn test_deserialize_run_tests() {
+        let json_data = json!({
+            "run-tests": true
+        });
+        let config: RattlerBuildBackendConfig = serde_json::from_value(json_data).unwrap();
+        assert_eq!(config.common.run_tests, Some(true));
+        assert!(config.run_tests());
+
+        let json_data = json!({});
+        let config: RattlerBuildBackendConfig = serde_json::from_value(json_data).unwrap();
+        assert_eq!(config.common.run_tests, None);
+        assert!(!config.run_tests());
+    }
+
+    #[test]
+    fn test_deserialize_package_format() {
+        let json_data = json!({
+            "package-format": "tar-bz2:max"
+        });
+        let config: RattlerBuildBackendConfig = serde_json::from_value(json_data).unwrap();
+        assert_eq!(
+            config.package_format(),
+            "tar-bz2:max".parse::<PackageFormat>().unwrap()
+        );
+
+        let json_data = json!({});
+        let config: RattlerBuildBackendConfig = serde_json::from_value(json_data).unwrap();
+        assert_eq!(config.package_format(), PackageFormat::default());
+
+        let json_data = json!({
+            "package-format": "zip"
+        });
+        assert!(serde_json::from_value::<RattlerBuildBackendConfig>(json_data).is_err());
+    }
+
+    #[test]
+    fn test_deserialize_sandbox() {
+        let json_data = json!({
+            "sandbox": {
+                "allow-network": false,
+                "allow-read-write": ["/tmp/cache"]
+            }
+        });
+        let config: RattlerBuildBackendConfig = serde_json::from_value(json_data).unwrap();
+        let sandbox = config.sandbox().expect("sandbox should be configured");
+        assert!(!sandbox.allow_network);
+        assert_eq!(sandbox.allow_read_write, vec![PathBuf::from("/tmp/cache")]);
+
+        let config: RattlerBuildBackendConfig = serde_json::from_value(json!({})).unwrap();
+        assert!(config.sandbox().is_none());
+    }
 }
@@ -11,35 +11,44 @@ use pixi_build_backend::specs_conversion::{
     from_build_v1_args_to_finalized_dependencies,
 };
 use pixi_build_backend::{
+    common::platforms_with_virtual_packages,
     dependencies::{convert_binary_dependencies, convert_dependencies},
+    errors::{CategorizeError, ErrorCategory},
     intermediate_backend::{conda_build_v1_directories, find_matching_output},
+    notifications::{BuildPhase, OutputBuild},
+    package_test::run_package_tests,
     protocol::{Protocol, ProtocolInstantiator},
+    reproducible::{apply_normalized_build_env, source_date_epoch},
     tools::LoadedVariantConfig,
 };
 use pixi_build_types::{
-    BackendCapabilities, PathSpecV1, SourcePackageSpecV1, TargetV1,
+    PathSpecV1, PlatformAndVirtualPackages, SourcePackageSpecV1, TargetV1,
     procedures::{
+        self,
         conda_build_v1::{CondaBuildV1Params, CondaBuildV1Result},
         conda_outputs::{
             CondaOutput, CondaOutputDependencies, CondaOutputIgnoreRunExports, CondaOutputMetadata,
             CondaOutputRunExports, CondaOutputsParams, CondaOutputsResult,
         },
         initialize::{InitializeParams, InitializeResult},
-        negotiate_capabilities::{NegotiateCapabilitiesParams, NegotiateCapabilitiesResult},
     },
 };
 use rattler_build::{
     build::{WorkingDirectoryBehavior, run_build},
     console_utils::LoggingOutputHandler,
     hash::HashInfo,
-    metadata::{BuildConfiguration, Debug, Output, PlatformWithVirtualPackages},
-    recipe::{Jinja, ParsingError, Recipe, parser::find_outputs_from_src},
+    metadata::{BuildConfiguration, Debug, Output},
+    recipe::{
+        Jinja, ParsingError, Recipe,
+        parser::{Source, find_outputs_from_src},
+    },
+    script::{Script, ScriptContent},
     selectors::SelectorConfig,
     tool_configuration::Configuration,
-    types::{PackageIdentifier, PackagingSettings},
+    types::PackageIdentifier,
     variant_config::{ParseErrors, VariantConfig},
 };
-use rattler_conda_types::{Platform, compression_level::CompressionLevel, package::ArchiveType};
+use rattler_conda_types::Platform;
 use tracing::warn;
 pub struct RattlerBuildBackendInstantiator {
     logging_output_handler: LoggingOutputHandler,
@@ -57,6 +66,13 @@ impl RattlerBuildBackendInstantiator {
 
 #[async_trait::async_trait]
 impl Protocol for RattlerBuildBackend {
+    fn implemented_methods() -> &'static [&'static str] {
+        &[
+            procedures::conda_outputs::METHOD_NAME,
+            procedures::conda_build_v1::METHOD_NAME,
+        ]
+    }
+
     async fn conda_outputs(
         &self,
         params: CondaOutputsParams,
@@ -81,16 +97,21 @@ impl Protocol for RattlerBuildBackend {
             &self.recipe_source.path,
             &selector_config_for_variants,
             params.variant_files.iter().flatten().map(PathBuf::as_path),
-        )?
+        )
+        .categorize(ErrorCategory::VariantResolution)?
         .extend_with_input_variants(params.variant_configuration.unwrap_or_default());
 
         // Find all outputs from the recipe
-        let output_nodes = find_outputs_from_src(self.recipe_source.clone())?;
-        let discovered_outputs = variant_config.variant_config.find_variants(
-            &output_nodes,
-            self.recipe_source.clone(),
-            &selector_config_for_variants,
-        )?;
+        let output_nodes = find_outputs_from_src(self.recipe_source.clone())
+            .categorize(ErrorCategory::Manifest)?;
+        let discovered_outputs = variant_config
+            .variant_config
+            .find_variants(
+                &output_nodes,
+                self.recipe_source.clone(),
+                &selector_config_for_variants,
+            )
+            .categorize(ErrorCategory::VariantResolution)?;
 
         // Construct a mapping that for packages that we want from source.
         //
@@ -142,6 +163,14 @@ impl Protocol for RattlerBuildBackend {
                 continue;
             }
 
+            // Determine the files that influence the build of this particular output.
+            let input_globs = output_input_globs(
+                &self.manifest_root,
+                &self.recipe_source.path,
+                &recipe,
+                self.config.common.extra_input_globs.clone(),
+            )?;
+
             let jinja = Jinja::new(selector_config);
             let build_number = recipe.build().number;
             let build_string = recipe.build().string().resolve(&hash, build_number, &jinja);
@@ -262,9 +291,7 @@ impl Protocol for RattlerBuildBackend {
                     )?,
                 },
 
-                // The input globs are the same for all outputs
-                input_globs: None,
-                // TODO: Implement caching
+                input_globs: Some(input_globs),
             });
         }
 
@@ -293,6 +320,31 @@ impl Protocol for RattlerBuildBackend {
             .as_ref()
             .map_or_else(Platform::current, |prefix| prefix.platform);
 
+        // The virtual packages of the prefixes are required to correctly build recipes
+        // that depend on e.g. a specific `__cuda` or `__glibc` version.
+        let (build_platform_with_virtual_packages, host_platform_with_virtual_packages) =
+            platforms_with_virtual_packages(
+                params
+                    .build_prefix
+                    .as_ref()
+                    .map(|prefix| PlatformAndVirtualPackages {
+                        platform: prefix.platform,
+                        virtual_packages: prefix.virtual_packages.clone(),
+                    }),
+                params
+                    .host_prefix
+                    .as_ref()
+                    .map(|prefix| PlatformAndVirtualPackages {
+                        platform: prefix.platform,
+                        virtual_packages: prefix.virtual_packages.clone(),
+                    }),
+            )?;
+
+        // Report the progress of the build to the frontend.
+        let output_name = params.output.name.as_normalized().to_string();
+        let build = OutputBuild::start(&output_name);
+        build.progress(BuildPhase::Rendering);
+
         // Construct a `VariantConfig` based on the input parameters. We only
         // have a single variant here so we can just use the variant from the
         // parameters.
@@ -325,13 +377,17 @@ impl Protocol for RattlerBuildBackend {
             allow_undefined: false,
             recipe_path: Some(self.recipe_source.path.clone()),
         };
-        let outputs = find_outputs_from_src(self.recipe_source.clone())?;
-        let discovered_outputs = variant_config.find_variants(
-            &outputs,
-            self.recipe_source.clone(),
-            &selector_config_for_variants,
-        )?;
-        let discovered_output = find_matching_output(&params.output, discovered_outputs)?;
+        let outputs = find_outputs_from_src(self.recipe_source.clone())
+            .categorize(ErrorCategory::Manifest)?;
+        let discovered_outputs = variant_config
+            .find_variants(
+                &outputs,
+                self.recipe_source.clone(),
+                &selector_config_for_variants,
+            )
+            .categorize(ErrorCategory::VariantResolution)?;
+        let discovered_output = find_matching_output(&params.output, discovered_outputs)
+            .categorize(ErrorCategory::VariantResolution)?;
 
         // Set up the proper directories for the build.
         let directories = conda_build_v1_directories(
@@ -346,6 +402,7 @@ impl Protocol for RattlerBuildBackend {
         let tool_config = Configuration::builder()
             .with_opt_cache_dir(self.cache_dir.clone())
             .with_logging_output_handler(self.logging_output_handler.clone())
+            // Tests are executed separately after the build, see `run_package_tests`.
             .with_testing(false)
             // Pixi is incremental so keep the build
             .with_keep_build(true)
@@ -354,33 +411,39 @@ impl Protocol for RattlerBuildBackend {
             .with_environments_externally_managed(true)
             .finish();
 
+        // For reproducible builds the timestamp is fixed and the build script runs in a
+        // normalized environment.
+        let mut output_recipe = discovered_output.recipe;
+        let timestamp = if self.config.reproducible() {
+            let timestamp = source_date_epoch(self.config.source_date_epoch(), &self.source_dir)?;
+            apply_normalized_build_env(&mut output_recipe, timestamp);
+            timestamp
+        } else {
+            chrono::Utc::now()
+        };
+
         let output = Output {
-            recipe: discovered_output.recipe,
+            recipe: output_recipe,
             build_configuration: BuildConfiguration {
                 target_platform: discovered_output.target_platform,
-                host_platform: PlatformWithVirtualPackages {
-                    platform: host_platform,
-                    virtual_packages: vec![],
-                },
-                build_platform: PlatformWithVirtualPackages {
-                    platform: build_platform,
-                    virtual_packages: vec![],
-                },
+                host_platform: host_platform_with_virtual_packages,
+                build_platform: build_platform_with_virtual_packages,
                 hash: discovered_output.hash,
                 variant: discovered_output.used_vars.clone(),
                 directories,
-                channels: vec![],
+                // The channels are only used to resolve the test environments.
+                channels: params.channels,
                 channel_priority: Default::default(),
                 solve_strategy: Default::default(),
-                timestamp: chrono::Utc::now(),
+                timestamp,
                 subpackages: BTreeMap::new(),
-                packaging_settings: PackagingSettings::from_args(
-                    ArchiveType::Conda,
-                    CompressionLevel::default(),
-                ),
+                packaging_settings: self.config.package_format().packaging_settings(),
                 store_recipe: false,
                 force_colors: true,
-                sandbox_config: None,
+                sandbox_config: self
+                    .config
+                    .sandbox()
+                    .and_then(|sandbox| sandbox.to_rattler_build(&self.source_dir)),
                 debug: Debug::new(false),
                 exclude_newer: None,
             },
@@ -399,17 +462,33 @@ impl Protocol for RattlerBuildBackend {
             extra_meta: None,
         };
 
-        let (output, output_path) =
-            // rattler-build requires a clean work dir
-            run_build(output, &tool_config, WorkingDirectoryBehavior::Cleanup).await?;
+        build.progress(BuildPhase::Building);
+        // rattler-build requires a clean work dir
+        let (output, output_path) = build
+            .scope(run_build(
+                output,
+                &tool_config,
+                WorkingDirectoryBehavior::Cleanup,
+            ))
+            .await
+            .categorize_with(|| build.failure())?;
+
+        // Run the tests of the recipe against the package that was just built.
+        if self.config.common.run_tests.unwrap_or(false) {
+            build.progress(BuildPhase::Testing);
+            build
+                .scope(run_package_tests(&output, &output_path, &tool_config))
+                .await?;
+        }
 
+        build.progress(BuildPhase::Finished);
         Ok(CondaBuildV1Result {
             output_file: output_path,
             input_globs: build_input_globs(
                 &self.manifest_root,
                 &self.recipe_source.path,
                 extract_mutable_package_sources(&output),
-                self.config.extra_input_globs.clone(),
+                self.config.common.extra_input_globs.clone(),
             )?,
             name: output.name().as_normalized().to_string(),
             version: output.version().clone(),
@@ -422,18 +501,81 @@ impl Protocol for RattlerBuildBackend {
 /// Extracts the package sources from an `Output` object that are mutable and
 /// should be watched for changes.
 fn extract_mutable_package_sources(output: &Output) -> Option<Vec<PathBuf>> {
-    output.finalized_sources.as_ref().map(|package_sources| {
-        package_sources
-            .iter()
-            .filter_map(|source| {
-                if let rattler_build::recipe::parser::Source::Path(path_source) = source {
-                    Some(path_source.path.clone())
-                } else {
-                    None
-                }
-            })
-            .collect()
-    })
+    output
+        .finalized_sources
+        .as_ref()
+        .map(|package_sources| path_sources(package_sources))
+}
+
+/// Returns the paths of all the path sources in `sources`.
+fn path_sources(sources: &[Source]) -> Vec<PathBuf> {
+    sources
+        .iter()
+        .filter_map(|source| {
+            if let Source::Path(path_source) = source {
+                Some(path_source.path.clone())
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Returns the script files that are used to build an output, relative to the
+/// recipe directory. Inline scripts don't refer to any files.
+fn build_script_paths(recipe_dir: &Path, script: &Script) -> Vec<PathBuf> {
+    let candidates = match &script.content {
+        ScriptContent::Default => vec![recipe_dir.join("build.sh"), recipe_dir.join("build.bat")],
+        ScriptContent::Path(path) => vec![recipe_dir.join(path)],
+        ScriptContent::CommandOrPath(command_or_path) => vec![recipe_dir.join(command_or_path)],
+        ScriptContent::Command(_) | ScriptContent::Commands(_) => Vec::new(),
+    };
+    candidates
+        .into_iter()
+        .filter(|path| path.is_file())
+        .collect()
+}
+
+/// Returns the input globs of a single output of a recipe.
+///
+/// These consist of the recipe itself, the path sources and the build script
+/// of the output. If the output has no path sources the entire recipe
+/// directory is included because the build script can reference any file
+/// relative to the recipe.
+fn output_input_globs(
+    manifest_root: &Path,
+    recipe_path: &Path,
+    recipe: &Recipe,
+    extra_globs: Vec<String>,
+) -> miette::Result<BTreeSet<String>> {
+    let recipe_dir = if recipe_path.is_file() {
+        recipe_path.parent().unwrap_or(recipe_path)
+    } else {
+        recipe_path
+    };
+
+    let mut input_globs = get_metadata_input_globs(manifest_root, recipe_path)?;
+
+    let package_sources = path_sources(&recipe.source);
+    if package_sources.is_empty() {
+        input_globs.insert(build_relative_glob(manifest_root, recipe_dir)?);
+    }
+    for source in package_sources {
+        let source = if source.is_absolute() {
+            source
+        } else {
+            recipe_dir.join(source)
+        };
+        input_globs.insert(build_relative_glob(manifest_root, &source)?);
+    }
+
+    for script in build_script_paths(recipe_dir, &recipe.build.script) {
+        input_globs.insert(build_relative_glob(manifest_root, &script)?);
+    }
+
+    input_globs.extend(extra_globs);
+
+    Ok(input_globs)
 }
 
 /// Returns the relative path from `base` to `input`, joined by "/".
@@ -519,6 +661,8 @@ fn get_metadata_input_globs(
 
 #[async_trait::async_trait]
 impl ProtocolInstantiator for RattlerBuildBackendInstantiator {
+    type Protocol = RattlerBuildBackend;
+
     async fn initialize(
         &self,
         params: InitializeParams,
@@ -526,12 +670,13 @@ impl ProtocolInstantiator for RattlerBuildBackendInstantiator {
         let config = if let Some(config) = params.configuration {
             serde_json::from_value(config)
                 .into_diagnostic()
-                .context("failed to parse configuration")?
+                .context("failed to parse configuration")
+                .categorize(ErrorCategory::Configuration)?
         } else {
             RattlerBuildBackendConfig::default()
         };
 
-        if let Some(path) = config.debug_dir.as_ref() {
+        if let Some(path) = config.common.debug_dir.as_ref() {
             warn!(
                 path = %path.display(),
                 "`debug-dir` backend configuration is deprecated and ignored; debug data is now written to the build work directory."
@@ -601,24 +746,6 @@ impl ProtocolInstantiator for RattlerBuildBackendInstantiator {
 
         Ok((Box::new(instance), InitializeResult {}))
     }
-
-    async fn negotiate_capabilities(
-        _params: NegotiateCapabilitiesParams,
-    ) -> miette::Result<NegotiateCapabilitiesResult> {
-        Ok(NegotiateCapabilitiesResult {
-            capabilities: default_capabilities(),
-        })
-    }
-}
-
-pub(crate) fn default_capabilities() -> BackendCapabilities {
-    BackendCapabilities {
-        provides_conda_outputs: Some(true),
-        provides_conda_build_v1: Some(true),
-        highest_supported_project_model: Some(
-            pixi_build_types::VersionedProjectModel::highest_version(),
-        ),
-    }
 }
 
 #[cfg(test)]
@@ -686,6 +813,97 @@ mod tests {
         - numpy
     "#;
 
+    const MULTI_OUTPUT_PATH_SOURCE_RECIPE: &str = r#"
+    recipe:
+      version: 0.1.0
+
+    outputs:
+      - package:
+          name: foo
+        source:
+          path: ../foo
+        build:
+          script: build-foo.sh
+
+      - package:
+          name: bar
+        source:
+          path: ../bar
+        build:
+          script:
+            - echo "bar"
+    "#;
+
+    #[tokio::test]
+    async fn test_conda_outputs_have_per_output_input_globs() {
+        let temp_dir = tempdir().unwrap();
+        let recipe_dir = temp_dir.path().join("recipe");
+        for dir in [
+            &recipe_dir,
+            &temp_dir.path().join("foo"),
+            &temp_dir.path().join("bar"),
+        ] {
+            tokio::fs::create_dir_all(dir).await.unwrap();
+        }
+        tokio::fs::write(recipe_dir.join("build-foo.sh"), "echo foo")
+            .await
+            .unwrap();
+        let recipe_path = recipe_dir.join("recipe.yaml");
+        tokio::fs::write(&recipe_path, MULTI_OUTPUT_PATH_SOURCE_RECIPE)
+            .await
+            .unwrap();
+
+        let factory = RattlerBuildBackendInstantiator::new(LoggingOutputHandler::default())
+            .initialize(InitializeParams {
+                workspace_root: None,
+                source_dir: None,
+                manifest_path: recipe_path,
+                project_model: None,
+                configuration: None,
+                target_configuration: None,
+                cache_directory: None,
+            })
+            .await
+            .unwrap();
+
+        let result = factory
+            .0
+            .conda_outputs(CondaOutputsParams {
+                channels: vec![],
+                host_platform: Platform::Linux64,
+                build_platform: Platform::Linux64,
+                variant_configuration: None,
+                variant_files: None,
+                work_directory: temp_dir.path().to_path_buf(),
+            })
+            .await
+            .unwrap();
+
+        let input_globs = result
+            .outputs
+            .iter()
+            .map(|output| {
+                (
+                    output.metadata.name.as_normalized().to_string(),
+                    output.input_globs.clone().unwrap_or_default(),
+                )
+            })
+            .collect::<BTreeMap<_, _>>();
+
+        assert_eq!(
+            input_globs["foo"],
+            BTreeSet::from([
+                String::from("../foo/**"),
+                String::from("build-foo.sh"),
+                String::from("recipe.yaml"),
+            ])
+        );
+        assert_eq!(
+            input_globs["bar"],
+            BTreeSet::from([String::from("../bar/**"), String::from("recipe.yaml")])
+        );
+    }
+
     #[tokio::test]
     async fn test_variant_files_are_applied() {
         let temp_dir = tempdir().unwrap();
@@ -1,4 +1,4 @@
-use minijinja::Environment;
+use pixi_build_backend::build_script::BuildScriptOptions;
 use serde::Serialize;
 
 #[derive(Serialize)]
@@ -20,17 +20,16 @@ pub struct BuildScriptContext {
 }
 
 impl BuildScriptContext {
-    pub fn render(&self) -> String {
-        let env = Environment::new();
-        let template = env
-            .template_from_str(include_str!("build_script.j2"))
-            .unwrap();
-        template.render(self).unwrap().trim().to_string()
+    /// Renders the build script, taking the user customizations in `options`
+    /// into account.
+    pub fn render(&self, options: &BuildScriptOptions) -> miette::Result<String> {
+        options.render(include_str!("build_script.j2"), self)
     }
 }
 
 #[cfg(test)]
 mod test {
+    use pixi_build_backend::build_script::BuildScriptOptions;
     use rstest::*;
 
     #[rstest]
@@ -42,7 +41,7 @@ mod test {
             has_sccache: false,
             is_bash,
         };
-        let script = context.render();
+        let script = context.render(&BuildScriptOptions::default()).unwrap();
 
         let mut settings = insta::Settings::clone_current();
         settings.set_snapshot_suffix(if is_bash { "bash" } else { "cmdexe" });
@@ -60,7 +59,7 @@ mod test {
             has_sccache: true,
             is_bash,
         };
-        let script = context.render();
+        let script = context.render(&BuildScriptOptions::default()).unwrap();
 
         let mut settings = insta::Settings::clone_current();
         settings.set_snapshot_suffix(if is_bash { "bash" } else { "cmdexe" });
@@ -78,7 +77,7 @@ mod test {
             has_sccache: false,
             is_bash,
         };
-        let script = context.render();
+        let script = context.render(&BuildScriptOptions::default()).unwrap();
 
         let mut settings = insta::Settings::clone_current();
         settings.set_snapshot_suffix(if is_bash { "bash" } else { "cmdexe" });
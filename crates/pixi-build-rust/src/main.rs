@@ -9,6 +9,7 @@ use miette::IntoDiagnostic;
 use pixi_build_backend::variants::NormalizedKey;
 use pixi_build_backend::{
     Variable,
+    build_script::BuildScriptOptions,
     cache::{sccache_envs, sccache_tools},
     generated_recipe::{GenerateRecipe, GeneratedRecipe, PythonParams},
     intermediate_backend::IntermediateBackendInstantiator,
@@ -106,7 +107,7 @@ impl GenerateRecipe for RustGenerator {
 
         let mut has_sccache = false;
 
-        let config_env = config.env.clone();
+        let config_env = config.generator.env.clone();
 
         let system_env_vars = std::env::vars().collect::<HashMap<String, String>>();
 
@@ -156,17 +157,20 @@ impl GenerateRecipe for RustGenerator {
             has_sccache = true;
         }
 
-        let build_script = BuildScriptContext {
-            source_dir: manifest_root.display().to_string(),
-            extra_args: config.extra_args.clone(),
-            has_openssl,
-            has_sccache,
-            is_bash: !Platform::current().is_windows(),
-        }
-        .render();
+        let build_script =
+            BuildScriptOptions::render_for_config(config, &manifest_root, |options| {
+                BuildScriptContext {
+                    source_dir: manifest_root.display().to_string(),
+                    extra_args: config.extra_args.clone(),
+                    has_openssl,
+                    has_sccache,
+                    is_bash: !Platform::current().is_windows(),
+                }
+                .render(options)
+            })?;
 
         generated_recipe.recipe.build.script = Script {
-            content: build_script,
+            content: build_script.script,
             env: config_env,
             secrets: sccache_secrets,
         };
@@ -176,6 +180,10 @@ impl GenerateRecipe for RustGenerator {
             .metadata_input_globs
             .extend(cargo_metadata.input_globs());
 
+        generated_recipe
+            .build_input_globs
+            .extend(build_script.input_globs);
+
         Ok(generated_recipe)
     }
 
@@ -196,7 +204,7 @@ impl GenerateRecipe for RustGenerator {
         ]
         .iter()
         .map(|s| s.to_string())
-        .chain(config.extra_input_globs.clone())
+        .chain(config.common.extra_input_globs.clone())
         .collect())
     }
 
@@ -235,6 +243,7 @@ pub async fn main() {
 mod tests {
     use cargo_toml::Manifest;
     use indexmap::IndexMap;
+    use pixi_build_backend::config::{CommonBackendConfig, GeneratorConfig};
     use recipe_stage0::recipe::{Item, Value};
 
     use super::*;
@@ -242,7 +251,10 @@ mod tests {
     #[test]
     fn test_input_globs_includes_extra_globs() {
         let config = RustBackendConfig {
-            extra_input_globs: vec!["custom/*.txt".to_string(), "extra/**/*.py".to_string()],
+            common: CommonBackendConfig {
+                extra_input_globs: vec!["custom/*.txt".to_string(), "extra/**/*.py".to_string()],
+                ..Default::default()
+            },
             ..Default::default()
         };
 
@@ -253,7 +265,7 @@ mod tests {
             .unwrap();
 
         // Verify that all extra globs are included in the result
-        for extra_glob in &config.extra_input_globs {
+        for extra_glob in &config.common.extra_input_globs {
             assert!(
                 result.contains(extra_glob),
                 "Result should contain extra glob: {extra_glob}"
@@ -379,8 +391,11 @@ mod tests {
             .generate_recipe(
                 &project_model,
                 &RustBackendConfig {
-                    env: env.clone(),
                     ignore_cargo_manifest: Some(true),
+                    generator: GeneratorConfig {
+                        env: env.clone(),
+                        ..Default::default()
+                    },
                     ..Default::default()
                 },
                 PathBuf::from("."),
@@ -424,8 +439,11 @@ mod tests {
                 .generate_recipe(
                     &project_model,
                     &RustBackendConfig {
-                        env,
                         ignore_cargo_manifest: Some(true),
+                        generator: GeneratorConfig {
+                            env,
+                            ..Default::default()
+                        },
                         ..Default::default()
                     },
                     PathBuf::from("."),
@@ -612,8 +630,15 @@ mod tests {
             .generate_recipe(
                 &project_model,
                 &RustBackendConfig {
-                    compilers: Some(vec!["rust".to_string(), "c".to_string(), "cxx".to_string()]),
                     ignore_cargo_manifest: Some(true),
+                    generator: GeneratorConfig {
+                        compilers: Some(vec![
+                            "rust".to_string(),
+                            "c".to_string(),
+                            "cxx".to_string(),
+                        ]),
+                        ..Default::default()
+                    },
                     ..Default::default()
                 },
                 PathBuf::from("."),
@@ -678,7 +703,6 @@ mod tests {
             .generate_recipe(
                 &project_model,
                 &RustBackendConfig {
-                    compilers: None,
                     ignore_cargo_manifest: Some(true),
                     ..Default::default()
                 },
@@ -7,6 +7,7 @@ use cargo_toml::{
 use miette::Diagnostic;
 use once_cell::unsync::OnceCell;
 use pixi_build_backend::generated_recipe::MetadataProvider;
+use purl::PackageUrl;
 use rattler_conda_types::{ParseVersionError, Version};
 
 #[derive(Debug, thiserror::Error, Diagnostic)]
@@ -19,6 +20,8 @@ pub enum MetadataError {
     IoError(#[from] std::io::Error),
     #[error("missing inherited value from workspace {0}")]
     MissingInheritedValue(String),
+    #[error("failed to construct a package url, {0}")]
+    InvalidPurl(purl::ParseError),
 }
 
 /// An implementation of [`MetadataProvider`] that reads metadata from a
@@ -352,6 +355,20 @@ impl MetadataProvider for CargoMetadataProvider {
         };
         Ok(Some(repository.clone()))
     }
+
+    /// Returns the package URL of the crate on crates.io.
+    ///
+    /// If `ignore_cargo_manifest` is true, returns `None`. Otherwise, returns
+    /// a `pkg:cargo/<name>` package URL based on the name of the package.
+    fn purls(&mut self) -> Result<Option<BTreeSet<PackageUrl>>, Self::Error> {
+        let Some(name) = self.name()? else {
+            return Ok(None);
+        };
+        let purl = format!("pkg:cargo/{name}")
+            .parse()
+            .map_err(MetadataError::InvalidPurl)?;
+        Ok(Some(BTreeSet::from([purl])))
+    }
 }
 
 #[cfg(test)]
@@ -816,6 +833,25 @@ description = "Test description"
         assert_eq!(provider.documentation().unwrap(), None);
         assert_eq!(provider.license_file().unwrap(), None);
         assert_eq!(provider.summary().unwrap(), None);
+        assert_eq!(provider.purls().unwrap(), None);
+    }
+
+    #[test]
+    fn test_purls() {
+        let cargo_toml_content = r#"
+[package]
+name = "test-package"
+version = "1.0.0"
+"#;
+
+        let temp_dir = create_temp_cargo_project(cargo_toml_content);
+        let mut provider = create_metadata_provider(temp_dir.path());
+
+        let purls = provider.purls().unwrap().unwrap();
+        assert_eq!(
+            purls.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            vec!["pkg:cargo/test-package"]
+        );
     }
 
     #[test]
@@ -5,8 +5,10 @@ use marked_yaml::{Node as MarkedNode, Span};
 pub type MappingHash = LinkedHashMap<MarkedScalarNode, MarkedNode>;
 
 use crate::recipe::{
-    About, Build, Conditional, ConditionalList, ConditionalRequirements, Extra, IntermediateRecipe,
-    Item, ListOrItem, Package, PackageContents, Source, Test, Value,
+    About, Build, Cache, Conditional, ConditionalList, ConditionalRequirements, DownstreamTest,
+    Extra, IgnoreRunExports, IntermediateRecipe, Item, ListOrItem, Output, Package,
+    PackageContents, PerlTest, PythonTest, RTest, RubyTest, Script, ScriptTest, ScriptTestFiles,
+    ScriptTestRequirements, Source, Test, Value,
 };
 
 // Trait for converting to marked YAML nodes
@@ -140,6 +142,18 @@ impl ToMarkedYaml for Build {
             );
         }
 
+        if !self.files.is_empty() {
+            let nodes: Vec<MarkedNode> = self
+                .files
+                .iter()
+                .map(|file| MarkedNode::Scalar(MarkedScalarNode::new(Span::new_blank(), file)))
+                .collect();
+            mapping.insert(
+                MarkedScalarNode::new(Span::new_blank(), "files"),
+                MarkedNode::Sequence(MarkedSequenceNode::new(Span::new_blank(), nodes)),
+            );
+        }
+
         MarkedNode::Mapping(MarkedMappingNode::new(Span::new_blank(), mapping))
     }
 }
@@ -176,6 +190,37 @@ impl ToMarkedYaml for ConditionalRequirements {
             );
         }
 
+        if !self.ignore_run_exports.is_empty() {
+            mapping.insert(
+                MarkedScalarNode::new(Span::new_blank(), "ignore_run_exports"),
+                self.ignore_run_exports.to_marked_yaml(),
+            );
+        }
+
+        MarkedNode::Mapping(MarkedMappingNode::new(Span::new_blank(), mapping))
+    }
+}
+
+impl ToMarkedYaml for IgnoreRunExports {
+    fn to_marked_yaml(&self) -> MarkedNode {
+        let mut mapping = MappingHash::new();
+
+        for (key, names) in [
+            ("from_package", &self.from_package),
+            ("by_name", &self.by_name),
+        ] {
+            if !names.is_empty() {
+                let nodes: Vec<MarkedNode> = names
+                    .iter()
+                    .map(|name| MarkedNode::Scalar(MarkedScalarNode::new(Span::new_blank(), name)))
+                    .collect();
+                mapping.insert(
+                    MarkedScalarNode::new(Span::new_blank(), key),
+                    MarkedNode::Sequence(MarkedSequenceNode::new(Span::new_blank(), nodes)),
+                );
+            }
+        }
+
         MarkedNode::Mapping(MarkedMappingNode::new(Span::new_blank(), mapping))
     }
 }
@@ -202,14 +247,38 @@ impl ToMarkedYaml for PackageContents {
     }
 }
 
-impl ToMarkedYaml for Test {
+impl ToMarkedYaml for Script {
     fn to_marked_yaml(&self) -> MarkedNode {
         let mut mapping = MappingHash::new();
 
-        if let Some(ref package_contents) = self.package_contents {
+        mapping.insert(
+            MarkedScalarNode::new(Span::new_blank(), "content"),
+            MarkedNode::Scalar(MarkedScalarNode::new(Span::new_blank(), &self.content)),
+        );
+
+        if !self.env.is_empty() {
+            let mut env_mapping = MappingHash::new();
+            for (key, value) in self.env.iter() {
+                env_mapping.insert(
+                    MarkedScalarNode::new(Span::new_blank(), key),
+                    MarkedNode::Scalar(MarkedScalarNode::new(Span::new_blank(), value)),
+                );
+            }
             mapping.insert(
-                MarkedScalarNode::new(Span::new_blank(), "package_contents"),
-                package_contents.to_marked_yaml(),
+                MarkedScalarNode::new(Span::new_blank(), "env"),
+                MarkedNode::Mapping(MarkedMappingNode::new(Span::new_blank(), env_mapping)),
+            );
+        }
+
+        if !self.secrets.is_empty() {
+            let nodes: Vec<MarkedNode> = self
+                .secrets
+                .iter()
+                .map(|secret| MarkedNode::Scalar(MarkedScalarNode::new(Span::new_blank(), secret)))
+                .collect();
+            mapping.insert(
+                MarkedScalarNode::new(Span::new_blank(), "secrets"),
+                MarkedNode::Sequence(MarkedSequenceNode::new(Span::new_blank(), nodes)),
             );
         }
 
@@ -217,6 +286,195 @@ impl ToMarkedYaml for Test {
     }
 }
 
+impl ToMarkedYaml for PythonTest {
+    fn to_marked_yaml(&self) -> MarkedNode {
+        let mut mapping = MappingHash::new();
+
+        if !self.imports.is_empty() {
+            mapping.insert(
+                MarkedScalarNode::new(Span::new_blank(), "imports"),
+                self.imports.to_marked_yaml(),
+            );
+        }
+
+        if !self.pip_check {
+            mapping.insert(
+                MarkedScalarNode::new(Span::new_blank(), "pip_check"),
+                MarkedNode::Scalar(MarkedScalarNode::new(Span::new_blank(), "false")),
+            );
+        }
+
+        if let Some(ref python_version) = self.python_version {
+            mapping.insert(
+                MarkedScalarNode::new(Span::new_blank(), "python_version"),
+                python_version.to_marked_yaml(),
+            );
+        }
+
+        MarkedNode::Mapping(MarkedMappingNode::new(Span::new_blank(), mapping))
+    }
+}
+
+impl ToMarkedYaml for PerlTest {
+    fn to_marked_yaml(&self) -> MarkedNode {
+        let mut mapping = MappingHash::new();
+
+        mapping.insert(
+            MarkedScalarNode::new(Span::new_blank(), "uses"),
+            self.uses.to_marked_yaml(),
+        );
+
+        MarkedNode::Mapping(MarkedMappingNode::new(Span::new_blank(), mapping))
+    }
+}
+
+impl ToMarkedYaml for RTest {
+    fn to_marked_yaml(&self) -> MarkedNode {
+        let mut mapping = MappingHash::new();
+
+        mapping.insert(
+            MarkedScalarNode::new(Span::new_blank(), "libraries"),
+            self.libraries.to_marked_yaml(),
+        );
+
+        MarkedNode::Mapping(MarkedMappingNode::new(Span::new_blank(), mapping))
+    }
+}
+
+impl ToMarkedYaml for RubyTest {
+    fn to_marked_yaml(&self) -> MarkedNode {
+        let mut mapping = MappingHash::new();
+
+        mapping.insert(
+            MarkedScalarNode::new(Span::new_blank(), "requires"),
+            self.requires.to_marked_yaml(),
+        );
+
+        MarkedNode::Mapping(MarkedMappingNode::new(Span::new_blank(), mapping))
+    }
+}
+
+impl ToMarkedYaml for ScriptTestRequirements {
+    fn to_marked_yaml(&self) -> MarkedNode {
+        let mut mapping = MappingHash::new();
+
+        if !self.build.is_empty() {
+            mapping.insert(
+                MarkedScalarNode::new(Span::new_blank(), "build"),
+                self.build.to_marked_yaml(),
+            );
+        }
+
+        if !self.run.is_empty() {
+            mapping.insert(
+                MarkedScalarNode::new(Span::new_blank(), "run"),
+                self.run.to_marked_yaml(),
+            );
+        }
+
+        MarkedNode::Mapping(MarkedMappingNode::new(Span::new_blank(), mapping))
+    }
+}
+
+impl ToMarkedYaml for ScriptTestFiles {
+    fn to_marked_yaml(&self) -> MarkedNode {
+        let mut mapping = MappingHash::new();
+
+        if !self.source.is_empty() {
+            mapping.insert(
+                MarkedScalarNode::new(Span::new_blank(), "source"),
+                self.source.to_marked_yaml(),
+            );
+        }
+
+        if !self.recipe.is_empty() {
+            mapping.insert(
+                MarkedScalarNode::new(Span::new_blank(), "recipe"),
+                self.recipe.to_marked_yaml(),
+            );
+        }
+
+        MarkedNode::Mapping(MarkedMappingNode::new(Span::new_blank(), mapping))
+    }
+}
+
+impl ScriptTest {
+    /// Adds the keys of a script test to an existing test mapping. A script
+    /// test is not nested under a single key but spread over the test itself.
+    fn insert_into(&self, mapping: &mut MappingHash) {
+        mapping.insert(
+            MarkedScalarNode::new(Span::new_blank(), "script"),
+            self.script.to_marked_yaml(),
+        );
+
+        if !self.requirements.is_empty() {
+            mapping.insert(
+                MarkedScalarNode::new(Span::new_blank(), "requirements"),
+                self.requirements.to_marked_yaml(),
+            );
+        }
+
+        if !self.files.is_empty() {
+            mapping.insert(
+                MarkedScalarNode::new(Span::new_blank(), "files"),
+                self.files.to_marked_yaml(),
+            );
+        }
+    }
+}
+
+impl DownstreamTest {
+    fn insert_into(&self, mapping: &mut MappingHash) {
+        mapping.insert(
+            MarkedScalarNode::new(Span::new_blank(), "downstream"),
+            MarkedNode::Scalar(MarkedScalarNode::new(Span::new_blank(), &self.downstream)),
+        );
+    }
+}
+
+impl ToMarkedYaml for Test {
+    fn to_marked_yaml(&self) -> MarkedNode {
+        let mut mapping = MappingHash::new();
+
+        match self {
+            Test::Python { python } => {
+                mapping.insert(
+                    MarkedScalarNode::new(Span::new_blank(), "python"),
+                    python.to_marked_yaml(),
+                );
+            }
+            Test::Perl { perl } => {
+                mapping.insert(
+                    MarkedScalarNode::new(Span::new_blank(), "perl"),
+                    perl.to_marked_yaml(),
+                );
+            }
+            Test::R { r } => {
+                mapping.insert(
+                    MarkedScalarNode::new(Span::new_blank(), "r"),
+                    r.to_marked_yaml(),
+                );
+            }
+            Test::Ruby { ruby } => {
+                mapping.insert(
+                    MarkedScalarNode::new(Span::new_blank(), "ruby"),
+                    ruby.to_marked_yaml(),
+                );
+            }
+            Test::Script(script) => script.insert_into(&mut mapping),
+            Test::Downstream(downstream) => downstream.insert_into(&mut mapping),
+            Test::PackageContents { package_contents } => {
+                mapping.insert(
+                    MarkedScalarNode::new(Span::new_blank(), "package_contents"),
+                    package_contents.to_marked_yaml(),
+                );
+            }
+        }
+
+        MarkedNode::Mapping(MarkedMappingNode::new(Span::new_blank(), mapping))
+    }
+}
+
 impl ToMarkedYaml for About {
     fn to_marked_yaml(&self) -> MarkedNode {
         let mut mapping = MappingHash::new();
@@ -308,9 +566,14 @@ impl ToMarkedYaml for IntermediateRecipe {
             );
         }
 
-        // Add package
+        // Add package, multi-output recipes call it `recipe`
+        let package_key = if self.is_multi_output() {
+            "recipe"
+        } else {
+            "package"
+        };
         mapping.insert(
-            MarkedScalarNode::new(Span::new_blank(), "package"),
+            MarkedScalarNode::new(Span::new_blank(), package_key),
             self.package.to_marked_yaml(),
         );
 
@@ -326,20 +589,81 @@ impl ToMarkedYaml for IntermediateRecipe {
             self.build.to_marked_yaml(),
         );
 
+        // Multi-output recipes declare requirements and tests per output
+        if !self.is_multi_output() {
+            mapping.insert(
+                MarkedScalarNode::new(Span::new_blank(), "requirements"),
+                self.requirements.to_marked_yaml(),
+            );
+
+            if !self.tests.is_empty() {
+                mapping.insert(
+                    MarkedScalarNode::new(Span::new_blank(), "tests"),
+                    tests_to_marked_yaml(&self.tests),
+                );
+            }
+        }
+
+        if let Some(ref about) = self.about {
+            mapping.insert(
+                MarkedScalarNode::new(Span::new_blank(), "about"),
+                about.to_marked_yaml(),
+            );
+        }
+
+        if let Some(ref extra) = self.extra {
+            mapping.insert(
+                MarkedScalarNode::new(Span::new_blank(), "extra"),
+                extra.to_marked_yaml(),
+            );
+        }
+
+        if let Some(ref cache) = self.cache {
+            mapping.insert(
+                MarkedScalarNode::new(Span::new_blank(), "cache"),
+                cache.to_marked_yaml(),
+            );
+        }
+
+        if self.is_multi_output() {
+            let output_nodes: Vec<MarkedNode> = self
+                .outputs
+                .iter()
+                .map(|output| output.to_marked_yaml())
+                .collect();
+            mapping.insert(
+                MarkedScalarNode::new(Span::new_blank(), "outputs"),
+                MarkedNode::Sequence(MarkedSequenceNode::new(Span::new_blank(), output_nodes)),
+            );
+        }
+
+        MarkedNode::Mapping(MarkedMappingNode::new(Span::new_blank(), mapping))
+    }
+}
+
+impl ToMarkedYaml for Output {
+    fn to_marked_yaml(&self) -> MarkedNode {
+        let mut mapping = MappingHash::new();
+
+        mapping.insert(
+            MarkedScalarNode::new(Span::new_blank(), "package"),
+            self.package.to_marked_yaml(),
+        );
+
+        mapping.insert(
+            MarkedScalarNode::new(Span::new_blank(), "build"),
+            self.build.to_marked_yaml(),
+        );
+
         mapping.insert(
             MarkedScalarNode::new(Span::new_blank(), "requirements"),
             self.requirements.to_marked_yaml(),
         );
 
         if !self.tests.is_empty() {
-            let test_nodes: Vec<MarkedNode> = self
-                .tests
-                .iter()
-                .map(|test| test.to_marked_yaml())
-                .collect();
             mapping.insert(
                 MarkedScalarNode::new(Span::new_blank(), "tests"),
-                MarkedNode::Sequence(MarkedSequenceNode::new(Span::new_blank(), test_nodes)),
+                tests_to_marked_yaml(&self.tests),
             );
         }
 
@@ -350,17 +674,40 @@ impl ToMarkedYaml for IntermediateRecipe {
             );
         }
 
-        if let Some(ref extra) = self.extra {
+        MarkedNode::Mapping(MarkedMappingNode::new(Span::new_blank(), mapping))
+    }
+}
+
+impl ToMarkedYaml for Cache {
+    fn to_marked_yaml(&self) -> MarkedNode {
+        let mut mapping = MappingHash::new();
+
+        if !self.source.is_empty() {
             mapping.insert(
-                MarkedScalarNode::new(Span::new_blank(), "extra"),
-                extra.to_marked_yaml(),
+                MarkedScalarNode::new(Span::new_blank(), "source"),
+                self.source.to_marked_yaml(),
             );
         }
 
+        mapping.insert(
+            MarkedScalarNode::new(Span::new_blank(), "build"),
+            self.build.to_marked_yaml(),
+        );
+
+        mapping.insert(
+            MarkedScalarNode::new(Span::new_blank(), "requirements"),
+            self.requirements.to_marked_yaml(),
+        );
+
         MarkedNode::Mapping(MarkedMappingNode::new(Span::new_blank(), mapping))
     }
 }
 
+fn tests_to_marked_yaml(tests: &[Test]) -> MarkedNode {
+    let test_nodes: Vec<MarkedNode> = tests.iter().map(|test| test.to_marked_yaml()).collect();
+    MarkedNode::Sequence(MarkedSequenceNode::new(Span::new_blank(), test_nodes))
+}
+
 impl<T> ToMarkedYaml for ListOrItem<T>
 where
     T: ToString,
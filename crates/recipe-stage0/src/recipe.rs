@@ -6,7 +6,7 @@ use std::{
 use indexmap::IndexMap;
 use itertools::Itertools;
 use rattler_conda_types::{PackageName, Platform, package::EntryPoint};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, Serializer, ser::SerializeMap};
 
 use crate::{
     matchspec::{PackageDependency, SerializableMatchSpec},
@@ -332,11 +332,15 @@ impl<T: Display> Display for Conditional<T> {
 pub type ConditionalList<T> = Vec<Item<T>>;
 
 // Main recipe structure
-#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+//
+// A recipe with `outputs` is a multi-output recipe. For those, `package` is
+// serialized as the top-level `recipe` section and the top-level
+// `requirements` and `tests` are ignored, every output declares its own.
+#[derive(Deserialize, Default, Clone, Debug)]
 pub struct IntermediateRecipe {
     #[serde(default)]
     pub context: IndexMap<String, Value<String>>,
-    #[serde(default)]
+    #[serde(default, alias = "recipe")]
     pub package: Package,
     #[serde(default)]
     pub source: ConditionalList<Source>,
@@ -350,6 +354,88 @@ pub struct IntermediateRecipe {
     pub about: Option<About>,
     #[serde(default)]
     pub extra: Option<Extra>,
+    /// The staging cache that is built once and shared by all outputs.
+    #[serde(default)]
+    pub cache: Option<Cache>,
+    #[serde(default)]
+    pub outputs: Vec<Output>,
+}
+
+impl Serialize for IntermediateRecipe {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let multi_output = self.is_multi_output();
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("context", &self.context)?;
+        if multi_output {
+            map.serialize_entry("recipe", &self.package)?;
+        } else {
+            map.serialize_entry("package", &self.package)?;
+        }
+        map.serialize_entry("source", &self.source)?;
+        map.serialize_entry("build", &self.build)?;
+        if !multi_output {
+            map.serialize_entry("requirements", &self.requirements)?;
+            map.serialize_entry("tests", &self.tests)?;
+        }
+        map.serialize_entry("about", &self.about)?;
+        map.serialize_entry("extra", &self.extra)?;
+        if let Some(cache) = &self.cache {
+            map.serialize_entry("cache", cache)?;
+        }
+        if multi_output {
+            map.serialize_entry("outputs", &self.outputs)?;
+        }
+        map.end()
+    }
+}
+
+/// A single package of a multi-output recipe.
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct Output {
+    pub package: Package,
+    #[serde(default)]
+    pub build: Build,
+    #[serde(default)]
+    pub requirements: ConditionalRequirements,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tests: Vec<Test>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub about: Option<About>,
+}
+
+impl Display for Output {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{{ package: {}, requirements: {}, tests: [{}] }}",
+            self.package,
+            self.requirements,
+            self.tests.iter().format(", ")
+        )
+    }
+}
+
+/// The staging cache of a multi-output recipe. It is built once and the
+/// outputs pick the files they contain from its prefix with `build.files`.
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct Cache {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub source: ConditionalList<Source>,
+    #[serde(default)]
+    pub build: Build,
+    #[serde(default)]
+    pub requirements: ConditionalRequirements,
+}
+
+impl Display for Cache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{{ source: [{}], requirements: {} }}",
+            self.source.iter().format(", "),
+            self.requirements
+        )
+    }
 }
 
 pub struct EvaluatedDependencies {
@@ -481,7 +567,12 @@ pub struct PathSource {
     pub sha256: Option<Value<String>>,
 }
 
+/// A script that is executed during the build or a test.
+///
+/// Like in rattler-build, a script can be written as a single string, as a
+/// list of commands or as a mapping with its `content`, `env` and `secrets`.
 #[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[serde(from = "ScriptRepr")]
 pub struct Script {
     pub content: String,
     #[serde(default)]
@@ -490,6 +581,45 @@ pub struct Script {
     pub secrets: Vec<String>,
 }
 
+/// The forms in which a [`Script`] can be written in a recipe.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ScriptRepr {
+    Content(String),
+    Commands(Vec<String>),
+    Mapping {
+        content: String,
+        #[serde(default)]
+        env: IndexMap<String, String>,
+        #[serde(default)]
+        secrets: Vec<String>,
+    },
+}
+
+impl From<ScriptRepr> for Script {
+    fn from(repr: ScriptRepr) -> Self {
+        match repr {
+            ScriptRepr::Content(content) => Script {
+                content,
+                ..Default::default()
+            },
+            ScriptRepr::Commands(commands) => Script {
+                content: commands.join("\n"),
+                ..Default::default()
+            },
+            ScriptRepr::Mapping {
+                content,
+                env,
+                secrets,
+            } => Script {
+                content,
+                env,
+                secrets,
+            },
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "lowercase")]
 pub enum NoArchKind {
@@ -513,12 +643,18 @@ pub struct Python {
     /// executable and the module + function that should be executed.
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub entry_points: Vec<EntryPoint>,
+
+    /// The location of the `site-packages` directory relative to the root of
+    /// the prefix. This is only relevant for python interpreter packages that
+    /// do not use the default layout.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub site_packages_path: Option<String>,
 }
 
 impl Python {
     /// Returns true if this is the default python configuration.
     pub fn is_default(&self) -> bool {
-        self.entry_points.is_empty()
+        self.entry_points.is_empty() && self.site_packages_path.is_none()
     }
 }
 
@@ -527,6 +663,9 @@ impl Display for Python {
         for entry_point in &self.entry_points {
             write!(f, "{entry_point}, ")?;
         }
+        if let Some(site_packages_path) = &self.site_packages_path {
+            write!(f, "site_packages_path: {site_packages_path}")?;
+        }
         Ok(())
     }
 }
@@ -539,6 +678,9 @@ pub struct Build {
     pub noarch: Option<NoArchKind>,
     #[serde(default, skip_serializing_if = "Python::is_default")]
     pub python: Python,
+    /// Globs that select the files of an output from the staging cache.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub files: Vec<String>,
 }
 
 impl Build {
@@ -580,6 +722,43 @@ pub struct ConditionalRequirements {
     pub run: ConditionalList<PackageDependency>,
     #[serde(default)]
     pub run_constraints: ConditionalList<PackageDependency>,
+
+    /// Constraints on the packages in the build environment. These do not
+    /// cause packages to be installed but restrict the versions that may be
+    /// installed.
+    ///
+    /// The recipe format of rattler-build has no notion of build constraints,
+    /// so [`IntermediateRecipe::to_rattler_build_yaml`] leaves them out.
+    /// Instead, they are forwarded directly to the frontend by the backend.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub build_constraints: ConditionalList<PackageDependency>,
+
+    /// Constraints on the packages in the host environment. Like
+    /// [`Self::build_constraints`] these are not passed to rattler-build.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub host_constraints: ConditionalList<PackageDependency>,
+
+    /// Run exports of the build and host dependencies that are ignored.
+    #[serde(default, skip_serializing_if = "IgnoreRunExports::is_empty")]
+    pub ignore_run_exports: IgnoreRunExports,
+}
+
+/// Run exports that are not added to the run requirements of the package.
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+pub struct IgnoreRunExports {
+    /// Ignore all run exports of these packages.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub from_package: Vec<String>,
+    /// Ignore the run exports with these names, regardless of the package
+    /// they come from.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub by_name: Vec<String>,
+}
+
+impl IgnoreRunExports {
+    pub fn is_empty(&self) -> bool {
+        self.from_package.is_empty() && self.by_name.is_empty()
+    }
 }
 
 impl ConditionalRequirements {
@@ -650,11 +829,13 @@ impl Display for ConditionalRequirements {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "{{ build: {}, host: {}, run: {}, run_constraints: {} }}",
+            "{{ build: {}, host: {}, run: {}, run_constraints: {}, build_constraints: {}, host_constraints: {} }}",
             self.build.iter().format(", "),
             self.host.iter().format(", "),
             self.run.iter().format(", "),
             self.run_constraints.iter().format(", "),
+            self.build_constraints.iter().format(", "),
+            self.host_constraints.iter().format(", "),
         )
     }
 }
@@ -667,21 +848,267 @@ impl Display for ConditionalRequirements {
 //     pub run_constraints: Vec<SerializableMatchSpec>,
 // }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Default)]
-pub struct Test {
-    pub package_contents: Option<PackageContents>,
+/// A test that is executed against the built package. The variants mirror the
+/// test sections that are supported by rattler-build.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(untagged)]
+pub enum Test {
+    /// Checks that python modules can be imported and optionally runs
+    /// `pip check`.
+    Python { python: PythonTest },
+    /// Checks that perl modules can be loaded.
+    Perl { perl: PerlTest },
+    /// Checks that R libraries can be loaded.
+    R { r: RTest },
+    /// Checks that ruby modules can be required.
+    Ruby { ruby: RubyTest },
+    /// Runs a script in a dedicated test environment.
+    Script(ScriptTest),
+    /// Runs the tests of a downstream package against the built package.
+    Downstream(DownstreamTest),
+    /// Checks the files that are contained in the package.
+    PackageContents { package_contents: PackageContents },
+}
+
+impl Test {
+    /// Creates a test that imports the given python modules.
+    pub fn python_imports(imports: Vec<String>) -> Self {
+        Test::Python {
+            python: PythonTest {
+                imports: imports
+                    .into_iter()
+                    .map(|i| Item::Value(Value::Concrete(i)))
+                    .collect(),
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Creates a test that runs the given commands.
+    pub fn script(commands: Vec<String>) -> Self {
+        Test::Script(ScriptTest {
+            script: Script {
+                content: commands.join("\n"),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+    }
+
+    /// Creates a test that runs the tests of the given downstream package.
+    pub fn downstream(package: String) -> Self {
+        Test::Downstream(DownstreamTest {
+            downstream: package,
+        })
+    }
+}
+
+impl From<PackageContents> for Test {
+    fn from(package_contents: PackageContents) -> Self {
+        Test::PackageContents { package_contents }
+    }
 }
 
 impl Display for Test {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Test::Python { python } => write!(f, "Test {{ python: {python} }}"),
+            Test::Perl { perl } => write!(f, "Test {{ perl: {perl} }}"),
+            Test::R { r } => write!(f, "Test {{ r: {r} }}"),
+            Test::Ruby { ruby } => write!(f, "Test {{ ruby: {ruby} }}"),
+            Test::Script(script) => write!(f, "Test {{ {script} }}"),
+            Test::Downstream(downstream) => write!(f, "Test {{ {downstream} }}"),
+            Test::PackageContents { package_contents } => {
+                write!(f, "Test {{ package_contents: {package_contents} }}")
+            }
+        }
+    }
+}
+
+fn default_pip_check() -> bool {
+    true
+}
+
+fn is_true(value: &bool) -> bool {
+    *value
+}
+
+/// Python specific test configuration
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PythonTest {
+    /// The python modules that should be importable.
+    #[serde(default)]
+    pub imports: ConditionalList<String>,
+    /// Whether to run `pip check` in the test environment. Defaults to `true`.
+    #[serde(default = "default_pip_check", skip_serializing_if = "is_true")]
+    pub pip_check: bool,
+    /// The python version(s) to run the test with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub python_version: Option<ListOrItem<String>>,
+}
+
+impl Default for PythonTest {
+    fn default() -> Self {
+        Self {
+            imports: Vec::new(),
+            pip_check: true,
+            python_version: None,
+        }
+    }
+}
+
+impl Display for PythonTest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{{ imports: [{}], pip_check: {}, python_version: {} }}",
+            self.imports.iter().format(", "),
+            self.pip_check,
+            self.python_version.as_ref().into_iter().format(""),
+        )
+    }
+}
+
+/// Perl specific test configuration
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct PerlTest {
+    /// The perl modules that should be loadable.
+    #[serde(default)]
+    pub uses: ConditionalList<String>,
+}
+
+impl Display for PerlTest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{{ uses: [{}] }}", self.uses.iter().format(", "))
+    }
+}
+
+/// R specific test configuration
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct RTest {
+    /// The R libraries that should be loadable.
+    #[serde(default)]
+    pub libraries: ConditionalList<String>,
+}
+
+impl Display for RTest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{{ libraries: [{}] }}",
+            self.libraries.iter().format(", ")
+        )
+    }
+}
+
+/// Ruby specific test configuration
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct RubyTest {
+    /// The ruby modules that should be requirable.
+    #[serde(default)]
+    pub requires: ConditionalList<String>,
+}
+
+impl Display for RubyTest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{{ requires: [{}] }}", self.requires.iter().format(", "))
+    }
+}
+
+/// A test that runs a script in a dedicated test environment.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ScriptTest {
+    /// The script to execute.
+    pub script: Script,
+    /// Additional requirements of the test environment.
+    #[serde(default, skip_serializing_if = "ScriptTestRequirements::is_empty")]
+    pub requirements: ScriptTestRequirements,
+    /// Files that should be copied into the test directory.
+    #[serde(default, skip_serializing_if = "ScriptTestFiles::is_empty")]
+    pub files: ScriptTestFiles,
+}
+
+impl Display for ScriptTest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "script: {}, requirements: {}, files: {}",
+            self.script.content, self.requirements, self.files
+        )
+    }
+}
+
+/// The requirements of the environment in which a [`ScriptTest`] is run.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ScriptTestRequirements {
+    /// Requirements of the build environment of the test.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub build: ConditionalList<PackageDependency>,
+    /// Requirements of the environment that also contains the package.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub run: ConditionalList<PackageDependency>,
+}
+
+impl ScriptTestRequirements {
+    /// Returns true if there are no additional requirements.
+    pub fn is_empty(&self) -> bool {
+        self.build.is_empty() && self.run.is_empty()
+    }
+}
+
+impl Display for ScriptTestRequirements {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{{ build: [{}], run: [{}] }}",
+            self.build.iter().format(", "),
+            self.run.iter().format(", "),
+        )
+    }
+}
+
+/// The files that are copied into the test directory of a [`ScriptTest`].
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ScriptTestFiles {
+    /// Files from the source directory.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub source: ConditionalList<String>,
+    /// Files from the recipe directory.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub recipe: ConditionalList<String>,
+}
+
+impl ScriptTestFiles {
+    /// Returns true if no files are copied.
+    pub fn is_empty(&self) -> bool {
+        self.source.is_empty() && self.recipe.is_empty()
+    }
+}
+
+impl Display for ScriptTestFiles {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "Test {{ package_contents: {} }}",
-            self.package_contents.as_ref().into_iter().format("")
+            "{{ source: [{}], recipe: [{}] }}",
+            self.source.iter().format(", "),
+            self.recipe.iter().format(", "),
         )
     }
 }
 
+/// A test that runs the tests of a downstream package.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DownstreamTest {
+    /// The name (or match spec) of the downstream package.
+    pub downstream: String,
+}
+
+impl Display for DownstreamTest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "downstream: {}", self.downstream)
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct PackageContents {
     pub include: Option<ConditionalList<String>>,
@@ -756,10 +1183,35 @@ impl IntermediateRecipe {
         self.to_yaml()
     }
 
+    /// Converts the recipe to the YAML string that is parsed by
+    /// rattler-build. This leaves out the build and host constraints, which
+    /// are not part of the recipe format of rattler-build.
+    pub fn to_rattler_build_yaml(&self) -> Result<String, serde_yaml::Error> {
+        fn strip_constraints(requirements: &mut ConditionalRequirements) {
+            requirements.build_constraints.clear();
+            requirements.host_constraints.clear();
+        }
+
+        let mut recipe = self.clone();
+        strip_constraints(&mut recipe.requirements);
+        if let Some(cache) = &mut recipe.cache {
+            strip_constraints(&mut cache.requirements);
+        }
+        for output in &mut recipe.outputs {
+            strip_constraints(&mut output.requirements);
+        }
+        recipe.to_yaml()
+    }
+
     /// Creates a recipe from YAML string
     pub fn from_yaml(yaml: &str) -> Result<IntermediateRecipe, serde_yaml::Error> {
         serde_yaml::from_str(yaml)
     }
+
+    /// Returns true if the recipe produces multiple outputs.
+    pub fn is_multi_output(&self) -> bool {
+        !self.outputs.is_empty()
+    }
 }
 
 impl<T: ToString + Default + Debug> Conditional<T> {
@@ -837,6 +1289,7 @@ mod tests {
                 ],
                 run: vec!["xtl >=0.7,<0.8".parse().unwrap()],
                 run_constraints: vec!["xsimd >=8.0.3,<10".parse().unwrap()],
+                ..Default::default()
             },
             about: Some(About {
                 homepage: Some(Value::Concrete(
@@ -861,4 +1314,197 @@ mod tests {
 
         insta::assert_yaml_snapshot!(recipe)
     }
+
+    #[test]
+    fn test_recipe_tests_roundtrip() {
+        let yaml = r#"
+package:
+  name: foo
+  version: 0.1.0
+tests:
+  - python:
+      imports:
+        - foo
+      pip_check: false
+  - perl:
+      uses:
+        - Foo::Bar
+  - r:
+      libraries:
+        - foo
+  - ruby:
+      requires:
+        - foo
+  - script:
+      content: foo --help
+    requirements:
+      run:
+        - pytest
+    files:
+      source:
+        - tests/
+  - downstream: bar
+  - package_contents:
+      files:
+        - bin/foo
+"#;
+        let recipe = IntermediateRecipe::from_yaml(yaml).unwrap();
+        assert!(matches!(
+            recipe.tests.as_slice(),
+            [
+                Test::Python { .. },
+                Test::Perl { .. },
+                Test::R { .. },
+                Test::Ruby { .. },
+                Test::Script(_),
+                Test::Downstream(_),
+                Test::PackageContents { .. },
+            ]
+        ));
+
+        let Test::Python { python } = &recipe.tests[0] else {
+            unreachable!()
+        };
+        assert!(!python.pip_check);
+
+        let Test::Script(script) = &recipe.tests[4] else {
+            unreachable!()
+        };
+        assert_eq!(script.script.content, "foo --help");
+        assert_eq!(script.requirements.run.len(), 1);
+        assert_eq!(script.files.source.len(), 1);
+
+        let reparsed = IntermediateRecipe::from_yaml(&recipe.to_yaml().unwrap()).unwrap();
+        assert_eq!(reparsed.tests.len(), recipe.tests.len());
+        assert_eq!(
+            reparsed
+                .tests
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            recipe
+                .tests
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_constraints_round_trip() {
+        let yaml = r#"
+package:
+  name: foo
+  version: 0.1.0
+requirements:
+  host:
+    - numpy
+  build_constraints:
+    - cmake <4
+  host_constraints:
+    - numpy >=2
+"#;
+        let recipe = IntermediateRecipe::from_yaml(yaml).unwrap();
+        let reparsed = IntermediateRecipe::from_yaml(&recipe.to_yaml().unwrap()).unwrap();
+        assert_eq!(reparsed.requirements.build_constraints.len(), 1);
+        assert_eq!(reparsed.requirements.host_constraints.len(), 1);
+
+        let rattler_build_yaml = recipe.to_rattler_build_yaml().unwrap();
+        assert!(!rattler_build_yaml.contains("build_constraints"));
+        assert!(!rattler_build_yaml.contains("host_constraints"));
+        assert!(rattler_build_yaml.contains("numpy"));
+    }
+
+    #[test]
+    fn test_script_test_shorthands() {
+        let yaml = r#"
+package:
+  name: foo
+  version: 0.1.0
+tests:
+  - script: foo --help
+  - script:
+      - foo --version
+      - foo --help
+    requirements:
+      run:
+        - pytest
+"#;
+        let recipe = IntermediateRecipe::from_yaml(yaml).unwrap();
+        let [Test::Script(single), Test::Script(list)] = recipe.tests.as_slice() else {
+            panic!("expected two script tests, got {:?}", recipe.tests);
+        };
+        assert_eq!(single.script.content, "foo --help");
+        assert_eq!(list.script.content, "foo --version\nfoo --help");
+        assert_eq!(list.requirements.run.len(), 1);
+    }
+
+    #[test]
+    fn test_multi_output_roundtrip() {
+        let yaml = r#"
+recipe:
+  name: foo
+  version: 0.1.0
+cache:
+  requirements:
+    build:
+      - cmake
+  build:
+    script:
+      content: cmake --install build
+outputs:
+  - package:
+      name: libfoo
+      version: 0.1.0
+    build:
+      files:
+        - lib/
+  - package:
+      name: foo-tools
+      version: 0.1.0
+    build:
+      files:
+        - bin/
+    requirements:
+      run:
+        - libfoo
+    tests:
+      - script:
+          content: foo --help
+"#;
+        let recipe = IntermediateRecipe::from_yaml(yaml).unwrap();
+        assert!(recipe.is_multi_output());
+        assert_eq!(recipe.package.name.to_string(), "foo");
+        assert_eq!(
+            recipe.cache.as_ref().unwrap().build.script.content,
+            "cmake --install build"
+        );
+        assert_eq!(recipe.outputs.len(), 2);
+        assert_eq!(recipe.outputs[0].build.files, ["lib/"]);
+        assert_eq!(recipe.outputs[1].requirements.run.len(), 1);
+        assert_eq!(recipe.outputs[1].tests.len(), 1);
+
+        // Multi-output recipes use a top-level `recipe` section instead of
+        // `package` and have no top-level requirements.
+        let serialized = recipe.to_yaml().unwrap();
+        let value: serde_yaml::Value = serde_yaml::from_str(&serialized).unwrap();
+        assert!(value.get("recipe").is_some());
+        assert!(value.get("package").is_none());
+        assert!(value.get("requirements").is_none());
+
+        let reparsed = IntermediateRecipe::from_yaml(&serialized).unwrap();
+        assert_eq!(
+            reparsed
+                .outputs
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            recipe
+                .outputs
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+        );
+        assert!(reparsed.cache.is_some());
+    }
 }
@@ -17,6 +17,11 @@ from pixi_build_backend.pixi_build_backend import (
     PyPathSource,
     PyPackageSpecDependencies,
     PyItemString,
+    PyTest,
+    PyOutput,
+    PyCache,
+    PyOptionCache,
+    PyIgnoreRunExports,
 )
 from pixi_build_backend.types.item import VecItemPackageDependency, ItemPackageDependency
 from pixi_build_backend.types.platform import Platform
@@ -64,6 +69,16 @@ class IntermediateRecipe:
         """Set the requirements configuration."""
         self._inner.requirements = value._inner
 
+    @property
+    def tests(self) -> List["Test"]:
+        """Get the tests of the recipe."""
+        inner_tests = self._inner.tests
+        return [Test._from_inner(inner_tests[i]) for i in range(len(inner_tests))]
+
+    def add_test(self, test: "Test") -> None:
+        """Add a test that is executed against the built package."""
+        self._inner.tests.append(test._inner)
+
     @property
     def about(self) -> Optional["About"]:
         """Get the about information."""
@@ -76,6 +91,39 @@ class IntermediateRecipe:
         inner_extra = self._inner.extra
         return Extra._from_inner(inner_extra) if inner_extra else None
 
+    @property
+    def cache(self) -> Optional["Cache"]:
+        """Get the staging cache that is shared by all outputs."""
+        inner_cache = self._inner.cache.get()
+        return Cache._from_inner(inner_cache) if inner_cache else None
+
+    @cache.setter
+    def cache(self, value: Optional["Cache"]) -> None:
+        """Set the staging cache that is shared by all outputs."""
+        self._inner.cache = PyOptionCache(value._inner if value else None)
+
+    @property
+    def outputs(self) -> List["Output"]:
+        """Get the outputs of a multi-output recipe."""
+        inner_outputs = self._inner.outputs
+        return [Output._from_inner(inner_outputs[i]) for i in range(len(inner_outputs))]
+
+    def add_output(self, output: "Output") -> None:
+        """
+        Add an output to the recipe, which turns it into a multi-output recipe.
+
+        Examples
+        --------
+        ```python
+        >>> recipe = IntermediateRecipe()
+        >>> recipe.add_output(Output(Package("libfoo", "1.0.0")))
+        >>> "outputs" in recipe.to_yaml()
+        True
+        >>>
+        ```
+        """
+        self._inner.outputs.append(output._inner)
+
     def __repr__(self) -> str:
         return self._inner.__repr__()
 
@@ -233,6 +281,16 @@ class Build:
         """Set the Python configuration."""
         self._inner.python = value._inner
 
+    @property
+    def files(self) -> List[str]:
+        """Get the globs that select the files of an output from the staging cache."""
+        return self._inner.files
+
+    @files.setter
+    def files(self, value: List[str]) -> None:
+        """Set the globs that select the files of an output from the staging cache."""
+        self._inner.files = value
+
     @classmethod
     def _from_inner(cls, inner: PyBuild) -> "Build":
         """Create a Build from a PyBuild."""
@@ -252,6 +310,104 @@ class Build:
         return str(self._inner)
 
 
+class Output:
+    """A single package of a multi-output recipe."""
+
+    _inner: PyOutput
+
+    def __init__(self, package: "Package") -> None:
+        self._inner = PyOutput(package._inner)
+
+    @property
+    def package(self) -> "Package":
+        """Get the package information."""
+        return Package._from_inner(self._inner.package)
+
+    @package.setter
+    def package(self, value: "Package") -> None:
+        """Set the package information."""
+        self._inner.package = value._inner
+
+    @property
+    def build(self) -> "Build":
+        """Get the build configuration."""
+        return Build._from_inner(self._inner.build)
+
+    @build.setter
+    def build(self, value: "Build") -> None:
+        """Set the build configuration."""
+        self._inner.build = value._inner
+
+    @property
+    def requirements(self) -> "ConditionalRequirements":
+        """Get the requirements configuration."""
+        return ConditionalRequirements._from_inner(self._inner.requirements)
+
+    @requirements.setter
+    def requirements(self, value: "ConditionalRequirements") -> None:
+        """Set the requirements configuration."""
+        self._inner.requirements = value._inner
+
+    @property
+    def tests(self) -> List["Test"]:
+        """Get the tests of the output."""
+        inner_tests = self._inner.tests
+        return [Test._from_inner(inner_tests[i]) for i in range(len(inner_tests))]
+
+    def add_test(self, test: "Test") -> None:
+        """Add a test that is executed against the built package."""
+        self._inner.tests.append(test._inner)
+
+    @classmethod
+    def _from_inner(cls, inner: PyOutput) -> "Output":
+        """Create an Output from a PyOutput."""
+        instance = cls.__new__(cls)
+        instance._inner = inner
+        return instance
+
+    def __str__(self) -> str:
+        return str(self._inner)
+
+
+class Cache:
+    """The staging cache of a multi-output recipe, which is built once and shared by all outputs."""
+
+    _inner: PyCache
+
+    def __init__(self) -> None:
+        self._inner = PyCache()
+
+    @property
+    def build(self) -> "Build":
+        """Get the build configuration."""
+        return Build._from_inner(self._inner.build)
+
+    @build.setter
+    def build(self, value: "Build") -> None:
+        """Set the build configuration."""
+        self._inner.build = value._inner
+
+    @property
+    def requirements(self) -> "ConditionalRequirements":
+        """Get the requirements configuration."""
+        return ConditionalRequirements._from_inner(self._inner.requirements)
+
+    @requirements.setter
+    def requirements(self, value: "ConditionalRequirements") -> None:
+        """Set the requirements configuration."""
+        self._inner.requirements = value._inner
+
+    @classmethod
+    def _from_inner(cls, inner: PyCache) -> "Cache":
+        """Create a Cache from a PyCache."""
+        instance = cls.__new__(cls)
+        instance._inner = inner
+        return instance
+
+    def __str__(self) -> str:
+        return str(self._inner)
+
+
 class Script:
     """A script wrapper."""
 
@@ -315,8 +471,8 @@ class Python:
 
     _inner: PyPython
 
-    def __init__(self, entry_points: List[str]):
-        self._inner = PyPython(entry_points)
+    def __init__(self, entry_points: List[str], site_packages_path: Optional[str] = None):
+        self._inner = PyPython(entry_points, site_packages_path)
 
     @property
     def entry_points(self) -> List[str]:
@@ -328,6 +484,16 @@ class Python:
         """Set the entry points."""
         self._inner.set_entry_points(value)
 
+    @property
+    def site_packages_path(self) -> Optional[str]:
+        """Get the location of the site-packages directory relative to the prefix."""
+        return self._inner.site_packages_path
+
+    @site_packages_path.setter
+    def site_packages_path(self, value: Optional[str]) -> None:
+        """Set the location of the site-packages directory relative to the prefix."""
+        self._inner.site_packages_path = value
+
     @classmethod
     def _from_inner(cls, inner: PyPython) -> "Python":
         """Create a Python from a PyPython."""
@@ -712,6 +878,46 @@ class ConditionalRequirements:
             vec.extend(value)
             self._inner.run_constraints = vec._inner
 
+    @property
+    def build_constraints(self) -> "VecItemPackageDependency":
+        """Get the build constraints."""
+        return VecItemPackageDependency._from_inner(self._inner.build_constraints)
+
+    @build_constraints.setter
+    def build_constraints(self, value: Union[List[ItemPackageDependency], "VecItemPackageDependency"]) -> None:
+        """Set the build constraints."""
+        if isinstance(value, VecItemPackageDependency):
+            self._inner.build_constraints = value._inner
+        else:
+            vec = VecItemPackageDependency()
+            vec.extend(value)
+            self._inner.build_constraints = vec._inner
+
+    @property
+    def host_constraints(self) -> "VecItemPackageDependency":
+        """Get the host constraints."""
+        return VecItemPackageDependency._from_inner(self._inner.host_constraints)
+
+    @host_constraints.setter
+    def host_constraints(self, value: Union[List[ItemPackageDependency], "VecItemPackageDependency"]) -> None:
+        """Set the host constraints."""
+        if isinstance(value, VecItemPackageDependency):
+            self._inner.host_constraints = value._inner
+        else:
+            vec = VecItemPackageDependency()
+            vec.extend(value)
+            self._inner.host_constraints = vec._inner
+
+    @property
+    def ignore_run_exports(self) -> "IgnoreRunExports":
+        """Get the run exports that are ignored."""
+        return IgnoreRunExports._from_inner(self._inner.ignore_run_exports)
+
+    @ignore_run_exports.setter
+    def ignore_run_exports(self, value: "IgnoreRunExports") -> None:
+        """Set the run exports that are ignored."""
+        self._inner.ignore_run_exports = value._inner
+
     def resolve(self, host_platform: Optional[Platform] = None) -> "PackageSpecDependencies":
         """Resolve the requirements."""
         py_platform = host_platform._inner if host_platform else None
@@ -736,6 +942,147 @@ class ConditionalRequirements:
         return str(self._inner)
 
 
+class IgnoreRunExports:
+    """Run exports that are not added to the run requirements of the package."""
+
+    _inner: PyIgnoreRunExports
+
+    def __init__(self, from_package: Optional[List[str]] = None, by_name: Optional[List[str]] = None) -> None:
+        self._inner = PyIgnoreRunExports(from_package, by_name)
+
+    @property
+    def from_package(self) -> List[str]:
+        """Get the packages whose run exports are ignored."""
+        return self._inner.from_package
+
+    @from_package.setter
+    def from_package(self, value: List[str]) -> None:
+        """Set the packages whose run exports are ignored."""
+        self._inner.from_package = value
+
+    @property
+    def by_name(self) -> List[str]:
+        """Get the names of the run exports that are ignored."""
+        return self._inner.by_name
+
+    @by_name.setter
+    def by_name(self, value: List[str]) -> None:
+        """Set the names of the run exports that are ignored."""
+        self._inner.by_name = value
+
+    @classmethod
+    def _from_inner(cls, inner: PyIgnoreRunExports) -> "IgnoreRunExports":
+        """Create an IgnoreRunExports from a PyIgnoreRunExports."""
+        instance = cls.__new__(cls)
+        instance._inner = inner
+        return instance
+
+    def __str__(self) -> str:
+        return str(self._inner)
+
+
+class Test:
+    """A test that is executed against the built package."""
+
+    _inner: PyTest
+
+    @classmethod
+    def python(cls, imports: List[str], pip_check: bool = True) -> "Test":
+        """
+        Create a test that imports python modules.
+
+        Examples
+        --------
+        ```python
+        >>> test = Test.python(["boltons"])
+        >>> test.is_python()
+        True
+        >>>
+        ```
+        """
+        return cls._from_inner(PyTest.python(imports, pip_check))
+
+    @classmethod
+    def perl(cls, uses: List[str]) -> "Test":
+        """Create a test that loads perl modules."""
+        return cls._from_inner(PyTest.perl(uses))
+
+    @classmethod
+    def r(cls, libraries: List[str]) -> "Test":
+        """Create a test that loads R libraries."""
+        return cls._from_inner(PyTest.r(libraries))
+
+    @classmethod
+    def ruby(cls, requires: List[str]) -> "Test":
+        """Create a test that requires ruby modules."""
+        return cls._from_inner(PyTest.ruby(requires))
+
+    @classmethod
+    def script(cls, script: "Script", requirements: Optional[List["ItemPackageDependency"]] = None) -> "Test":
+        """
+        Create a test that runs a script in a test environment.
+
+        Examples
+        --------
+        ```python
+        >>> test = Test.script(Script("foo --help"))
+        >>> test.is_script()
+        True
+        >>>
+        ```
+        """
+        inner_requirements = [requirement._inner for requirement in requirements] if requirements else None
+        return cls._from_inner(PyTest.script(script._inner, inner_requirements))
+
+    @classmethod
+    def downstream(cls, downstream: str) -> "Test":
+        """Create a test that runs the tests of a downstream package."""
+        return cls._from_inner(PyTest.downstream(downstream))
+
+    @classmethod
+    def package_contents(cls, include: Optional[List[str]] = None, files: Optional[List[str]] = None) -> "Test":
+        """Create a test that checks the contents of the package."""
+        return cls._from_inner(PyTest.package_contents(include, files))
+
+    def is_python(self) -> bool:
+        """Check if this is a python test."""
+        return self._inner.is_python()
+
+    def is_perl(self) -> bool:
+        """Check if this is a perl test."""
+        return self._inner.is_perl()
+
+    def is_r(self) -> bool:
+        """Check if this is an R test."""
+        return self._inner.is_r()
+
+    def is_ruby(self) -> bool:
+        """Check if this is a ruby test."""
+        return self._inner.is_ruby()
+
+    def is_script(self) -> bool:
+        """Check if this is a script test."""
+        return self._inner.is_script()
+
+    def is_downstream(self) -> bool:
+        """Check if this is a downstream test."""
+        return self._inner.is_downstream()
+
+    def is_package_contents(self) -> bool:
+        """Check if this is a package contents test."""
+        return self._inner.is_package_contents()
+
+    @classmethod
+    def _from_inner(cls, inner: PyTest) -> "Test":
+        """Create a Test from a PyTest."""
+        instance = cls.__new__(cls)
+        instance._inner = inner
+        return instance
+
+    def __str__(self) -> str:
+        return str(self._inner)
+
+
 class About:
     """An about information wrapper."""
 
@@ -94,8 +94,15 @@ fn pixi_build_backend(py: Python<'_>, m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<recipe_stage0::recipe::PyValueString>()?;
     m.add_class::<recipe_stage0::recipe::PyValueU64>()?;
     m.add_class::<recipe_stage0::recipe::PyConditionalRequirements>()?;
+    m.add_class::<recipe_stage0::recipe::PyIgnoreRunExports>()?;
+    m.add_class::<recipe_stage0::recipe::PyTest>()?;
+    m.add_class::<recipe_stage0::recipe::PyVecTest>()?;
     m.add_class::<recipe_stage0::recipe::PyAbout>()?;
     m.add_class::<recipe_stage0::recipe::PyExtra>()?;
+    m.add_class::<recipe_stage0::recipe::PyOutput>()?;
+    m.add_class::<recipe_stage0::recipe::PyVecOutput>()?;
+    m.add_class::<recipe_stage0::recipe::PyCache>()?;
+    m.add_class::<recipe_stage0::recipe::PyOptionCache>()?;
 
     // Add requirements types
     m.add_class::<recipe_stage0::requirements::PyPackageSpecDependencies>()?;
@@ -2,7 +2,7 @@ use crate::{
     create_py_wrap,
     error::PyPixiBuildBackendError,
     recipe_stage0::{
-        conditional::{PyItemSource, PyItemString},
+        conditional::{PyItemPackageDependency, PyItemSource, PyItemString},
         conditional_requirements::PyVecItemPackageDependency,
         requirements::PyPackageSpecDependencies,
     },
@@ -18,8 +18,10 @@ use pyo3::{
 };
 use rattler_conda_types::package::EntryPoint;
 use recipe_stage0::recipe::{
-    About, Build, ConditionalRequirements, Extra, IntermediateRecipe, Item, NoArchKind, Package,
-    PathSource, Python as RecipePython, Script, Source, Test, UrlSource, Value,
+    About, Build, Cache, ConditionalRequirements, DownstreamTest, Extra, IgnoreRunExports,
+    IntermediateRecipe, Item, NoArchKind, Output, Package, PackageContents, PathSource, PerlTest,
+    Python as RecipePython, PythonTest, RTest, RubyTest, Script, ScriptTest,
+    ScriptTestRequirements, Source, Test, UrlSource, Value,
 };
 
 use std::fmt::{Display, Formatter};
@@ -58,6 +60,88 @@ create_py_wrap!(
     }
 );
 
+#[pymethods]
+impl PyVecTest {
+    #[new]
+    pub fn new() -> Self {
+        PyVecTest::default()
+    }
+
+    pub fn __getitem__(&self, index: usize) -> PyResult<PyTest> {
+        self.inner
+            .get(index)
+            .cloned()
+            .ok_or_else(|| pyo3::exceptions::PyIndexError::new_err("list index out of range"))
+    }
+
+    pub fn __len__(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn append(&mut self, test: PyTest) {
+        self.inner.push(test);
+    }
+}
+
+create_py_wrap!(
+    PyVecOutput,
+    Vec<PyOutput>,
+    |vec: &Vec<PyOutput>, f: &mut Formatter<'_>| {
+        write!(f, "[")?;
+        for item in vec {
+            write!(f, "{item}, ")?;
+        }
+        write!(f, "]")
+    }
+);
+
+#[pymethods]
+impl PyVecOutput {
+    #[new]
+    pub fn new() -> Self {
+        PyVecOutput::default()
+    }
+
+    pub fn __getitem__(&self, index: usize) -> PyResult<PyOutput> {
+        self.inner
+            .get(index)
+            .cloned()
+            .ok_or_else(|| pyo3::exceptions::PyIndexError::new_err("list index out of range"))
+    }
+
+    pub fn __len__(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn append(&mut self, output: PyOutput) {
+        self.inner.push(output);
+    }
+}
+
+create_py_wrap!(
+    PyOptionCache,
+    Option<PyCache>,
+    |opt: &Option<PyCache>, f: &mut Formatter<'_>| {
+        match opt {
+            Some(cache) => write!(f, "{cache}"),
+            None => write!(f, "None"),
+        }
+    }
+);
+
+#[pymethods]
+impl PyOptionCache {
+    #[new]
+    #[pyo3(signature = (cache=None))]
+    pub fn new(cache: Option<PyCache>) -> Self {
+        PyOptionCache { inner: cache }
+    }
+
+    pub fn get(&self) -> Option<PyCache> {
+        self.inner.clone()
+    }
+}
+
 create_py_wrap!(
     PyOptionAbout,
     Option<PyAbout>,
@@ -90,13 +174,15 @@ pub struct PyIntermediateRecipe {
     pub tests: Py<PyVecTest>,
     pub about: Py<PyOptionAbout>,
     pub extra: Py<PyOptionExtra>,
+    pub cache: Py<PyOptionCache>,
+    pub outputs: Py<PyVecOutput>,
 }
 
 impl Display for PyIntermediateRecipe {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "{{ context: {}, package: {}, source: {}, build: {}, requirements: {}, tests: {}, about: {}, extra: {} }}",
+            "{{ context: {}, package: {}, source: {}, build: {}, requirements: {}, tests: {}, about: {}, extra: {}, cache: {}, outputs: {} }}",
             self.context,
             self.package,
             self.source,
@@ -104,7 +190,9 @@ impl Display for PyIntermediateRecipe {
             self.requirements,
             self.tests,
             self.about,
-            self.extra
+            self.extra,
+            self.cache,
+            self.outputs
         )
     }
 }
@@ -122,6 +210,8 @@ impl PyIntermediateRecipe {
             tests: Py::new(py, PyVecTest::default())?,
             about: Py::new(py, PyOptionAbout::default())?,
             extra: Py::new(py, PyOptionExtra::default())?,
+            cache: Py::new(py, PyOptionCache::default())?,
+            outputs: Py::new(py, PyVecOutput::default())?,
         })
     }
     /// Creates a recipe from YAML string
@@ -154,9 +244,29 @@ impl PyIntermediateRecipe {
         let mut value: Value =
             serde_yaml::from_str(&yaml).map_err(PyPixiBuildBackendError::YamlSerialization)?;
 
-        // Navigate to build.script.content
-        if let Value::Mapping(ref mut map) = value
-            && let Some(Value::Mapping(build_map)) = map.get_mut("build")
+        // Navigate to the build sections of the recipe, the cache and the outputs
+        if let Value::Mapping(ref mut map) = value {
+            Self::preprocess_build_script_content(map);
+            if let Some(Value::Mapping(cache_map)) = map.get_mut("cache") {
+                Self::preprocess_build_script_content(cache_map);
+            }
+            if let Some(Value::Sequence(outputs)) = map.get_mut("outputs") {
+                for output in outputs {
+                    if let Value::Mapping(output_map) = output {
+                        Self::preprocess_build_script_content(output_map);
+                    }
+                }
+            }
+        }
+
+        Ok(serde_yaml::to_string(&value).map_err(PyPixiBuildBackendError::YamlSerialization)?)
+    }
+
+    /// Converts `build.script.content` of a section from Vec<String> to String
+    fn preprocess_build_script_content(map: &mut serde_yaml::Mapping) {
+        use serde_yaml::Value;
+
+        if let Some(Value::Mapping(build_map)) = map.get_mut("build")
             && let Some(Value::Mapping(script_map)) = build_map.get_mut("script")
             && let Some(content_value) = script_map.get_mut("content")
         {
@@ -174,8 +284,6 @@ impl PyIntermediateRecipe {
             }
             // If it's already a String, leave it as is
         }
-
-        Ok(serde_yaml::to_string(&value).map_err(PyPixiBuildBackendError::YamlSerialization)?)
     }
 
     pub fn from_intermediate_recipe(recipe: IntermediateRecipe, py: Python) -> Self {
@@ -218,6 +326,19 @@ impl PyIntermediateRecipe {
             inner: recipe.extra.map(|extra| extra.into()),
         };
 
+        // Convert cache (Option<Cache>)
+        let py_cache = PyOptionCache {
+            inner: recipe.cache.map(|cache| PyCache::from_cache(py, cache)),
+        };
+
+        // Convert outputs
+        let py_outputs: Vec<PyOutput> = recipe
+            .outputs
+            .into_iter()
+            .map(|output| PyOutput::from_output(py, output))
+            .collect();
+        let py_vec_outputs: PyVecOutput = py_outputs.into();
+
         PyIntermediateRecipe {
             context: Py::new(py, py_context).unwrap(),
             package: Py::new(py, py_package).unwrap(),
@@ -227,6 +348,8 @@ impl PyIntermediateRecipe {
             tests: Py::new(py, py_vec_tests).unwrap(),
             about: Py::new(py, py_about).unwrap(),
             extra: Py::new(py, py_extra).unwrap(),
+            cache: Py::new(py, py_cache).unwrap(),
+            outputs: Py::new(py, py_vec_outputs).unwrap(),
         }
     }
 
@@ -266,6 +389,15 @@ impl PyIntermediateRecipe {
             .clone()
             .map(|extra| (*extra).clone());
 
+        let cache: Option<Cache> = (*self.cache.borrow(py).clone())
+            .clone()
+            .map(|cache| cache.into_cache(py));
+        let outputs: Vec<Output> = (*self.outputs.borrow(py).clone())
+            .clone()
+            .into_iter()
+            .map(|output| output.into_output(py))
+            .collect();
+
         IntermediateRecipe {
             context,
             package,
@@ -275,10 +407,152 @@ impl PyIntermediateRecipe {
             tests,
             about,
             extra,
+            cache,
+            outputs,
+        }
+    }
+}
+
+#[pyclass(get_all, set_all, str)]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PyOutput {
+    pub package: Py<PyPackage>,
+    pub build: Py<PyBuild>,
+    pub requirements: Py<PyConditionalRequirements>,
+    pub tests: Py<PyVecTest>,
+    pub about: Py<PyOptionAbout>,
+}
+
+#[pymethods]
+impl PyOutput {
+    #[new]
+    pub fn new(py: Python, package: PyPackage) -> PyResult<Self> {
+        Ok(PyOutput {
+            package: Py::new(py, package)?,
+            build: Py::new(py, PyBuild::new(py))?,
+            requirements: Py::new(py, PyConditionalRequirements::new(py))?,
+            tests: Py::new(py, PyVecTest::default())?,
+            about: Py::new(py, PyOptionAbout::default())?,
+        })
+    }
+}
+
+impl PyOutput {
+    pub fn into_output(self, py: Python) -> Output {
+        Output {
+            package: (*self.package.borrow(py).clone()).clone(),
+            build: self.build.borrow(py).clone().into_build(py),
+            requirements: self
+                .requirements
+                .borrow(py)
+                .clone()
+                .into_conditional_requirements(py),
+            tests: (*self.tests.borrow(py).clone())
+                .clone()
+                .into_iter()
+                .map(|test| (*test).clone())
+                .collect(),
+            about: (*self.about.borrow(py).clone())
+                .clone()
+                .map(|about| (*about).clone()),
+        }
+    }
+
+    pub fn from_output(py: Python, output: Output) -> Self {
+        let py_tests: Vec<PyTest> = output.tests.into_iter().map(|test| test.into()).collect();
+        let py_vec_tests: PyVecTest = py_tests.into();
+        let py_about = PyOptionAbout {
+            inner: output.about.map(|about| about.into()),
+        };
+
+        PyOutput {
+            package: Py::new(py, PyPackage::from(output.package)).unwrap(),
+            build: Py::new(py, PyBuild::from_build(py, output.build)).unwrap(),
+            requirements: Py::new(
+                py,
+                PyConditionalRequirements::from_conditional_requirements(py, output.requirements),
+            )
+            .unwrap(),
+            tests: Py::new(py, py_vec_tests).unwrap(),
+            about: Py::new(py, py_about).unwrap(),
         }
     }
 }
 
+impl Display for PyOutput {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{{ package: {}, build: {}, requirements: {}, tests: {}, about: {} }}",
+            self.package, self.build, self.requirements, self.tests, self.about
+        )
+    }
+}
+
+#[pyclass(get_all, set_all, str)]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PyCache {
+    pub source: Py<PyVecItemSource>,
+    pub build: Py<PyBuild>,
+    pub requirements: Py<PyConditionalRequirements>,
+}
+
+#[pymethods]
+impl PyCache {
+    #[new]
+    pub fn new(py: Python) -> PyResult<Self> {
+        Ok(PyCache {
+            source: Py::new(py, PyVecItemSource::default())?,
+            build: Py::new(py, PyBuild::new(py))?,
+            requirements: Py::new(py, PyConditionalRequirements::new(py))?,
+        })
+    }
+}
+
+impl PyCache {
+    pub fn into_cache(self, py: Python) -> Cache {
+        Cache {
+            source: (*self.source.borrow(py).clone())
+                .clone()
+                .into_iter()
+                .map(|item| (*item).clone())
+                .collect(),
+            build: self.build.borrow(py).clone().into_build(py),
+            requirements: self
+                .requirements
+                .borrow(py)
+                .clone()
+                .into_conditional_requirements(py),
+        }
+    }
+
+    pub fn from_cache(py: Python, cache: Cache) -> Self {
+        let py_sources: Vec<PyItemSource> =
+            cache.source.into_iter().map(|item| item.into()).collect();
+        let py_vec_source: PyVecItemSource = py_sources.into();
+
+        PyCache {
+            source: Py::new(py, py_vec_source).unwrap(),
+            build: Py::new(py, PyBuild::from_build(py, cache.build)).unwrap(),
+            requirements: Py::new(
+                py,
+                PyConditionalRequirements::from_conditional_requirements(py, cache.requirements),
+            )
+            .unwrap(),
+        }
+    }
+}
+
+impl Display for PyCache {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{{ source: {}, build: {}, requirements: {} }}",
+            self.source, self.build, self.requirements
+        )
+    }
+}
+
 #[pyclass(str)]
 #[derive(Clone, Default, Serialize, Deserialize)]
 pub struct PyPackage {
@@ -479,6 +753,7 @@ pub struct PyBuild {
     pub script: Py<PyScript>,
     pub noarch: Py<PyOptionPyNoArchKind>,
     pub python: Py<PyPython>,
+    pub files: Vec<String>,
 }
 
 impl PyBuild {
@@ -500,6 +775,7 @@ impl PyBuild {
             script: self.script.borrow(py).clone().into_script(py),
             noarch,
             python: self.python.borrow(py).inner.clone(),
+            files: self.files,
         }
     }
 
@@ -516,6 +792,7 @@ impl PyBuild {
             script: Py::new(py, PyScript::from_script(py, build.script)).unwrap(),
             noarch: Py::new(py, py_noarch_value).unwrap(),
             python: Py::new(py, Into::<PyPython>::into(build.python)).unwrap(),
+            files: build.files,
         }
     }
 }
@@ -528,7 +805,8 @@ impl PyBuild {
             number: Py::new(py, PyOptionValueU64::default()).unwrap(),
             script: Py::new(py, PyScript::new(py, None, None, None)).unwrap(),
             noarch: Py::new(py, PyOptionPyNoArchKind::default()).unwrap(),
-            python: Py::new(py, PyPython::new(None).unwrap()).unwrap(),
+            python: Py::new(py, PyPython::new(None, None).unwrap()).unwrap(),
+            files: Vec::new(),
         }
     }
 }
@@ -537,8 +815,12 @@ impl Display for PyBuild {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "{{ number: {}, script: {}, noarch: {}, python: {} }}",
-            self.number, self.script, self.noarch, self.python
+            "{{ number: {}, script: {}, noarch: {}, python: {}, files: [{}] }}",
+            self.number,
+            self.script,
+            self.noarch,
+            self.python,
+            self.files.join(", ")
         )
     }
 }
@@ -625,7 +907,11 @@ pub struct PyPython {
 #[pymethods]
 impl PyPython {
     #[new]
-    pub fn new(entry_points: Option<Vec<String>>) -> PyResult<Self> {
+    #[pyo3(signature = (entry_points=None, site_packages_path=None))]
+    pub fn new(
+        entry_points: Option<Vec<String>>,
+        site_packages_path: Option<String>,
+    ) -> PyResult<Self> {
         let entry_points: Result<Vec<EntryPoint>, _> = entry_points
             .unwrap_or_default()
             .into_iter()
@@ -634,7 +920,10 @@ impl PyPython {
 
         match entry_points {
             Ok(entry_points) => Ok(PyPython {
-                inner: RecipePython { entry_points },
+                inner: RecipePython {
+                    entry_points,
+                    site_packages_path,
+                },
             }),
             Err(_) => Err(pyo3::exceptions::PyValueError::new_err(
                 "Invalid entry point format",
@@ -666,6 +955,16 @@ impl PyPython {
             )),
         }
     }
+
+    #[getter]
+    pub fn site_packages_path(&self) -> Option<String> {
+        self.inner.site_packages_path.clone()
+    }
+
+    #[setter]
+    pub fn set_site_packages_path(&mut self, site_packages_path: Option<String>) {
+        self.inner.site_packages_path = site_packages_path;
+    }
 }
 
 impl From<RecipePython> for PyPython {
@@ -813,6 +1112,57 @@ pub struct PyConditionalRequirements {
     pub(crate) run: Py<PyVecItemPackageDependency>,
     // #[serde(default)]
     pub(crate) run_constraints: Py<PyVecItemPackageDependency>,
+    pub(crate) build_constraints: Py<PyVecItemPackageDependency>,
+    pub(crate) host_constraints: Py<PyVecItemPackageDependency>,
+    pub(crate) ignore_run_exports: Py<PyIgnoreRunExports>,
+}
+
+#[pyclass(get_all, set_all, str)]
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct PyIgnoreRunExports {
+    pub from_package: Vec<String>,
+    pub by_name: Vec<String>,
+}
+
+#[pymethods]
+impl PyIgnoreRunExports {
+    #[new]
+    #[pyo3(signature = (from_package=None, by_name=None))]
+    pub fn new(from_package: Option<Vec<String>>, by_name: Option<Vec<String>>) -> Self {
+        PyIgnoreRunExports {
+            from_package: from_package.unwrap_or_default(),
+            by_name: by_name.unwrap_or_default(),
+        }
+    }
+}
+
+impl From<IgnoreRunExports> for PyIgnoreRunExports {
+    fn from(ignore_run_exports: IgnoreRunExports) -> Self {
+        PyIgnoreRunExports {
+            from_package: ignore_run_exports.from_package,
+            by_name: ignore_run_exports.by_name,
+        }
+    }
+}
+
+impl From<PyIgnoreRunExports> for IgnoreRunExports {
+    fn from(py_ignore_run_exports: PyIgnoreRunExports) -> Self {
+        IgnoreRunExports {
+            from_package: py_ignore_run_exports.from_package,
+            by_name: py_ignore_run_exports.by_name,
+        }
+    }
+}
+
+impl Display for PyIgnoreRunExports {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{{ from_package: [{}], by_name: [{}] }}",
+            self.from_package.join(", "),
+            self.by_name.join(", ")
+        )
+    }
 }
 
 #[pymethods]
@@ -823,12 +1173,17 @@ impl PyConditionalRequirements {
         let host = PyVecItemPackageDependency::new();
         let run = PyVecItemPackageDependency::new();
         let run_constraints = PyVecItemPackageDependency::new();
+        let build_constraints = PyVecItemPackageDependency::new();
+        let host_constraints = PyVecItemPackageDependency::new();
 
         PyConditionalRequirements {
             build: Py::new(py, build).unwrap(),
             host: Py::new(py, host).unwrap(),
             run: Py::new(py, run).unwrap(),
             run_constraints: Py::new(py, run_constraints).unwrap(),
+            build_constraints: Py::new(py, build_constraints).unwrap(),
+            host_constraints: Py::new(py, host_constraints).unwrap(),
+            ignore_run_exports: Py::new(py, PyIgnoreRunExports::default()).unwrap(),
         }
     }
 
@@ -882,6 +1237,17 @@ impl PyConditionalRequirements {
                 .clone()
                 .into_iter()
                 .collect(),
+            build_constraints: (self.build_constraints.borrow(py).clone())
+                .inner
+                .clone()
+                .into_iter()
+                .collect(),
+            host_constraints: (self.host_constraints.borrow(py).clone())
+                .inner
+                .clone()
+                .into_iter()
+                .collect(),
+            ignore_run_exports: self.ignore_run_exports.borrow(py).clone().into(),
         }
     }
 
@@ -893,12 +1259,21 @@ impl PyConditionalRequirements {
         let host: PyVecItemPackageDependency = requirements.host.into();
         let run: PyVecItemPackageDependency = requirements.run.into();
         let run_constraints: PyVecItemPackageDependency = requirements.run_constraints.into();
+        let build_constraints: PyVecItemPackageDependency = requirements.build_constraints.into();
+        let host_constraints: PyVecItemPackageDependency = requirements.host_constraints.into();
 
         PyConditionalRequirements {
             build: Py::new(py, build).unwrap(),
             host: Py::new(py, host).unwrap(),
             run: Py::new(py, run).unwrap(),
             run_constraints: Py::new(py, run_constraints).unwrap(),
+            build_constraints: Py::new(py, build_constraints).unwrap(),
+            host_constraints: Py::new(py, host_constraints).unwrap(),
+            ignore_run_exports: Py::new(
+                py,
+                PyIgnoreRunExports::from(requirements.ignore_run_exports),
+            )
+            .unwrap(),
         }
     }
 }
@@ -909,11 +1284,166 @@ impl Display for PyConditionalRequirements {
         write!(f, "{{ host: {} }}", self.host)?;
         write!(f, "{{ run: {} }}", self.run)?;
         write!(f, "{{ run_constraints: {} }}", self.run_constraints)?;
+        write!(f, "{{ build_constraints: {} }}", self.build_constraints)?;
+        write!(f, "{{ host_constraints: {} }}", self.host_constraints)?;
+        write!(f, "{{ ignore_run_exports: {} }}", self.ignore_run_exports)?;
         Ok(())
     }
 }
 
-create_py_wrap!(PyTest, Test);
+#[pyclass(str)]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PyTest {
+    pub(crate) inner: Test,
+}
+
+fn to_conditional_list(items: Vec<String>) -> Vec<Item<String>> {
+    items
+        .into_iter()
+        .map(|item| Item::Value(Value::Concrete(item)))
+        .collect()
+}
+
+#[pymethods]
+impl PyTest {
+    #[staticmethod]
+    #[pyo3(signature = (imports, pip_check=true))]
+    pub fn python(imports: Vec<String>, pip_check: bool) -> Self {
+        PyTest {
+            inner: Test::Python {
+                python: PythonTest {
+                    imports: to_conditional_list(imports),
+                    pip_check,
+                    python_version: None,
+                },
+            },
+        }
+    }
+
+    #[staticmethod]
+    pub fn perl(uses: Vec<String>) -> Self {
+        PyTest {
+            inner: Test::Perl {
+                perl: PerlTest {
+                    uses: to_conditional_list(uses),
+                },
+            },
+        }
+    }
+
+    #[staticmethod]
+    pub fn r(libraries: Vec<String>) -> Self {
+        PyTest {
+            inner: Test::R {
+                r: RTest {
+                    libraries: to_conditional_list(libraries),
+                },
+            },
+        }
+    }
+
+    #[staticmethod]
+    pub fn ruby(requires: Vec<String>) -> Self {
+        PyTest {
+            inner: Test::Ruby {
+                ruby: RubyTest {
+                    requires: to_conditional_list(requires),
+                },
+            },
+        }
+    }
+
+    #[staticmethod]
+    #[pyo3(signature = (script, requirements=None))]
+    pub fn script(
+        py: Python,
+        script: PyScript,
+        requirements: Option<Vec<PyItemPackageDependency>>,
+    ) -> Self {
+        let run = requirements
+            .unwrap_or_default()
+            .into_iter()
+            .map(|item| item.inner)
+            .collect();
+
+        PyTest {
+            inner: Test::Script(ScriptTest {
+                script: script.into_script(py),
+                requirements: ScriptTestRequirements {
+                    run,
+                    ..Default::default()
+                },
+                ..Default::default()
+            }),
+        }
+    }
+
+    #[staticmethod]
+    pub fn downstream(downstream: String) -> Self {
+        PyTest {
+            inner: Test::Downstream(DownstreamTest { downstream }),
+        }
+    }
+
+    #[staticmethod]
+    #[pyo3(signature = (include=None, files=None))]
+    pub fn package_contents(include: Option<Vec<String>>, files: Option<Vec<String>>) -> Self {
+        PyTest {
+            inner: PackageContents {
+                include: include.map(to_conditional_list),
+                files: files.map(to_conditional_list),
+            }
+            .into(),
+        }
+    }
+
+    pub fn is_python(&self) -> bool {
+        matches!(self.inner, Test::Python { .. })
+    }
+
+    pub fn is_perl(&self) -> bool {
+        matches!(self.inner, Test::Perl { .. })
+    }
+
+    pub fn is_r(&self) -> bool {
+        matches!(self.inner, Test::R { .. })
+    }
+
+    pub fn is_ruby(&self) -> bool {
+        matches!(self.inner, Test::Ruby { .. })
+    }
+
+    pub fn is_script(&self) -> bool {
+        matches!(self.inner, Test::Script(_))
+    }
+
+    pub fn is_downstream(&self) -> bool {
+        matches!(self.inner, Test::Downstream(_))
+    }
+
+    pub fn is_package_contents(&self) -> bool {
+        matches!(self.inner, Test::PackageContents { .. })
+    }
+}
+
+impl From<Test> for PyTest {
+    fn from(test: Test) -> Self {
+        PyTest { inner: test }
+    }
+}
+
+impl Deref for PyTest {
+    type Target = Test;
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl Display for PyTest {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.inner)
+    }
+}
 
 #[pyclass(str)]
 #[derive(Clone, Default, Serialize, Deserialize)]
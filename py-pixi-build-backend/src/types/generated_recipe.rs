@@ -142,6 +142,7 @@ impl PyGeneratedRecipe {
             recipe,
             metadata_input_globs,
             build_input_globs,
+            purls: None,
         }
     }
 }
@@ -1,6 +1,14 @@
 from pathlib import Path
 from typing import Any
-from pixi_build_backend.types.intermediate_recipe import IntermediateRecipe, Python
+from pixi_build_backend.types.intermediate_recipe import (
+    Cache,
+    IntermediateRecipe,
+    Output,
+    Package,
+    Python,
+    Script,
+    Test,
+)
 from pixi_build_backend.types.item import ItemPackageDependency
 
 
@@ -37,6 +45,10 @@ def test_we_can_create_python() -> None:
     py = Python(["entry-point=module:function"])
 
     assert py.entry_points == ["entry-point = module:function"]
+    assert py.site_packages_path is None
+
+    py.site_packages_path = "lib/python3.13t/site-packages"
+    assert py.site_packages_path == "lib/python3.13t/site-packages"
 
 
 def test_package_types() -> None:
@@ -54,3 +66,49 @@ def test_package_types_conditional() -> None:
 
     assert package.concrete is None
     assert package.template
+
+
+def test_recipe_tests() -> None:
+    recipe = IntermediateRecipe()
+    recipe.add_test(Test.python(["boltons"]))
+    recipe.add_test(Test.script(Script(["boltons --help"]), [ItemPackageDependency("pytest")]))
+    recipe.add_test(Test.package_contents(files=["bin/boltons"]))
+
+    tests = recipe.tests
+    assert len(tests) == 3
+    assert tests[0].is_python()
+    assert tests[1].is_script()
+    assert tests[2].is_package_contents()
+
+    roundtrip = IntermediateRecipe.from_yaml(recipe.to_yaml())
+    assert [str(test) for test in roundtrip.tests] == [str(test) for test in tests]
+
+
+def test_multi_output_recipe() -> None:
+    recipe = IntermediateRecipe()
+    recipe.package = Package("foo", "0.1.0")
+
+    cache = Cache()
+    cache.build.script = Script("cmake --install build")
+    recipe.cache = cache
+
+    lib = Output(Package("libfoo", "0.1.0"))
+    lib.build.files = ["lib/"]
+    recipe.add_output(lib)
+
+    tools = Output(Package("foo-tools", "0.1.0"))
+    tools.build.files = ["bin/"]
+    tools.add_test(Test.script(Script("foo --help")))
+    recipe.add_output(tools)
+
+    yaml = recipe.to_yaml()
+    assert "recipe:" in yaml
+    assert "cache:" in yaml
+
+    roundtrip = IntermediateRecipe.from_yaml(yaml)
+    outputs = roundtrip.outputs
+    assert [str(output.package.name) for output in outputs] == ["libfoo", "foo-tools"]
+    assert outputs[0].build.files == ["lib/"]
+    assert len(outputs[1].tests) == 1
+    assert roundtrip.cache is not None
+    assert roundtrip.cache.build.script.content == ["cmake --install build"]